@@ -0,0 +1,548 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    path::Path,
+};
+
+use typst_ts_core::error::prelude::*;
+use typst_ts_core::hash::Fingerprint;
+use typst_ts_core::vector::{
+    flat_ir::{
+        FlatModule, FlatSvgItem, GroupRef, ItemPack, LayoutRegion, LayoutRegionNode, Module,
+        ModuleMetadata, MultiSvgDocument, Page, TransformedRef,
+    },
+    stream::{ArtifactCompression, ArtifactHeader, BytesModuleStream},
+};
+
+/// A vector artifact loaded from disk, i.e. a `.sir.in` file.
+pub struct Artifact {
+    /// Size of the artifact file in bytes.
+    pub size: usize,
+    /// The header of the artifact, `None` for a legacy artifact.
+    pub header: Option<ArtifactHeader>,
+    /// The module stored in the artifact, with metadata sections in order.
+    pub module: FlatModule,
+}
+
+impl Artifact {
+    /// Read an artifact from a file.
+    pub fn read(path: &Path) -> ZResult<Self> {
+        let data = std::fs::read(path).map_err(error_once_map!(
+            "artifact.read",
+            path: path.display()
+        ))?;
+        let stream = BytesModuleStream::from_slice(&data)?;
+
+        Ok(Self {
+            size: data.len(),
+            header: stream.header().copied(),
+            module: stream.checkout_owned(),
+        })
+    }
+
+    /// Merge the metadata sections into a document.
+    pub fn to_document(&self) -> MultiSvgDocument {
+        let mut doc = MultiSvgDocument::default();
+        doc.merge_delta(&self.module);
+        doc
+    }
+}
+
+/// Summarize the header and the metadata sections of an artifact.
+pub fn info(artifact: &Artifact) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "size: {} bytes", artifact.size);
+    match &artifact.header {
+        Some(header) => {
+            let compression = match header.compression {
+                ArtifactCompression::None => "none",
+                ArtifactCompression::Zstd => "zstd",
+            };
+            let _ = writeln!(
+                out,
+                "container: v{}, ir: v{}, compression: {compression}, payload: {} bytes",
+                header.container_version, header.ir_version, header.payload_len
+            );
+        }
+        None => {
+            let _ = writeln!(out, "container: none (legacy artifact)");
+        }
+    }
+
+    let mut sizes = ItemSizes::new();
+    let _ = writeln!(out, "sections:");
+    for section in &artifact.module.metadata {
+        let size = match section {
+            ModuleMetadata::Item(items) => sizes.section(items.0.iter().map(|(f, i)| (f, i))),
+            _ => archived_size(vec![section.clone()]),
+        };
+        let _ = writeln!(
+            out,
+            "  {}: {} entries, {size} bytes",
+            section_name(section),
+            section_len(section),
+        );
+
+        match section {
+            ModuleMetadata::BuildVersion(info) => {
+                let _ = writeln!(out, "    version: {}", info.version);
+                let _ = writeln!(out, "    compiler: {}", info.compiler);
+            }
+            ModuleMetadata::Item(items) => {
+                for (kind, (count, size)) in sizes.stats(items.0.iter().map(|(f, i)| (f, i))) {
+                    let _ = writeln!(out, "    {kind}: {count} items, {size} bytes");
+                }
+            }
+            ModuleMetadata::Layout(regions) => {
+                for (idx, region) in regions.iter().enumerate() {
+                    let (kind, nodes) = layout_nodes(region);
+                    let _ = writeln!(out, "    region {idx}, by {kind}:");
+                    for (key, node) in nodes {
+                        let pages = node.pages_meta().map_or(0, |pages| pages.len());
+                        let _ = writeln!(out, "      {key}: {pages} pages");
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Dump the item tree of a page in a layout of the document.
+///
+/// The layout and the page are indexed from 0. Items deeper than `max_depth`
+/// are elided.
+pub fn dump(
+    doc: &MultiSvgDocument,
+    layout: usize,
+    page: usize,
+    max_depth: usize,
+) -> ZResult<String> {
+    let pages = document_pages(doc, layout)?;
+    let page = pages
+        .get(page)
+        .ok_or_else(|| error_once!("artifact.dump.PageNotFound", page: page, pages: pages.len()))?;
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "page {}x{}, label {:?}",
+        page.size.x.0, page.size.y.0, page.label.label
+    );
+    dump_item(&mut out, &doc.module, &page.content, None, 0, max_depth);
+    Ok(out)
+}
+
+/// Diff two artifacts by fingerprints of items, so as to explain the size of
+/// an incremental update from `old` to `new`.
+pub fn diff(old: &MultiSvgDocument, new: &MultiSvgDocument, layout: usize) -> ZResult<String> {
+    let added: Vec<_> = new
+        .module
+        .items
+        .iter()
+        .filter(|(fingerprint, _)| !old.module.items.contains_key(fingerprint))
+        .collect();
+    let removed: Vec<_> = old
+        .module
+        .items
+        .iter()
+        .filter(|(fingerprint, _)| !new.module.items.contains_key(fingerprint))
+        .collect();
+    let shared = new.module.items.len() - added.len();
+
+    let mut sizes = ItemSizes::new();
+    let mut out = String::new();
+    let _ = writeln!(out, "items: +{} -{} ={shared}", added.len(), removed.len());
+    let _ = writeln!(out, "  added:");
+    for (kind, (count, size)) in sizes.stats(added.iter().copied()) {
+        let _ = writeln!(out, "    {kind}: {count} items, {size} bytes");
+    }
+    let _ = writeln!(out, "  removed:");
+    for (kind, (count, size)) in sizes.stats(removed.iter().copied()) {
+        let _ = writeln!(out, "    {kind}: {count} items, {size} bytes");
+    }
+    let _ = writeln!(
+        out,
+        "fonts: {} -> {}",
+        old.module.fonts.len(),
+        new.module.fonts.len()
+    );
+    let _ = writeln!(
+        out,
+        "glyphs: {} -> {}",
+        old.module.glyphs.len(),
+        new.module.glyphs.len()
+    );
+
+    // attribute the added items to the pages reaching them
+    let old_pages = document_pages(old, layout)?;
+    let new_pages = document_pages(new, layout)?;
+    let added: HashSet<_> = added
+        .into_iter()
+        .map(|(fingerprint, _)| *fingerprint)
+        .collect();
+    let _ = writeln!(out, "pages: {} -> {}", old_pages.len(), new_pages.len());
+    for (idx, page) in new_pages.iter().enumerate() {
+        if old_pages.get(idx).map(|p| p.content) == Some(page.content) {
+            let _ = writeln!(out, "  page {idx}: unchanged");
+            continue;
+        }
+
+        let mut reached = HashSet::new();
+        collect_items(&new.module, &page.content, &mut reached);
+        let reached_added: Vec<_> = reached
+            .intersection(&added)
+            .filter_map(|fingerprint| new.module.items.get_key_value(fingerprint))
+            .collect();
+        let size: usize = reached_added
+            .iter()
+            .map(|(fingerprint, item)| sizes.get(fingerprint, item))
+            .sum();
+        let _ = writeln!(
+            out,
+            "  page {idx}: changed, {} new items, {size} bytes",
+            reached_added.len()
+        );
+    }
+
+    Ok(out)
+}
+
+fn section_name(section: &ModuleMetadata) -> &'static str {
+    match section {
+        ModuleMetadata::BuildVersion(..) => "BuildVersion",
+        ModuleMetadata::SourceMappingData(..) => "SourceMappingData",
+        ModuleMetadata::PageSourceMapping(..) => "PageSourceMapping",
+        ModuleMetadata::GarbageCollection(..) => "GarbageCollection",
+        ModuleMetadata::Item(..) => "Item",
+        ModuleMetadata::Font(..) => "Font",
+        ModuleMetadata::Glyph(..) => "Glyph",
+        ModuleMetadata::Layout(..) => "Layout",
+        ModuleMetadata::Element(..) => "Element",
+    }
+}
+
+fn section_len(section: &ModuleMetadata) -> usize {
+    match section {
+        ModuleMetadata::BuildVersion(..) | ModuleMetadata::PageSourceMapping(..) => 1,
+        ModuleMetadata::SourceMappingData(v) => v.len(),
+        ModuleMetadata::GarbageCollection(v) => v.len(),
+        ModuleMetadata::Item(v) => v.0.len(),
+        ModuleMetadata::Font(v) => v.items.len(),
+        ModuleMetadata::Glyph(v) => v.items.len(),
+        ModuleMetadata::Layout(v) => v.len(),
+        ModuleMetadata::Element(v) => v.len(),
+    }
+}
+
+fn item_kind(item: &FlatSvgItem) -> &'static str {
+    match item {
+        FlatSvgItem::None => "none",
+        FlatSvgItem::Image(..) => "image",
+        FlatSvgItem::Link(..) => "link",
+        FlatSvgItem::Path(..) => "path",
+        FlatSvgItem::Text(..) => "text",
+        FlatSvgItem::Item(..) => "transformed",
+        FlatSvgItem::Group(..) => "group",
+    }
+}
+
+/// Size of the metadata sections once archived, without the header of the
+/// artifact.
+fn archived_size(metadata: Vec<ModuleMetadata>) -> usize {
+    FlatModule::new(metadata).to_archive().len()
+}
+
+/// Archived sizes of items, so that each item is archived only once however
+/// many summaries it is counted in.
+///
+/// The size of an item is the growth of an item section by the item, thus
+/// the sizes of items sum up to the size of their section, up to padding.
+struct ItemSizes {
+    /// Size of an empty item section.
+    empty: usize,
+    sizes: HashMap<Fingerprint, usize>,
+}
+
+impl ItemSizes {
+    fn new() -> Self {
+        Self {
+            empty: archived_size(vec![ModuleMetadata::Item(ItemPack(vec![]))]),
+            sizes: HashMap::new(),
+        }
+    }
+
+    fn get(&mut self, fingerprint: &Fingerprint, item: &FlatSvgItem) -> usize {
+        let empty = self.empty;
+        *self.sizes.entry(*fingerprint).or_insert_with(|| {
+            let items = ItemPack(vec![(*fingerprint, item.clone())]);
+            archived_size(vec![ModuleMetadata::Item(items)]) - empty
+        })
+    }
+
+    /// Size of an item section containing the items.
+    fn section<'a>(
+        &mut self,
+        items: impl Iterator<Item = (&'a Fingerprint, &'a FlatSvgItem)>,
+    ) -> usize {
+        let empty = self.empty;
+        empty + items.map(|(f, item)| self.get(f, item)).sum::<usize>()
+    }
+
+    /// Count and size of the items by kind.
+    fn stats<'a>(
+        &mut self,
+        items: impl Iterator<Item = (&'a Fingerprint, &'a FlatSvgItem)>,
+    ) -> BTreeMap<&'static str, (usize, usize)> {
+        let mut by_kind = BTreeMap::<_, (usize, usize)>::new();
+        for (fingerprint, item) in items {
+            let size = self.get(fingerprint, item);
+            let stat = by_kind.entry(item_kind(item)).or_default();
+            stat.0 += 1;
+            stat.1 += size;
+        }
+        by_kind
+    }
+}
+
+/// The kind of the layout region and its layouts, keyed by a readable string.
+fn layout_nodes(region: &LayoutRegion) -> (&str, Vec<(String, &LayoutRegionNode)>) {
+    match region {
+        LayoutRegion::ByScalar(v) => (
+            v.kind.as_ref(),
+            v.layouts
+                .iter()
+                .map(|(key, node)| (format!("{}={}", v.kind, key.0), node))
+                .collect(),
+        ),
+        LayoutRegion::ByStr(v) => (
+            v.kind.as_ref(),
+            v.layouts
+                .iter()
+                .map(|(key, node)| (format!("{}={}", v.kind, key), node))
+                .collect(),
+        ),
+    }
+}
+
+/// The pages of the layout in the first layout region of the document.
+fn document_pages(doc: &MultiSvgDocument, layout: usize) -> ZResult<Vec<Page>> {
+    let region = doc
+        .layouts
+        .first()
+        .ok_or_else(|| error_once!("artifact.LayoutNotFound", layout: layout, layouts: 0))?;
+    let (_, nodes) = layout_nodes(region);
+    let node = nodes.get(layout).ok_or_else(
+        || error_once!("artifact.LayoutNotFound", layout: layout, layouts: nodes.len()),
+    )?;
+
+    match node.1.pages_meta() {
+        Some(pages) => Ok(pages.to_vec()),
+        None => Err(error_once!("artifact.LayoutWithoutPages", layout: layout)),
+    }
+}
+
+fn dump_item(
+    out: &mut String,
+    module: &Module,
+    fingerprint: &Fingerprint,
+    pos: Option<String>,
+    depth: usize,
+    max_depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    let id = fingerprint.as_svg_id("p");
+    let pos = pos.map(|pos| format!(" at {pos}")).unwrap_or_default();
+
+    let Some(item) = module.get_item(fingerprint) else {
+        let _ = writeln!(out, "{indent}missing {id}{pos}");
+        return;
+    };
+
+    let detail = match item {
+        FlatSvgItem::Image(image) => format!(
+            " {}, {}x{}",
+            image.image.format, image.image.size.x, image.image.size.y
+        ),
+        FlatSvgItem::Link(link) => format!(" {:?}", link.href),
+        FlatSvgItem::Path(path) => format!(" {} bytes of path", path.d.len()),
+        FlatSvgItem::Text(text) => format!(
+            " font {}, {} glyphs, {:?}",
+            text.font.idx,
+            text.content.glyphs.len(),
+            text.content.content
+        ),
+        FlatSvgItem::Item(TransformedRef(transform, _)) => format!(" {transform:?}"),
+        FlatSvgItem::Group(GroupRef(children)) => format!(" {} children", children.len()),
+        FlatSvgItem::None => String::new(),
+    };
+    let _ = writeln!(out, "{indent}{} {id}{pos}{detail}", item_kind(item));
+
+    let children: Vec<_> = match item {
+        FlatSvgItem::Group(GroupRef(children)) => children
+            .iter()
+            .map(|(pos, child)| (Some(format!("({}, {})", pos.x.0, pos.y.0)), *child))
+            .collect(),
+        FlatSvgItem::Item(TransformedRef(_, child)) => vec![(None, *child)],
+        _ => return,
+    };
+    if depth >= max_depth {
+        let _ = writeln!(out, "{indent}  ...");
+        return;
+    }
+    for (pos, child) in children {
+        dump_item(out, module, &child, pos, depth + 1, max_depth);
+    }
+}
+
+fn collect_items(module: &Module, fingerprint: &Fingerprint, reached: &mut HashSet<Fingerprint>) {
+    if !reached.insert(*fingerprint) {
+        return;
+    }
+
+    match module.get_item(fingerprint) {
+        Some(FlatSvgItem::Group(GroupRef(children))) => {
+            for (_, child) in children.iter() {
+                collect_items(module, child, reached);
+            }
+        }
+        Some(FlatSvgItem::Item(TransformedRef(_, child))) => {
+            collect_items(module, child, reached);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use typst_ts_core::vector::{
+        flat_ir::PageLabel,
+        ir::{Axes, PathItem, Point, Scalar, Size, TransformItem},
+    };
+
+    use super::*;
+
+    fn fp(v: u128) -> Fingerprint {
+        Fingerprint::from_u128(v)
+    }
+
+    fn path(d: &str) -> FlatSvgItem {
+        FlatSvgItem::Path(PathItem {
+            d: d.into(),
+            styles: vec![],
+        })
+    }
+
+    fn group(children: &[(f32, f32, Fingerprint)]) -> FlatSvgItem {
+        let children = children.iter();
+        let children = children.map(|(x, y, child)| (Point::new(Scalar(*x), Scalar(*y)), *child));
+        FlatSvgItem::Group(GroupRef(children.collect()))
+    }
+
+    fn translate(child: Fingerprint) -> FlatSvgItem {
+        let ts = TransformItem::Translate(Arc::new(Axes::new(Scalar(1.), Scalar(1.))));
+        FlatSvgItem::Item(TransformedRef(ts, child))
+    }
+
+    fn module(items: Vec<(Fingerprint, FlatSvgItem)>, pages: &[Fingerprint]) -> FlatModule {
+        let pages = pages.iter().map(|content| Page {
+            content: *content,
+            size: Size::new(Scalar(100.), Scalar(100.)),
+            label: PageLabel::default(),
+        });
+        let layout = LayoutRegionNode::new_pages(pages.collect());
+
+        FlatModule::new(vec![
+            ModuleMetadata::Item(ItemPack(items)),
+            ModuleMetadata::Layout(Arc::new(vec![LayoutRegion::new_single(layout)])),
+        ])
+    }
+
+    /// A page of a group, containing a translated path and another path.
+    fn base_items() -> Vec<(Fingerprint, FlatSvgItem)> {
+        vec![
+            (fp(1), path("M 0 0 L 1 1")),
+            (fp(2), path("M 0 0 L 2 2")),
+            (fp(3), translate(fp(1))),
+            (fp(4), group(&[(0., 0., fp(3)), (1., 2., fp(2))])),
+        ]
+    }
+
+    fn document(module: &FlatModule) -> MultiSvgDocument {
+        let mut doc = MultiSvgDocument::default();
+        doc.merge_delta(module);
+        doc
+    }
+
+    #[test]
+    fn test_info() {
+        let artifact = Artifact {
+            size: 0,
+            header: None,
+            module: module(base_items(), &[fp(4)]),
+        };
+
+        let out = info(&artifact);
+        assert!(out.contains("container: none"), "{out}");
+        assert!(out.contains("  Item: 4 entries"), "{out}");
+        assert!(out.contains("  Layout: 1 entries"), "{out}");
+        assert!(out.contains("    group: 1 items"), "{out}");
+        assert!(out.contains("    path: 2 items"), "{out}");
+        assert!(out.contains("    transformed: 1 items"), "{out}");
+        assert!(out.contains("    region 0, by _:"), "{out}");
+        assert!(out.contains("      _=0: 1 pages"), "{out}");
+    }
+
+    #[test]
+    fn test_dump_depth() {
+        let doc = document(&module(base_items(), &[fp(4)]));
+        let kinds = |max_depth| {
+            let out = dump(&doc, 0, 0, max_depth).unwrap();
+            let lines = out.lines().skip(1);
+            lines
+                .map(|line| line.split(' ').find(|w| !w.is_empty()).unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(kinds(0), ["group", "..."]);
+        assert_eq!(kinds(1), ["group", "transformed", "...", "path"]);
+        assert_eq!(kinds(2), ["group", "transformed", "path", "path"]);
+
+        let out = dump(&doc, 0, 0, 2).unwrap();
+        assert!(out.contains("\n  path p"), "{out}");
+        assert!(out.contains(" at (1, 2) "), "{out}");
+
+        assert!(dump(&doc, 0, 1, 2).is_err());
+        assert!(dump(&doc, 1, 0, 2).is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = document(&module(base_items(), &[fp(4), fp(3)]));
+
+        // the second path of the first page is replaced
+        let mut items = base_items();
+        items.retain(|(f, _)| *f != fp(2) && *f != fp(4));
+        items.push((fp(5), path("M 0 0 L 3 3")));
+        items.push((fp(6), group(&[(0., 0., fp(3)), (1., 2., fp(5))])));
+        let new = document(&module(items, &[fp(6), fp(3)]));
+
+        let out = diff(&old, &new, 0).unwrap();
+        assert!(out.contains("items: +2 -2 =2"), "{out}");
+        assert!(out.contains("  added:\n    group: 1 items"), "{out}");
+        assert!(out.contains("    path: 1 items"), "{out}");
+        assert!(out.contains("pages: 2 -> 2"), "{out}");
+        assert!(out.contains("  page 0: changed, 2 new items"), "{out}");
+        assert!(out.contains("  page 1: unchanged"), "{out}");
+
+        let out = diff(&old, &old, 0).unwrap();
+        assert!(out.contains("items: +0 -0 =4"), "{out}");
+        assert!(out.contains("  page 0: unchanged"), "{out}");
+        assert!(!out.contains(": changed"), "{out}");
+    }
+}
@@ -1,20 +1,30 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use typst::doc::Document;
 use typst_ts_compiler::{
     service::{CompileDriver, CompileExporter, Compiler, DynamicLayoutCompiler, WatchDriver},
+    vfs::archive::ArchiveFormat,
     TypstSystemWorld,
 };
 use typst_ts_core::{config::CompileOpts, exporter_builtins::GroupExporter, path::PathClean};
 
 use crate::{
-    font::EMBEDDED_FONT,
+    font::{read_font_config, EMBEDDED_FONT},
     tracing::TraceGuard,
     utils::{self, UnwrapOrExit},
     CompileArgs, CompileOnceArgs,
 };
 
+/// Get the path to the archive if the workspace is packed in an archive.
+pub fn workspace_archive(args: &CompileOnceArgs) -> Option<PathBuf> {
+    let workspace = Path::new(args.workspace.as_str());
+    (ArchiveFormat::from_path(workspace).is_some() && workspace.is_file())
+        .then(|| utils::make_absolute(workspace).clean())
+}
+
 pub fn create_driver(args: CompileOnceArgs) -> CompileDriver {
+    let archive = workspace_archive(&args);
+
     let workspace_dir = Path::new(args.workspace.as_str()).clean();
     let entry_file_path = Path::new(args.entry.as_str()).clean();
 
@@ -27,6 +37,9 @@ pub fn create_driver(args: CompileOnceArgs) -> CompileDriver {
 
     let entry_file_path = if entry_file_path.is_absolute() {
         entry_file_path
+    } else if archive.is_some() {
+        // the files in archive are mounted under the path of the archive.
+        workspace_dir.join(entry_file_path)
     } else {
         let cwd = std::env::current_dir().unwrap_or_exit();
         cwd.join(entry_file_path)
@@ -47,6 +60,14 @@ pub fn create_driver(args: CompileOnceArgs) -> CompileDriver {
         root_dir: workspace_dir.clone(),
         font_paths: args.font.paths.clone(),
         with_embedded_fonts: EMBEDDED_FONT.to_owned(),
+        glyph_pack_paths: args.font.glyph_packs.clone(),
+        font_config: read_font_config(&args.font),
+        lazy_fonts: args.font.lazy,
+        // the files in archive are mounted under the path of the archive.
+        workspace_archive: archive,
+        sandbox: args.sandbox,
+        sandbox_read_only_roots: args.sandbox_roots.clone(),
+        sandbox_max_file_size: args.sandbox_max_file_size,
         ..CompileOpts::default()
     })
     .unwrap_or_exit();
@@ -66,6 +87,15 @@ pub fn compile_export(args: CompileArgs, exporter: GroupExporter<Document>) -> !
         .exit()
     }
 
+    let archive = workspace_archive(&args.compile);
+    if archive.is_some() && args.watch {
+        clap::Error::raw(
+            clap::error::ErrorKind::ArgumentConflict,
+            "cannot watch a workspace packed in an archive\n",
+        )
+        .exit()
+    }
+
     let driver = create_driver(args.compile.clone());
 
     let _trace_guard = {
@@ -84,6 +114,8 @@ pub fn compile_export(args: CompileArgs, exporter: GroupExporter<Document>) -> !
     let output_dir = {
         // If output is specified, use it.
         let dir = (!args.compile.output.is_empty()).then(|| Path::new(&args.compile.output));
+        // Or, use the directory containing the archive.
+        let dir = dir.or_else(|| archive.as_ref().and_then(|a| a.parent()));
         // Otherwise, use the parent directory of the entry file.
         let dir = dir.unwrap_or_else(|| {
             driver
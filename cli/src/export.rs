@@ -3,8 +3,12 @@ use std::path::{Path, PathBuf};
 use typst_ts_core::{
     exporter_builtins::{FsPathExporter, GroupExporter},
     program_meta::REPORT_BUG_MESSAGE,
+    AsWritable,
+};
+use typst_ts_svg_exporter::{
+    transform::{InvertColors, PrefixIds, RoundPrecision, StripDataAttributes, TransformPipeline},
+    DefaultExportFeature, ExportFeature, SvgExportFeature,
 };
-use typst_ts_svg_exporter::DefaultExportFeature;
 
 use crate::CompileArgs;
 
@@ -14,12 +18,18 @@ type GroupDocExporter = GroupExporter<typst::doc::Document>;
 /// be
 pub static AVAILABLE_FORMATS: &[(/* format name */ &str, /* feature hint */ &str)] = &[
     ("ast", REPORT_BUG_MESSAGE),
+    ("meta-json", "serde-json"),
     ("nothing", REPORT_BUG_MESSAGE),
     ("pdf", "pdf"),
     ("svg", "svg"),
+    ("svg.gz", "svg"),
+    ("svg-pages", "svg"),
     ("svg_html", "svg"),
+    ("svg_html.br", "svg"),
     ("sir", "svg"),
     ("vector", "svg"),
+    ("vector-json", "serde-json"),
+    ("vector-rmp", "serde-rmp"),
 ];
 
 /// Hint the user that the given format is not enable or not available.
@@ -50,10 +60,60 @@ fn exit_by_unknown_format(f: &str) -> ! {
     }
 }
 
+/// Parse the post-processing transforms of the svg formats.
+fn prepare_svg_transforms(specs: &[String]) -> TransformPipeline {
+    let mut transforms = TransformPipeline::new();
+    for spec in specs {
+        let (name, arg) = match spec.split_once('=') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (spec.as_str(), None),
+        };
+        match (name, arg) {
+            ("prefix-ids", Some(prefix)) => transforms.push(PrefixIds::new(prefix)),
+            ("round", Some(digits)) => match digits.parse() {
+                Ok(digits) => transforms.push(RoundPrecision::new(digits)),
+                Err(_) => exit_by_invalid_svg_transform(spec),
+            },
+            ("invert-colors", None) => transforms.push(InvertColors),
+            ("strip-data", None) => transforms.push(StripDataAttributes::default()),
+            _ => exit_by_invalid_svg_transform(spec),
+        }
+    }
+    transforms
+}
+
+/// Hint the user that the given svg transform is invalid.
+/// Then exit the program.
+fn exit_by_invalid_svg_transform(spec: &str) -> ! {
+    clap::Error::raw(
+        clap::error::ErrorKind::InvalidValue,
+        format!(
+            "invalid svg transform: {spec:?}, possible values: `prefix-ids=<prefix>`, `round=<digits>`, `invert-colors`, and `strip-data`\n"
+        ),
+    )
+    .exit()
+}
+
 /// With the given arguments, prepare exporters for the compilation.
-fn prepare_exporters_impl(out: PathBuf, mut formats: Vec<String>) -> GroupDocExporter {
+#[cfg_attr(not(feature = "svg"), allow(unused_variables))]
+fn prepare_exporters_impl(
+    out: PathBuf,
+    mut formats: Vec<String>,
+    svg_transforms: TransformPipeline,
+) -> GroupDocExporter {
     let mut doc: ExporterVec<Doc> = vec![];
 
+    // the transforms of the feature sets are applied before the given ones
+    #[cfg(feature = "svg")]
+    let (svg, svg_html) = {
+        let html_transforms = DefaultExportFeature::transforms().then(svg_transforms.clone());
+        let svg_transforms = SvgExportFeature::transforms().then(svg_transforms);
+        (
+            move || WithSvg::new(svg_transforms.clone()),
+            move || WithSvgHtml::new(html_transforms.clone()),
+        )
+    };
+
     /// connect export flow from $x to $y
     #[allow(unused_macros)]
     macro_rules! sink_flow {
@@ -66,6 +126,13 @@ fn prepare_exporters_impl(out: PathBuf, mut formats: Vec<String>) -> GroupDocExp
 
     /// write $exporters as $exporter to path `$output_dir @@ $extension`
     macro_rules! sink_path {
+        (@new $exporter:expr => $ser:ty as $exporters:ident, $output_dir:ident @@ $extension:literal) => {{
+            let output_path = $output_dir.with_extension($extension);
+            $exporters.push(Box::new(FsPathExporter::<$ser, _>::new(
+                output_path,
+                $exporter,
+            )));
+        }};
         ($exporter:ty as $ser:ty as $exporters:ident, $output_dir:ident @@ $extension:literal) => {{
             let output_path = $output_dir.with_extension($extension);
             $exporters.push(Box::new(FsPathExporter::<$ser, _>::new(
@@ -83,16 +150,28 @@ fn prepare_exporters_impl(out: PathBuf, mut formats: Vec<String>) -> GroupDocExp
         formats.iter().map(String::as_str).for_each(|f| match f {
             "nothing"     => (),
             "ast"         => sink_path!(WithAst as _ as doc, out @@ "ast.ansi.text"),
+            #[cfg(feature = "serde-json")]
+            "meta-json"   => sink_path!(WithMetaJson as _ as doc, out @@ "meta.json"),
             #[cfg(feature = "pdf")]
             "pdf"         => sink_path!(WithPdf as _ as doc, out @@ "pdf"),
             #[cfg(feature = "svg")]
-            "svg"         => sink_path!(WithSvg as _ as doc, out @@ "artifact.svg"),
+            "svg"         => sink_path!(@new svg() => AsWritable as doc, out @@ "artifact.svg"),
+            #[cfg(feature = "svg")]
+            "svg.gz"      => sink_path!(@new WithSvgGz::new(svg()) => AsWritable as doc, out @@ "artifact.svg.gz"),
+            #[cfg(feature = "svg")]
+            "svg-pages"   => doc.push(Box::new(SvgPagesExporter::new(out.clone(), svg()))),
             #[cfg(feature = "svg")]
-            "svg_html"         => sink_path!(WithSvgHtml as _ as doc, out @@ "artifact.svg.html"),
+            "svg_html"         => sink_path!(@new svg_html() => AsWritable as doc, out @@ "artifact.svg.html"),
+            #[cfg(feature = "svg")]
+            "svg_html.br" => sink_path!(@new WithSvgHtmlBr::new(svg_html()) => AsWritable as doc, out @@ "artifact.svg.html.br"),
             #[cfg(feature = "svg")]
             "sir"         => sink_path!(WithSIR as _ as doc, out @@ "artifact.sir.in"),
             #[cfg(feature = "svg")]
             "vector"      => sink_path!(WithSIR as _ as doc, out @@ "artifact.sir.in"),
+            #[cfg(all(feature = "svg", feature = "serde-json"))]
+            "vector-json" => sink_path!(WithSIRJson as _ as doc, out @@ "artifact.sir.json"),
+            #[cfg(all(feature = "svg", feature = "serde-rmp"))]
+            "vector-rmp"  => sink_path!(WithSIRRmp as _ as doc, out @@ "artifact.sir.rmp"),
             _             => exit_by_unknown_format(f),
         });
     }
@@ -101,12 +180,26 @@ fn prepare_exporters_impl(out: PathBuf, mut formats: Vec<String>) -> GroupDocExp
     type Doc = typst::doc::Document;
 
     type WithAst = typst_ts_ast_exporter::AstExporter;
-    // type WithJson<T> = typst_ts_serde_exporter::JsonExporter<T>;
+    #[cfg(feature = "serde-json")]
+    type WithJson<T> = typst_ts_serde_exporter::JsonExporter<T>;
+    #[cfg(feature = "serde-json")]
+    type WithMetaJson = typst_ts_core::introspection::DocumentIntrospectionExporter<
+        WithJson<typst_ts_core::DocumentIntrospection>,
+    >;
     type WithPdf = typst_ts_pdf_exporter::PdfDocExporter;
-    // type WithRmp<T> = typst_ts_serde_exporter::RmpExporter<T>;
+    #[cfg(feature = "serde-rmp")]
+    type WithRmp<T> = typst_ts_serde_exporter::RmpExporter<T>;
     type WithSvg = typst_ts_svg_exporter::PureSvgExporter;
     type WithSvgHtml = typst_ts_svg_exporter::SvgExporter<DefaultExportFeature>;
+    type WithSvgGz = typst_ts_svg_exporter::GzipExporter<WithSvg>;
+    type WithSvgHtmlBr = typst_ts_svg_exporter::BrotliExporter<WithSvgHtml>;
     type WithSIR = typst_ts_svg_exporter::SvgModuleExporter;
+    #[cfg(all(feature = "svg", feature = "serde-json"))]
+    type WithSIRJson = typst_ts_svg_exporter::FlatModuleExporter<WithJson<FlatModule>>;
+    #[cfg(all(feature = "svg", feature = "serde-rmp"))]
+    type WithSIRRmp = typst_ts_svg_exporter::FlatModuleExporter<WithRmp<FlatModule>>;
+    #[cfg(feature = "svg")]
+    type FlatModule = typst_ts_svg_exporter::FlatModule;
 
     type ExporterVec<T> = Vec<Box<dyn typst_ts_core::Exporter<T> + Send>>;
 }
@@ -131,5 +224,70 @@ pub fn prepare_exporters(args: &CompileArgs, entry_file: &Path) -> GroupDocExpor
         formats
     };
 
-    prepare_exporters_impl(output_dir, formats)
+    let svg_transforms = prepare_svg_transforms(&args.svg_transform);
+
+    prepare_exporters_impl(output_dir, formats, svg_transforms)
+}
+
+/// Write each page of the document into a standalone SVG file
+/// `{name}-{n}.svg`, with an index file `{name}.pages.json` listing the page
+/// files and their sizes, so that pages can be loaded lazily.
+#[cfg(feature = "svg")]
+pub struct SvgPagesExporter {
+    /// The output path with the file name of the entry file, e.g. `main.typ`.
+    out: PathBuf,
+    exporter: typst_ts_svg_exporter::PureSvgExporter,
+}
+
+#[cfg(feature = "svg")]
+impl SvgPagesExporter {
+    pub fn new(out: PathBuf, exporter: typst_ts_svg_exporter::PureSvgExporter) -> Self {
+        Self { out, exporter }
+    }
+}
+
+/// The index of SVG pages, in json format.
+#[cfg(feature = "svg")]
+#[derive(serde::Serialize)]
+struct SvgPagesIndex {
+    pages: Vec<SvgPageEntry>,
+}
+
+#[cfg(feature = "svg")]
+#[derive(serde::Serialize)]
+struct SvgPageEntry {
+    /// The file name of the page, relative to the index file.
+    path: String,
+    /// Page width in pt
+    width: u32,
+    /// Page height in pt
+    height: u32,
+}
+
+#[cfg(feature = "svg")]
+impl typst_ts_core::Exporter<typst::doc::Document> for SvgPagesExporter {
+    fn export(
+        &self,
+        _world: &dyn typst::World,
+        output: std::sync::Arc<typst::doc::Document>,
+    ) -> typst::diag::SourceResult<()> {
+        use typst_ts_core::exporter_utils::map_err;
+
+        let stem = self.out.file_stem().unwrap_or_default().to_string_lossy();
+
+        let mut index = SvgPagesIndex { pages: vec![] };
+        for (idx, page) in self.exporter.render_pages(&output).enumerate() {
+            let path = format!("{stem}-{}.svg", idx + 1);
+            std::fs::write(self.out.with_file_name(&path), page.svg).map_err(map_err)?;
+            index.pages.push(SvgPageEntry {
+                path,
+                width: page.width,
+                height: page.height,
+            });
+        }
+
+        let index = serde_json::to_string(&index).map_err(map_err)?;
+        std::fs::write(self.out.with_extension("pages.json"), index).map_err(map_err)?;
+        Ok(())
+    }
 }
@@ -1,5 +1,9 @@
 use std::borrow::Cow;
 
+use typst_ts_core::config::FontConfig;
+
+use crate::{utils::UnwrapOrExit, FontArgs};
+
 #[cfg(feature = "embedded-fonts")]
 pub static EMBEDDED_FONT: &[Cow<'_, [u8]>] = &[
     // Embed default fonts.
@@ -39,3 +43,13 @@ pub static EMBEDDED_FONT: &[Cow<'_, [u8]>] = &[
 
 #[cfg(not(feature = "embedded-fonts"))]
 pub static EMBEDDED_FONT: &[Cow<'_, [u8]>] = &[];
+
+/// Read the font config specified by the arguments.
+pub fn read_font_config(args: &FontArgs) -> FontConfig {
+    let Some(path) = &args.config else {
+        return FontConfig::default();
+    };
+
+    let data = std::fs::read(path).unwrap_or_exit();
+    serde_json::from_slice(&data).unwrap_or_exit()
+}
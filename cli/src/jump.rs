@@ -0,0 +1,64 @@
+use serde_json::{json, Value};
+use typst::diag::{bail, StrResult};
+use typst_ts_core::vector::{
+    ir::{Point, Rect, Scalar},
+    span::{DocumentPosition, SourceLocation, SourceSpanMapping},
+};
+use typst_ts_core::TypstFileId;
+
+use crate::JumpArgs;
+
+/// Perform the jump described by the arguments and format the result.
+pub fn jump(
+    mapping: &SourceSpanMapping,
+    cursor_file: Option<TypstFileId>,
+    args: &JumpArgs,
+) -> StrResult<Value> {
+    if let (Some(id), Some(offset)) = (cursor_file, args.offset) {
+        let positions = mapping.jump_from_cursor(id, offset);
+        return Ok(Value::Array(
+            positions.iter().map(position_to_json).collect(),
+        ));
+    }
+
+    if let (Some(page), Some(x), Some(y)) = (args.page, args.x, args.y) {
+        if page == 0 {
+            bail!("page number starts from 1");
+        }
+        let point = Point::new(Scalar(x), Scalar(y));
+        let location = mapping.jump_from_click(page - 1, point);
+        return Ok(location.as_ref().map_or(Value::Null, location_to_json));
+    }
+
+    bail!("expected either \"--file\" and \"--offset\", or \"--page\", \"--x\" and \"--y\"")
+}
+
+/// Convert a rectangle on the page to json, in unit of pt.
+pub fn rect_to_json(rect: &Rect) -> Value {
+    json!({
+        "x": rect.lo.x.0,
+        "y": rect.lo.y.0,
+        "width": rect.hi.x.0 - rect.lo.x.0,
+        "height": rect.hi.y.0 - rect.lo.y.0,
+    })
+}
+
+/// Convert a document position to json.
+/// The page number in json starts from 1.
+pub fn position_to_json(pos: &DocumentPosition) -> Value {
+    json!({
+        "page": pos.page + 1,
+        "rect": rect_to_json(&pos.rect),
+    })
+}
+
+/// Convert a source location to json.
+pub fn location_to_json(loc: &SourceLocation) -> Value {
+    json!({
+        "package": loc.id.package().map(|p| p.to_string()),
+        "path": loc.id.vpath().as_rootless_path().to_string_lossy(),
+        "range": [loc.range.start, loc.range.end],
+        "line": loc.line,
+        "column": loc.column,
+    })
+}
@@ -1,8 +1,12 @@
 use std::path::PathBuf;
 
+pub mod artifact;
 pub mod compile;
 pub mod export;
 pub mod font;
+pub mod jump;
+#[cfg(feature = "preview")]
+pub mod preview;
 pub mod query;
 pub mod query_repl;
 pub mod tracing;
@@ -44,6 +48,12 @@ pub enum Subcommands {
 
     QueryRepl(QueryReplArgs),
 
+    /// Jump between source locations and document positions
+    Jump(JumpArgs),
+
+    #[clap(about = "Preview the document in browser with live reload.")]
+    Preview(PreviewArgs),
+
     #[clap(about = "Generate shell completion script.")]
     Completion(CompletionArgs),
 
@@ -55,6 +65,79 @@ pub enum Subcommands {
 
     #[clap(subcommand)]
     Package(PackageSubCommands),
+
+    #[clap(subcommand)]
+    Artifact(ArtifactSubCommands),
+}
+
+/// Jump between source locations and document positions
+///
+/// Examples:
+/// ```shell
+/// # find the positions rendered from byte offset 120 of main.typ
+/// jump --entry main.typ --file main.typ --offset 120
+/// # find the source location of the item at (100pt, 200pt) on page 1
+/// jump --entry main.typ --page 1 --x 100 --y 200
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct JumpArgs {
+    /// compile arguments before jump.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// Source file to jump from, relative to the workspace.
+    #[clap(long, requires = "offset", conflicts_with = "page")]
+    pub file: Option<String>,
+
+    /// Byte offset in the source file to jump from.
+    #[clap(long, requires = "file")]
+    pub offset: Option<usize>,
+
+    /// Page number to jump from, starting from 1.
+    #[clap(long, requires_all = ["x", "y"])]
+    pub page: Option<usize>,
+
+    /// Horizontal position on the page in pt.
+    #[clap(long, requires = "page")]
+    pub x: Option<f32>,
+
+    /// Vertical position on the page in pt.
+    #[clap(long, requires = "page")]
+    pub y: Option<f32>,
+}
+
+/// Preview the document in browser with live reload.
+///
+/// Examples:
+/// ```shell
+/// # preview main.typ at http://127.0.0.1:23625, with the renderer built in
+/// # ./packages
+/// preview --entry main.typ
+/// # load the published renderer from a CDN instead
+/// preview --entry main.typ --renderer-url https://cdn.jsdelivr.net/npm/@myriaddreamin
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct PreviewArgs {
+    /// compile arguments before preview.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// Listen address of the preview server.
+    #[clap(long, default_value = "127.0.0.1:23625")]
+    pub host: String,
+
+    /// Directory of the typst.ts packages, from which the preview server
+    /// serves the built renderer, i.e. `typst.ts/dist/esm/main.bundle.js`
+    /// and `renderer/pkg/typst_ts_renderer_bg.wasm`.
+    #[clap(long, value_name = "DIR", default_value = "packages")]
+    pub renderer_dir: PathBuf,
+
+    /// Base url of the published typst.ts packages, e.g.
+    /// `https://cdn.jsdelivr.net/npm/@myriaddreamin`. If set, the browser
+    /// loads the renderer of the same version as this program from it,
+    /// instead of the one in `--renderer-dir`.
+    #[clap(long, value_name = "URL")]
+    pub renderer_url: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -69,6 +152,12 @@ pub enum FontSubCommands {
     List(ListFontsArgs),
     /// Measure fonts and generate a profile file for compiler
     Measure(MeasureFontsArgs),
+    /// Extract the glyph packs used by a document
+    Pack(PackFontsArgs),
+    /// Report unresolved families, missing glyphs and fallback fonts of a document
+    Report(ReportFontsArgs),
+    /// Report the glyphs, pages and output bytes of each font face used by a document
+    Usage(UsageFontsArgs),
 }
 
 #[derive(Debug, Subcommand)]
@@ -89,6 +178,22 @@ pub enum PackageSubCommands {
     Doc(GenPackagesDocArgs),
 }
 
+#[derive(Debug, Subcommand)]
+#[clap(
+    about = "Commands about vector artifacts, i.e. `.sir.in` files.",
+    after_help = "",
+    next_display_order = None
+)]
+#[allow(clippy::large_enum_variant)]
+pub enum ArtifactSubCommands {
+    /// Print the header and the metadata sections of an artifact
+    Info(ArtifactInfoArgs),
+    /// Dump the item tree of a page in an artifact
+    Dump(ArtifactDumpArgs),
+    /// Diff two artifacts by fingerprints of items
+    Diff(ArtifactDiffArgs),
+}
+
 /// Shared arguments for font related commands
 #[derive(Default, Debug, Clone, Parser)]
 pub struct FontArgs {
@@ -100,6 +205,21 @@ pub struct FontArgs {
         action = ArgAction::Append,
     )]
     pub paths: Vec<PathBuf>,
+
+    /// Add glyph packs extracted by `font pack` as font sources
+    #[clap(long = "glyph-pack", value_name = "FILE", action = ArgAction::Append)]
+    pub glyph_packs: Vec<PathBuf>,
+
+    /// Path to font config in json format, which specifies family aliases,
+    /// per-script fallbacks and excluded families
+    #[clap(long = "font-config", value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Index the searched fonts without parsing their coverage, which speeds
+    /// up startup, but these fonts are not used as fallback until they are
+    /// loaded
+    #[clap(long = "lazy-fonts")]
+    pub lazy: bool,
 }
 
 #[derive(Default, Debug, Clone, Parser)]
@@ -110,16 +230,33 @@ pub struct CompileOnceArgs {
     pub font: FontArgs,
 
     /// Path to typst workspace.
+    /// It can also be a `.zip`, `.tar`, or `.tar.gz` archive, which is read
+    /// without unpacking to disk.
     #[clap(long, short, default_value = ".")]
     pub workspace: String,
 
     /// Entry file.
+    /// If the workspace is an archive, the path is relative to the root of
+    /// the archive.
     #[clap(long, short, required = true)]
     pub entry: String,
 
     /// Output to directory, default in the same directory as the entry file.
     #[clap(long, short, default_value = "")]
     pub output: String,
+
+    /// Restrict the files read by the compiler to the workspace, the package
+    /// directories and the directories given by `--sandbox-root`.
+    #[clap(long)]
+    pub sandbox: bool,
+
+    /// Extra directory allowed to be read in the sandbox.
+    #[clap(long = "sandbox-root", value_name = "DIR", requires = "sandbox")]
+    pub sandbox_roots: Vec<PathBuf>,
+
+    /// Maximum size of a file read in the sandbox, in bytes.
+    #[clap(long, value_name = "BYTES", requires = "sandbox")]
+    pub sandbox_max_file_size: Option<u64>,
 }
 
 #[derive(Default, Debug, Clone, Parser)]
@@ -139,10 +276,23 @@ pub struct CompileArgs {
     #[clap(long)]
     pub dynamic_layout: bool,
 
-    /// Output formats, possible values: `ast`, `pdf`, `svg`, and, `svg_html`.
+    /// Output formats, possible values: `ast`, `meta-json`, `pdf`, `svg`,
+    /// `svg.gz`, `svg-pages`, `svg_html`, `svg_html.br`, `vector`,
+    /// `vector-json`, and `vector-rmp`.
     #[clap(long)]
     pub format: Vec<String>,
 
+    /// Post-processing transforms of the svg formats, applied in order after
+    /// the minification. Possible values: `prefix-ids=<prefix>`,
+    /// `round=<digits>`, `invert-colors`, and `strip-data`.
+    #[clap(long = "svg-transform", value_name = "TRANSFORM")]
+    pub svg_transform: Vec<String>,
+
+    /// Warn about unresolved families, missing glyphs and fallback fonts
+    /// after each compilation, see also `font report`.
+    #[clap(long)]
+    pub font_report: bool,
+
     /// Enable tracing.
     /// Possible usage: --trace=verbosity={0..3}
     ///   where verbosity: {0..3} -> {warning, info, debug, trace}
@@ -214,6 +364,77 @@ pub struct MeasureFontsArgs {
     /// Exclude system font paths
     #[arg(long)]
     pub no_system_fonts: bool,
+
+    /// Update the existing profile, only measuring the font files that are
+    /// changed since the last measurement. Otherwise, the profile is rebuilt
+    /// from scratch
+    #[arg(long)]
+    pub update: bool,
+}
+
+/// Extract the glyph packs used by a document, so that the document can be
+/// compiled without the full font files.
+///
+/// Examples:
+/// ```shell
+/// # extract the glyph packs used by main.typ
+/// font pack --entry main.typ --pack main.glyph-pack.json
+/// # compile main.typ with the glyph packs only
+/// compile --entry main.typ --glyph-pack main.glyph-pack.json
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct PackFontsArgs {
+    /// compile arguments before extracting.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// Path to output glyph pack file
+    #[arg(long = "pack", value_name = "FILE", required = true)]
+    pub pack: PathBuf,
+}
+
+/// Report the font resolution of a document, i.e. the requested families that
+/// are not found, the codepoints rendered as `.notdef`, and the fonts used as
+/// fallback.
+///
+/// Examples:
+/// ```shell
+/// # report the font resolution of main.typ
+/// font report --entry main.typ
+/// # report in json format
+/// font report --entry main.typ --json
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct ReportFontsArgs {
+    /// compile arguments before reporting.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// Print the report in json format
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Report the usage of each font face in a document, i.e. the number of
+/// distinct glyphs used, the number of pages used on, and the bytes
+/// contributed to the PDF and vector output.
+///
+/// Examples:
+/// ```shell
+/// # report the font usage of main.typ
+/// font usage --entry main.typ
+/// # report in json format
+/// font usage --entry main.typ --json
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct UsageFontsArgs {
+    /// compile arguments before reporting.
+    #[clap(flatten)]
+    pub compile: CompileOnceArgs,
+
+    /// Print the usage in json format
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(ValueEnum, Debug, Clone)]
@@ -267,3 +488,67 @@ pub struct GenPackagesDocArgs {
     #[clap(long)]
     pub dynamic_layout: bool,
 }
+
+/// Print the header and the metadata sections of an artifact, i.e. the counts
+/// and archived sizes of items, fonts, glyphs and layouts.
+///
+/// Examples:
+/// ```shell
+/// artifact info main.artifact.sir.in
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct ArtifactInfoArgs {
+    /// Path to the artifact
+    #[clap(value_name = "FILE")]
+    pub artifact: PathBuf,
+}
+
+/// Dump the item tree of a page in an artifact.
+///
+/// Examples:
+/// ```shell
+/// # dump the first page of the first layout
+/// artifact dump main.artifact.sir.in
+/// # dump the third page of the second layout, eliding items deeper than 4
+/// artifact dump main.multi.sir.in --layout 1 --page 3 --max-depth 4
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct ArtifactDumpArgs {
+    /// Path to the artifact
+    #[clap(value_name = "FILE")]
+    pub artifact: PathBuf,
+
+    /// Index of the layout to dump, starting from 0
+    #[arg(long, default_value_t = 0)]
+    pub layout: usize,
+
+    /// Page number to dump, starting from 1
+    #[arg(long, default_value_t = 1)]
+    pub page: usize,
+
+    /// Elide the items deeper than the depth
+    #[arg(long, default_value_t = usize::MAX)]
+    pub max_depth: usize,
+}
+
+/// Diff two artifacts by fingerprints of items, which explains the size of an
+/// incremental update between them.
+///
+/// Examples:
+/// ```shell
+/// artifact diff old.artifact.sir.in new.artifact.sir.in
+/// ```
+#[derive(Debug, Clone, Parser)]
+pub struct ArtifactDiffArgs {
+    /// Path to the old artifact
+    #[clap(value_name = "OLD")]
+    pub old: PathBuf,
+
+    /// Path to the new artifact
+    #[clap(value_name = "NEW")]
+    pub new: PathBuf,
+
+    /// Index of the layout to compare pages, starting from 0
+    #[arg(long, default_value_t = 0)]
+    pub layout: usize,
+}
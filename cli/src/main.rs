@@ -8,14 +8,16 @@ use clap::{Args, Command, FromArgMatches};
 use typst::{doc::Document, font::FontVariant, World};
 
 use typst_ts_cli::{
-    compile::compile_export,
-    font::EMBEDDED_FONT,
+    compile::{compile_export, create_driver},
+    font::{read_font_config, EMBEDDED_FONT},
     query::serialize,
     utils::{self, make_absolute, UnwrapOrExit},
     version::intercept_version,
-    CompileArgs, CompileOnceArgs, CompletionArgs, EnvKey, FontSubCommands, GenPackagesDocArgs,
-    LinkPackagesArgs, ListFontsArgs, ListPackagesArgs, MeasureFontsArgs, Opts, PackageSubCommands,
-    QueryArgs, QueryReplArgs, Subcommands,
+    ArtifactDiffArgs, ArtifactDumpArgs, ArtifactInfoArgs, ArtifactSubCommands, CompileArgs,
+    CompileOnceArgs, CompletionArgs, EnvKey, FontSubCommands, GenPackagesDocArgs, JumpArgs,
+    LinkPackagesArgs, ListFontsArgs, ListPackagesArgs, MeasureFontsArgs, Opts, PackFontsArgs,
+    PackageSubCommands, PreviewArgs, QueryArgs, QueryReplArgs, ReportFontsArgs, Subcommands,
+    UsageFontsArgs,
 };
 use typst_ts_compiler::TypstSystemWorld;
 use typst_ts_core::exporter_builtins::GroupExporter;
@@ -58,6 +60,8 @@ fn main() {
         Some(Subcommands::Compile(args)) => compile(args),
         Some(Subcommands::Query(args)) => query(args),
         Some(Subcommands::QueryRepl(args)) => query_repl(args),
+        Some(Subcommands::Jump(args)) => jump(args),
+        Some(Subcommands::Preview(args)) => preview(args),
         Some(Subcommands::Completion(args)) => generate_completion(args),
         Some(Subcommands::Env(args)) => match args.key {
             EnvKey::Features => {
@@ -67,6 +71,9 @@ fn main() {
         Some(Subcommands::Font(font_sub)) => match font_sub {
             FontSubCommands::List(args) => list_fonts(args),
             FontSubCommands::Measure(args) => measure_fonts(args),
+            FontSubCommands::Pack(args) => pack_fonts(args),
+            FontSubCommands::Report(args) => report_fonts(args),
+            FontSubCommands::Usage(args) => font_usage(args),
         },
         Some(Subcommands::Package(pkg_sub)) => match pkg_sub {
             PackageSubCommands::List(args) => list_packages(args),
@@ -74,6 +81,11 @@ fn main() {
             PackageSubCommands::Unlink(args) => link_packages(args, true),
             PackageSubCommands::Doc(args) => doc_packages(args),
         },
+        Some(Subcommands::Artifact(artifact_sub)) => match artifact_sub {
+            ArtifactSubCommands::Info(args) => artifact_info(args),
+            ArtifactSubCommands::Dump(args) => artifact_dump(args),
+            ArtifactSubCommands::Diff(args) => artifact_diff(args),
+        },
         None => help_sub_command(),
     };
 
@@ -85,7 +97,26 @@ fn main() {
 
 fn compile(args: CompileArgs) -> ! {
     let entry_file_path = Path::new(args.compile.entry.as_str()).clean();
-    let exporter = typst_ts_cli::export::prepare_exporters(&args, &entry_file_path);
+    // The artifacts of an archive workspace are placed next to the archive.
+    let entry_file_path = match typst_ts_cli::compile::workspace_archive(&args.compile) {
+        Some(archive) if entry_file_path.is_relative() => archive
+            .parent()
+            .expect("archive has no parent")
+            .join(entry_file_path),
+        _ => entry_file_path,
+    };
+    let mut exporter = typst_ts_cli::export::prepare_exporters(&args, &entry_file_path);
+
+    if args.font_report {
+        exporter.push_front(Box::new(|world: &dyn World, output: Arc<Document>| {
+            use typst_ts_core::font::FontReport;
+
+            for warning in FontReport::new(world, &output).warnings() {
+                log::warn!("{warning}");
+            }
+            Ok(())
+        }));
+    }
 
     compile_export(args, exporter)
 }
@@ -123,6 +154,58 @@ fn query_repl(args: QueryReplArgs) -> ! {
     exit(0)
 }
 
+/// Execute a jump command.
+fn jump(args: JumpArgs) -> ! {
+    use typst::syntax::VirtualPath;
+    use typst_ts_core::{vector::span::SourceSpanMapping, TypstFileId};
+
+    let compile_args = CompileArgs {
+        compile: args.compile.clone(),
+        format: vec!["nothing".to_owned()],
+        ..Default::default()
+    };
+
+    let cursor_file = args.file.as_ref().map(|file| {
+        let root = make_absolute(Path::new(&args.compile.workspace)).clean();
+        let file = make_absolute(Path::new(file)).clean();
+        let vpath = VirtualPath::within_root(&file, &root).unwrap_or_else(|| {
+            clap::Error::raw(
+                clap::error::ErrorKind::InvalidValue,
+                "file path must be in workspace directory\n",
+            )
+            .exit()
+        });
+        TypstFileId::new(None, vpath)
+    });
+
+    let mut exporter = GroupExporter::<Document>::new(vec![]);
+
+    exporter.push_front(Box::new(move |world: &dyn World, output: Arc<Document>| {
+        let mapping = SourceSpanMapping::new(world, &output);
+        let data = typst_ts_cli::jump::jump(&mapping, cursor_file, &args).map_err(map_err)?;
+        let serialized = serialize(&data, "json").map_err(map_err)?;
+        println!("{serialized}");
+        Ok(())
+    }));
+
+    compile_export(compile_args, exporter)
+}
+
+#[cfg(feature = "preview")]
+fn preview(args: PreviewArgs) -> ! {
+    typst_ts_cli::preview::preview_main(args)
+}
+
+#[cfg(not(feature = "preview"))]
+fn preview(_args: PreviewArgs) -> ! {
+    clap::Error::raw(
+        clap::error::ErrorKind::InvalidValue,
+        r#"feature not enabled for command "preview": suggested feature "preview". To figure out enabled features, use command "$program env features"
+"#,
+    )
+    .exit()
+}
+
 fn generate_completion(CompletionArgs { shell }: CompletionArgs) -> ! {
     clap_complete::generate(
         shell,
@@ -140,8 +223,10 @@ fn list_fonts(command: ListFontsArgs) -> ! {
 
     let world = TypstSystemWorld::new(CompileOpts {
         root_dir: root_path,
+        font_config: read_font_config(&command.font),
         font_paths: command.font.paths,
         with_embedded_fonts: EMBEDDED_FONT.to_owned(),
+        glyph_pack_paths: command.font.glyph_packs,
         ..CompileOpts::default()
     })
     .unwrap_or_exit();
@@ -164,40 +249,134 @@ fn list_fonts(command: ListFontsArgs) -> ! {
 }
 
 fn measure_fonts(args: MeasureFontsArgs) -> ! {
-    let mut root_path = PathBuf::new();
-    // todo: should cover default workspace path
-    root_path.push("-");
+    use typst_ts_compiler::font::system::SystemFontSearcher;
 
-    let mut font_profile_paths = vec![];
-    if args.output.exists() {
-        font_profile_paths.push(args.output.clone());
+    let mut searcher = SystemFontSearcher::new();
+    searcher.set_can_profile(true);
+
+    // the unchanged font files in the existing profile are not measured again
+    if args.update && args.output.exists() {
+        searcher.add_profile_by_path(&args.output).unwrap_or_exit();
     }
 
-    let world = TypstSystemWorld::new(CompileOpts {
-        root_dir: root_path,
-        font_profile_paths,
-        font_paths: args.font.paths,
-        font_profile_cache_path: args.output.clone(),
-        no_system_fonts: args.no_system_fonts,
-        ..CompileOpts::default()
-    })
-    .unwrap_or_exit();
+    for path in args.font.paths {
+        if path.is_dir() {
+            searcher.search_dir(&path);
+        } else {
+            searcher.search_file(&path);
+        }
+    }
+    searcher.search_vanilla();
+    if !args.no_system_fonts {
+        searcher.search_system();
+    }
 
-    // create directory for args.output
-    if let Some(output) = args.output.parent() {
-        std::fs::create_dir_all(output).unwrap();
+    if args.update {
+        searcher.retain_profile_items();
     }
+    searcher.write_profile(&args.output).unwrap_or_exit();
 
-    let profile = serde_json::to_vec(world.font_resolver.profile()).unwrap();
+    exit(0)
+}
 
-    // gzip
-    use flate2::write::GzEncoder;
-    use flate2::Compression;
-    use std::io::Write;
+/// Execute a font pack command.
+fn pack_fonts(args: PackFontsArgs) -> ! {
+    use typst_ts_core::font::FontGlyphPack;
 
-    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-    encoder.write_all(&profile).unwrap();
-    std::fs::write(args.output, encoder.finish().unwrap()).unwrap();
+    let compile_args = CompileArgs {
+        compile: args.compile.clone(),
+        format: vec!["nothing".to_owned()],
+        ..Default::default()
+    };
+
+    let mut exporter = GroupExporter::<Document>::new(vec![]);
+
+    exporter.push_front(Box::new(
+        move |_world: &dyn World, output: Arc<Document>| {
+            let packs = FontGlyphPack::from_document(&output);
+            let data = serde_json::to_vec(&packs).map_err(map_err)?;
+            std::fs::write(&args.pack, data).map_err(map_err)?;
+            Ok(())
+        },
+    ));
+
+    compile_export(compile_args, exporter)
+}
+
+/// Execute a font report command.
+fn report_fonts(args: ReportFontsArgs) -> ! {
+    use typst_ts_core::font::FontReport;
+
+    let compile_args = CompileArgs {
+        compile: args.compile.clone(),
+        format: vec!["nothing".to_owned()],
+        ..Default::default()
+    };
+
+    let mut exporter = GroupExporter::<Document>::new(vec![]);
+
+    exporter.push_front(Box::new(move |world: &dyn World, output: Arc<Document>| {
+        let report = FontReport::new(world, &output);
+        if args.json {
+            let serialized = serialize(&report, "json").map_err(map_err)?;
+            println!("{serialized}");
+        } else if report.is_empty() {
+            println!("all requested fonts are resolved and all glyphs are covered");
+        } else {
+            for warning in report.warnings() {
+                println!("{warning}");
+            }
+        }
+        Ok(())
+    }));
+
+    compile_export(compile_args, exporter)
+}
+
+/// Execute a font usage command.
+fn font_usage(args: UsageFontsArgs) -> ! {
+    use typst_ts_compiler::service::{Compiler, DiagObserver};
+    use typst_ts_core::font::FontUsage;
+
+    let mut driver = create_driver(args.compile.clone());
+    let Some(doc) = driver.with_compile_diag::<false, _>(|driver| driver.compile()) else {
+        utils::logical_exit(false)
+    };
+
+    // the fonts loaded by the compiler, which are reported even if unused.
+    let loaded_fonts = driver
+        .world
+        .font_resolver
+        .loaded_fonts()
+        .map(|(_, font)| font);
+    let usage = FontUsage::new(&doc, loaded_fonts);
+
+    if args.json {
+        let serialized = serde_json::to_string_pretty(&usage).unwrap_or_exit();
+        println!("{serialized}");
+        exit(0)
+    }
+
+    for face in &usage.faces {
+        let FontVariant {
+            style,
+            weight,
+            stretch,
+        } = face.variant;
+        let pdf_bytes = match face.pdf_bytes {
+            Some(bytes) => bytes.to_string(),
+            None => "-".to_owned(),
+        };
+        println!("{}", face.family);
+        println!(
+            "- Style: {style:?}, Weight: {weight:?}, Stretch: {stretch:?}, Index: {}",
+            face.index
+        );
+        println!(
+            "- Glyphs: {}, Pages: {}, Vector Bytes: {}, PDF Bytes: {}",
+            face.glyphs, face.pages, face.vector_bytes, pdf_bytes
+        );
+    }
 
     exit(0)
 }
@@ -330,3 +509,39 @@ fn doc_packages(args: GenPackagesDocArgs) -> ! {
 
     compile(compile_args)
 }
+
+fn artifact_info(args: ArtifactInfoArgs) -> ! {
+    use typst_ts_cli::artifact::{info, Artifact};
+
+    let artifact = Artifact::read(&args.artifact).unwrap_or_exit();
+    print!("{}", info(&artifact));
+    exit(0)
+}
+
+fn artifact_dump(args: ArtifactDumpArgs) -> ! {
+    use typst_ts_cli::artifact::{dump, Artifact};
+
+    if args.page == 0 {
+        clap::Error::raw(
+            clap::error::ErrorKind::InvalidValue,
+            "page number starts from 1\n",
+        )
+        .exit()
+    }
+
+    let doc = Artifact::read(&args.artifact)
+        .unwrap_or_exit()
+        .to_document();
+    let dumped = dump(&doc, args.layout, args.page - 1, args.max_depth).unwrap_or_exit();
+    print!("{dumped}");
+    exit(0)
+}
+
+fn artifact_diff(args: ArtifactDiffArgs) -> ! {
+    use typst_ts_cli::artifact::{diff, Artifact};
+
+    let old = Artifact::read(&args.old).unwrap_or_exit().to_document();
+    let new = Artifact::read(&args.new).unwrap_or_exit().to_document();
+    print!("{}", diff(&old, &new, args.layout).unwrap_or_exit());
+    exit(0)
+}
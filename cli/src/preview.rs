@@ -0,0 +1,343 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+};
+
+use futures::{SinkExt, StreamExt};
+use log::{error, info};
+use serde_json::Value;
+use tokio::sync::broadcast;
+use typst::{
+    doc::Document,
+    syntax::{FileId, VirtualPath},
+    World,
+};
+use typst_ts_compiler::service::{CompileExporter, Compiler, WatchDriver};
+use typst_ts_core::{
+    exporter_builtins::GroupExporter,
+    vector::{
+        ir::{Point, Scalar},
+        span::SourceSpanMapping,
+        LowerBuilder,
+    },
+};
+use typst_ts_svg_exporter::IncrSvgDocServer;
+use warp::{ws::Message, Filter};
+
+use crate::{
+    compile::create_driver,
+    jump::{location_to_json, position_to_json},
+    utils, PreviewArgs,
+};
+
+/// The preview page, which renders the document by the typst.ts renderer.
+///
+/// The page receives the packed deltas from the preview server and merges
+/// them into its renderer session, so that only the changed parts of the
+/// document are transferred and patched into the DOM.
+const PREVIEW_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8" />
+<title>Typst Preview</title>
+<script type="module" src="{bundle}"></script>
+</head>
+<body>
+<div id="typst-app"><p>compiling...</p></div>
+<script type="module">
+const { createTypstRenderer, patchRoot } = window.TypstRenderModule;
+const renderer = createTypstRenderer();
+await renderer.init({ getModule: () => '{wasm}' });
+
+const app = document.getElementById('typst-app');
+renderer.runWithSession(session => new Promise(() => {
+  const ws = new WebSocket(`ws://${location.host}/ws`);
+  ws.binaryType = 'arraybuffer';
+  ws.addEventListener('message', event => {
+    // the message is either `new,<snapshot>` or `diff-v1,<delta>`.
+    const message = new Uint8Array(event.data);
+    const comma = message.indexOf(44);
+    const kind = new TextDecoder().decode(message.subarray(0, comma));
+    const data = message.subarray(comma + 1);
+    if (kind === 'new') {
+      // there is no completed compilation yet.
+      if (!data.length) {
+        return;
+      }
+      session.manipulateData({ action: 'reset', data });
+    } else if (kind === 'diff-v1') {
+      session.manipulateData({ action: 'merge', data });
+    } else {
+      return;
+    }
+
+    const patch = session.renderSvgDiff({});
+    const prevDoc = app.querySelector('svg');
+    if (!prevDoc) {
+      app.innerHTML = patch;
+      return;
+    }
+    const elem = document.createElement('div');
+    elem.innerHTML = patch;
+    patchRoot(prevDoc, elem.firstElementChild);
+  });
+  // reload the page if the preview server is restarted.
+  ws.addEventListener('close', () => setTimeout(() => location.reload(), 1000));
+}));
+</script>
+</body>
+</html>
+"#;
+
+/// The path of the renderer script, relative to the renderer directory.
+const RENDERER_BUNDLE: &str = "typst.ts/dist/esm/main.bundle.js";
+/// The path of the renderer wasm module, relative to the renderer directory.
+const RENDERER_WASM: &str = "renderer/pkg/typst_ts_renderer_bg.wasm";
+
+/// Create the preview page, which loads the renderer served by the preview
+/// server.
+pub fn preview_page() -> String {
+    PREVIEW_PAGE
+        .replace("{bundle}", "/renderer/main.bundle.js")
+        .replace("{wasm}", "/renderer/typst_ts_renderer_bg.wasm")
+}
+
+/// Create the preview page, which loads the published renderer of the same
+/// version from `renderer_url`.
+pub fn preview_page_from_url(renderer_url: &str) -> String {
+    let version = env!("CARGO_PKG_VERSION");
+    let renderer_url = renderer_url.trim_end_matches('/');
+    PREVIEW_PAGE
+        .replace(
+            "{bundle}",
+            &format!("{renderer_url}/typst.ts@{version}/dist/esm/main.bundle.js"),
+        )
+        .replace(
+            "{wasm}",
+            &format!("{renderer_url}/typst-ts-renderer@{version}/pkg/typst_ts_renderer_bg.wasm"),
+        )
+}
+
+/// Shared state between the compiler and the preview server.
+pub struct PreviewState {
+    /// The incremental state of the vector document.
+    incr_server: Mutex<IncrSvgDocServer>,
+    /// The source mapping of the latest document.
+    mapping: RwLock<Option<Arc<SourceSpanMapping>>>,
+    /// Broadcasts the packed deltas to the connected clients.
+    tx: broadcast::Sender<Arc<Vec<u8>>>,
+}
+
+impl Default for PreviewState {
+    fn default() -> Self {
+        let mut incr_server = IncrSvgDocServer::default();
+        incr_server.set_should_attach_debug_info(true);
+        incr_server.set_should_attach_elements(true);
+
+        Self {
+            incr_server: Mutex::new(incr_server),
+            mapping: RwLock::new(None),
+            tx: broadcast::channel(16).0,
+        }
+    }
+}
+
+impl PreviewState {
+    /// Update the state with a newly compiled document, then notify the
+    /// connected clients.
+    pub fn update(&self, world: &dyn World, output: Arc<Document>) {
+        // the pages are lowered once for both the mapping and the delta.
+        let mut lower_builder = LowerBuilder::new(&output);
+        let pages = output.pages.iter().map(|p| lower_builder.lower(p));
+        let pages = pages.collect::<Vec<_>>();
+
+        let mapping = SourceSpanMapping::from_lowered(world, &pages);
+        *self.mapping.write().unwrap() = Some(Arc::new(mapping));
+
+        // the delta is sent while holding the lock, see `subscribe`.
+        let mut incr_server = self.incr_server.lock().unwrap();
+        let delta = incr_server.pack_delta_lowered(output, pages);
+        // it is fine that there is no subscriber at the moment.
+        let _ = self.tx.send(Arc::new(delta));
+    }
+
+    /// Get the source mapping of the latest document.
+    pub fn mapping(&self) -> Option<Arc<SourceSpanMapping>> {
+        self.mapping.read().unwrap().clone()
+    }
+
+    /// Subscribe to the updates, with a snapshot of the current document if
+    /// any.
+    pub fn subscribe(&self) -> (Option<Vec<u8>>, broadcast::Receiver<Arc<Vec<u8>>>) {
+        // the incremental server is locked while a delta is packed and sent,
+        // so the snapshot contains exactly the deltas sent before subscribing,
+        // and the receiver gets exactly the deltas after the snapshot.
+        let mut incr_server = self.incr_server.lock().unwrap();
+        let rx = self.tx.subscribe();
+        (incr_server.pack_current(), rx)
+    }
+}
+
+/// Serve the preview page, the renderer in `renderer_dir` and the websocket
+/// endpoint.
+pub async fn serve(
+    addr: SocketAddr,
+    state: Arc<PreviewState>,
+    page: String,
+    renderer_dir: PathBuf,
+) {
+    let with_state = {
+        let state = state.clone();
+        warp::any().map(move || state.clone())
+    };
+
+    let index = warp::path::end().map(move || warp::reply::html(page.clone()));
+
+    let renderer = warp::path("renderer").and(
+        (warp::path("main.bundle.js").and(warp::fs::file(renderer_dir.join(RENDERER_BUNDLE))))
+            .or(warp::path("typst_ts_renderer_bg.wasm")
+                .and(warp::fs::file(renderer_dir.join(RENDERER_WASM)))),
+    );
+
+    // `GET /jump/document?file=main.typ&offset=120`
+    let jump_document = warp::path!("jump" / "document")
+        .and(warp::query::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .map(|query: HashMap<String, String>, state: Arc<PreviewState>| {
+            let file = query.get("file").map(|f| VirtualPath::new(f.as_str()));
+            let offset = query.get("offset").and_then(|o| o.parse().ok());
+            let (Some(file), Some(offset), Some(mapping)) = (file, offset, state.mapping()) else {
+                return warp::reply::json(&Value::Null);
+            };
+
+            let positions = mapping.jump_from_cursor(FileId::new(None, file), offset);
+            let positions = positions.iter().map(position_to_json).collect::<Vec<_>>();
+            warp::reply::json(&positions)
+        });
+
+    // `GET /jump/source?page=1&x=100&y=200`
+    let jump_source = warp::path!("jump" / "source")
+        .and(warp::query::<HashMap<String, String>>())
+        .and(with_state.clone())
+        .map(|query: HashMap<String, String>, state: Arc<PreviewState>| {
+            let get = |k: &str| query.get(k).and_then(|v| v.parse::<f32>().ok());
+            let (Some(page), Some(x), Some(y), Some(mapping)) =
+                (get("page"), get("x"), get("y"), state.mapping())
+            else {
+                return warp::reply::json(&Value::Null);
+            };
+            if page < 1. {
+                return warp::reply::json(&Value::Null);
+            }
+
+            let point = Point::new(Scalar(x), Scalar(y));
+            let location = mapping.jump_from_click(page as usize - 1, point);
+            warp::reply::json(&location.as_ref().map_or(Value::Null, location_to_json))
+        });
+
+    let ws = warp::path("ws").and(warp::ws()).and(with_state).map(
+        |ws: warp::ws::Ws, state: Arc<PreviewState>| {
+            ws.on_upgrade(move |socket| serve_ws(socket, state))
+        },
+    );
+
+    info!("preview server listening on http://{addr}");
+    let routes = index.or(renderer).or(jump_document).or(jump_source).or(ws);
+    warp::serve(routes).run(addr).await
+}
+
+async fn serve_ws(socket: warp::ws::WebSocket, state: Arc<PreviewState>) {
+    let (mut tx, mut rx) = socket.split();
+    let (current, mut updates) = state.subscribe();
+
+    // Sends a placeholder when there is no completed compilation, which
+    // tells the client the connection is established.
+    let current = current.unwrap_or_else(|| b"new,".to_vec());
+    if let Err(err) = tx.send(Message::binary(current)).await {
+        error!("failed to send to preview client: {err}");
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            delta = updates.recv() => {
+                let delta = match delta {
+                    Ok(delta) => delta,
+                    // the client is too slow, it will be refreshed by the next delta.
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                };
+                if let Err(err) = tx.send(Message::binary(delta.as_slice())).await {
+                    error!("failed to send to preview client: {err}");
+                    return;
+                }
+            }
+            msg = rx.next() => {
+                // the client is disconnected.
+                if !matches!(msg, Some(Ok(_))) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Compile the document in watch mode and serve it to the browser.
+pub fn preview_main(args: PreviewArgs) -> ! {
+    let addr: SocketAddr = args.host.parse().unwrap_or_else(|err| {
+        clap::Error::raw(
+            clap::error::ErrorKind::InvalidValue,
+            format!("invalid host {:?}: {err}\n", args.host),
+        )
+        .exit()
+    });
+
+    let page = match &args.renderer_url {
+        Some(renderer_url) => preview_page_from_url(renderer_url),
+        None => {
+            check_renderer_dir(&args.renderer_dir);
+            preview_page()
+        }
+    };
+
+    let state = Arc::new(PreviewState::default());
+
+    let driver = create_driver(args.compile.clone());
+    let watch_root = driver.world().root.as_ref().to_owned();
+
+    let mut exporter = GroupExporter::<Document>::new(vec![]);
+    {
+        let state = state.clone();
+        exporter.push(Box::new(move |world: &dyn World, output: Arc<Document>| {
+            state.update(world, output);
+            Ok(())
+        }));
+    }
+
+    let driver = CompileExporter::new(driver).with_exporter(exporter);
+    let mut driver = WatchDriver::new(driver, watch_root).with_enable(true);
+
+    utils::async_continue(async move {
+        tokio::spawn(serve(addr, state, page, args.renderer_dir));
+        utils::logical_exit(driver.compile().await);
+    })
+}
+
+/// Exit if the renderer is not built in `renderer_dir`.
+fn check_renderer_dir(renderer_dir: &Path) {
+    for file in [RENDERER_BUNDLE, RENDERER_WASM] {
+        let path = renderer_dir.join(file);
+        if !path.is_file() {
+            clap::Error::raw(
+                clap::error::ErrorKind::InvalidValue,
+                format!(
+                    "renderer is not found at {path:?}, build the typst.ts packages \
+                     or load a published renderer by --renderer-url\n"
+                ),
+            )
+            .exit()
+        }
+    }
+}
@@ -1,20 +1,27 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs::File,
+    io::Write,
     path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
 use memmap2::Mmap;
+use rayon::prelude::*;
+use serde::Deserialize;
 use sha2::{Digest, Sha256};
-use typst::font::{FontBook, FontInfo};
+use typst::font::{Coverage, FontBook, FontInfo};
+use unicode_script::{Script, UnicodeScript};
 use walkdir::WalkDir;
 
 use typst_ts_core::{
     build_info,
+    config::FontConfig,
+    error::prelude::*,
     font::{
-        get_font_coverage_hash, BufferFontLoader, FontInfoItem, FontProfile, FontProfileItem,
-        FontResolverImpl, LazyBufferFontLoader, PartialFontBook,
+        font_info_without_coverage, get_font_coverage_hash, BufferFontLoader, FontGlyphPack,
+        FontInfoItem, FontProfile, FontProfileItem, FontResolverImpl, LazyBufferFontLoader,
+        PartialFontBook, PartialInfoFontLoader, SharedFontLoader,
     },
     Bytes, FontSlot,
 };
@@ -31,55 +38,130 @@ fn is_font_file_by_name(path: &Path) -> bool {
     )
 }
 
+fn hash_font_file(file: &mut File) -> Option<String> {
+    let mut hasher = Sha256::new();
+    std::io::copy(file, &mut hasher).ok()?;
+    let hash = hasher.finalize();
+
+    Some(format!("sha256:{}", hex::encode(hash)))
+}
+
 #[derive(Default)]
 struct FontProfileRebuilder {
-    path_items: HashMap<PathBuf, FontProfileItem>,
+    /// The items loaded from existing profiles, which are reused if the
+    /// corresponding font files are unchanged.
+    cached_items: HashMap<PathBuf, FontProfileItem>,
+    /// The indices of the items searched in this run.
+    path_items: HashMap<PathBuf, usize>,
     pub profile: FontProfile,
     can_profile: bool,
 }
 
+/// The fonts in a file found by [`FontProfileRebuilder::search_file`].
+enum SearchedFile {
+    /// The file is already searched, whose item is at the index.
+    Searched(usize),
+    /// The file is profiled, either measured or reused from cache.
+    Profiled(FontProfileItem),
+    /// The file is indexed lazily, see [`SystemFontSearcher::set_lazy`].
+    Lazy(Vec<(u32, FontInfo)>),
+}
+
 impl FontProfileRebuilder {
     /// Index the fonts in the file at the given path.
-    pub fn search_file(&mut self, path: impl AsRef<Path>) -> Option<&FontProfileItem> {
-        let path = path.as_ref().canonicalize().unwrap();
-        if let Some(item) = self.path_items.get(&path) {
-            return Some(item);
+    ///
+    /// It never changes the rebuilder, so that files can be searched in
+    /// parallel.
+    fn search_file(&self, path: &Path, lazy: bool) -> Option<(PathBuf, SearchedFile)> {
+        let path = path.canonicalize().ok()?;
+        if let Some(idx) = self.path_items.get(&path) {
+            return Some((path, SearchedFile::Searched(*idx)));
         }
 
-        if let Ok(mut file) = File::open(&path) {
-            let hash = if self.can_profile {
-                let mut hasher = Sha256::new();
-                let _bytes_written = std::io::copy(&mut file, &mut hasher).unwrap();
-                let hash = hasher.finalize();
+        let searched = match self.reuse_file(&path) {
+            Some(profile_item) => SearchedFile::Profiled(profile_item),
+            None if lazy => SearchedFile::Lazy(Self::index_file(&path)?),
+            None => SearchedFile::Profiled(self.measure_file(&path)?),
+        };
 
-                format!("sha256:{}", hex::encode(hash))
-            } else {
-                "".to_owned()
-            };
+        Some((path, searched))
+    }
+
+    /// Add a profiled item, returning the index of the item.
+    fn add_item(&mut self, path: PathBuf, profile_item: FontProfileItem) -> usize {
+        // the file may be searched twice in a batch
+        if let Some(idx) = self.path_items.get(&path) {
+            return *idx;
+        }
+
+        let idx = self.profile.items.len();
+        self.path_items.insert(path, idx);
+        self.profile.items.push(profile_item);
+        idx
+    }
+
+    /// Reuse the cached item if the file is unchanged, which is checked by
+    /// the mtime, or by the hash if the file is touched.
+    fn reuse_file(&self, path: &Path) -> Option<FontProfileItem> {
+        let mut profile_item = self.cached_items.get(path)?.clone();
 
-            let mut profile_item = FontProfileItem::new("path", hash);
-            profile_item.set_path(path.to_str().unwrap().to_owned());
-            profile_item.set_mtime(file.metadata().unwrap().modified().unwrap());
-
-            // println!("searched font: {:?}", path);
-
-            if let Ok(mmap) = unsafe { Mmap::map(&file) } {
-                for (i, info) in FontInfo::iter(&mmap).enumerate() {
-                    let coverage_hash = get_font_coverage_hash(&info.coverage);
-                    let mut ff = FontInfoItem::new(info);
-                    ff.set_coverage_hash(coverage_hash);
-                    if i != 0 {
-                        ff.set_index(i as u32);
-                    }
-                    profile_item.add_info(ff);
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        if profile_item.mtime_is_exact(mtime) {
+            return Some(profile_item);
+        }
+
+        if !self.can_profile || profile_item.hash().is_empty() {
+            return None;
+        }
+        let hash = hash_font_file(&mut File::open(path).ok()?)?;
+        if hash != profile_item.hash() {
+            return None;
+        }
+
+        profile_item.set_mtime(mtime);
+        Some(profile_item)
+    }
+
+    /// Measure the fonts in the file.
+    fn measure_file(&self, path: &Path) -> Option<FontProfileItem> {
+        let mut file = File::open(path).ok()?;
+        let hash = if self.can_profile {
+            hash_font_file(&mut file)?
+        } else {
+            "".to_owned()
+        };
+
+        let mut profile_item = FontProfileItem::new("path", hash);
+        profile_item.set_path(path.to_str()?.to_owned());
+        if let Ok(mtime) = file.metadata().and_then(|m| m.modified()) {
+            profile_item.set_mtime(mtime);
+        }
+
+        if let Ok(mmap) = unsafe { Mmap::map(&file) } {
+            for (i, info) in FontInfo::iter(&mmap).enumerate() {
+                let coverage_hash = get_font_coverage_hash(&info.coverage);
+                let mut ff = FontInfoItem::new(info);
+                ff.set_coverage_hash(coverage_hash);
+                if i != 0 {
+                    ff.set_index(i as u32);
                 }
+                profile_item.add_info(ff);
             }
-
-            self.profile.items.push(profile_item);
-            return self.profile.items.last();
         }
 
-        None
+        Some(profile_item)
+    }
+
+    /// Index the fonts in the file without computing their coverage.
+    fn index_file(path: &Path) -> Option<Vec<(u32, FontInfo)>> {
+        let file = File::open(path).ok()?;
+        let mmap = unsafe { Mmap::map(&file) }.ok()?;
+
+        let count = ttf_parser::fonts_in_collection(&mmap).unwrap_or(1);
+        let infos = (0..count)
+            .filter_map(|idx| Some((idx, font_info_without_coverage(&mmap, idx)?)))
+            .collect();
+        Some(infos)
     }
 }
 
@@ -88,6 +170,10 @@ pub struct SystemFontSearcher {
     pub book: FontBook,
     pub fonts: Vec<FontSlot>,
     profile_rebuilder: FontProfileRebuilder,
+    lazy: bool,
+    /// The indices of the fonts indexed lazily, whose information is parsed
+    /// in full when they are loaded.
+    lazy_fonts: HashSet<usize>,
 }
 
 impl SystemFontSearcher {
@@ -101,6 +187,8 @@ impl SystemFontSearcher {
             book: FontBook::new(),
             fonts: vec![],
             profile_rebuilder,
+            lazy: false,
+            lazy_fonts: HashSet::new(),
         }
     }
 
@@ -108,38 +196,122 @@ impl SystemFontSearcher {
         self.profile_rebuilder.can_profile = can_profile;
     }
 
-    pub fn add_profile_by_path(&mut self, profile_path: &Path) {
-        // let begin = std::time::Instant::now();
-        // profile_path is in format of json.gz
-        let profile_file = File::open(profile_path).unwrap();
-        let profile_gunzip = flate2::read::GzDecoder::new(profile_file);
-        let profile: FontProfile = serde_json::from_reader(profile_gunzip).unwrap();
+    /// Index the fonts without their coverage if they are not in the loaded
+    /// profiles, which makes searching much faster.
+    ///
+    /// The information of a lazily indexed font, including its coverage, is
+    /// parsed when the font is loaded, and takes effect in the font book
+    /// after [`FontResolverImpl::rebuild`]. Until then, the font is only
+    /// selected by family, and not used as fallback for the characters not
+    /// covered by the requested families. Besides, the lazily indexed fonts
+    /// are not recorded in the profile.
+    pub fn set_lazy(&mut self, lazy: bool) {
+        self.lazy = lazy;
+    }
 
-        if self.profile_rebuilder.profile.version != profile.version
-            || self.profile_rebuilder.profile.build_info != profile.build_info
-        {
-            return;
+    /// Load a profile in format of `json.gz`, whose items are reused when
+    /// searching the unchanged font files.
+    ///
+    /// The items that cannot be parsed are skipped, so that they are measured
+    /// again.
+    pub fn add_profile_by_path(&mut self, profile_path: &Path) -> ZResult<()> {
+        #[derive(Deserialize)]
+        struct RawFontProfile {
+            version: String,
+            build_info: String,
+            items: Vec<serde_json::Value>,
         }
 
-        for item in profile.items {
-            let path = match item.path() {
-                Some(path) => path,
-                None => continue,
-            };
-            let path = PathBuf::from(path);
+        let profile_file = File::open(profile_path).map_err(error_once_map_string!(
+            "SystemFontSearcher.open_profile",
+            path: profile_path.display()
+        ))?;
+        let profile_gunzip = flate2::read::GzDecoder::new(std::io::BufReader::new(profile_file));
+        let profile: RawFontProfile = serde_json::from_reader(profile_gunzip).map_err(
+            error_once_map_string!("SystemFontSearcher.parse_profile", path: profile_path.display()),
+        )?;
+
+        let rebuilder = &mut self.profile_rebuilder;
+        if rebuilder.profile.version != profile.version {
+            return Err(error_once!(
+                "SystemFontSearcher.profile_version_mismatch",
+                path: profile_path.display(),
+                expected: rebuilder.profile.version,
+                found: profile.version,
+            ));
+        }
+        if rebuilder.profile.build_info != profile.build_info {
+            log::info!(
+                "font profile {:?} is built by {}, reusing the valid items",
+                profile_path,
+                profile.build_info
+            );
+        }
 
-            if let Ok(m) = std::fs::metadata(&path) {
-                let modified = m.modified().ok();
-                if !modified.map(|m| item.mtime_is_exact(m)).unwrap_or_default() {
+        for item in profile.items {
+            let item = match serde_json::from_value::<FontProfileItem>(item) {
+                Ok(item) => item,
+                Err(err) => {
+                    log::warn!("skip invalid item in font profile {profile_path:?}: {err}");
                     continue;
                 }
+            };
+            if let Some(path) = item.path() {
+                rebuilder.cached_items.insert(PathBuf::from(path), item);
             }
+        }
 
-            self.profile_rebuilder.path_items.insert(path, item.clone());
-            self.profile_rebuilder.profile.items.push(item);
+        Ok(())
+    }
+
+    /// Keep the items of the loaded profiles whose font files are not
+    /// searched, so that the written profile updates the loaded ones instead
+    /// of replacing them. The items of the removed or changed files are
+    /// dropped.
+    ///
+    /// The fonts of the kept items are not added to the font book.
+    pub fn retain_profile_items(&mut self) {
+        let rebuilder = &mut self.profile_rebuilder;
+        let mut paths = rebuilder.cached_items.keys().cloned().collect::<Vec<_>>();
+        paths.sort();
+        for path in paths {
+            if rebuilder.path_items.contains_key(&path) {
+                continue;
+            }
+            if let Some(profile_item) = rebuilder.reuse_file(&path) {
+                rebuilder.add_item(path, profile_item);
+            }
+        }
+    }
+
+    /// Write the profile of the searched fonts in format of `json.gz`.
+    ///
+    /// The profile is written to a temporary file first and then renamed, so
+    /// that a concurrent reader never sees a partially written profile.
+    pub fn write_profile(&self, profile_path: &Path) -> ZResult<()> {
+        let profile = serde_json::to_vec(&self.profile_rebuilder.profile)
+            .map_err(map_string_err("SystemFontSearcher.serialize_profile"))?;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&profile)
+            .map_err(map_string_err("SystemFontSearcher.compress_profile"))?;
+        let data = encoder
+            .finish()
+            .map_err(map_string_err("SystemFontSearcher.compress_profile"))?;
+
+        let write_err = || error_once_map_string!("SystemFontSearcher.write_profile", path: profile_path.display());
+        if let Some(dir) = profile_path.parent() {
+            std::fs::create_dir_all(dir).map_err(write_err())?;
         }
-        // let end = std::time::Instant::now();
-        // println!("profile_rebuilder init took {:?}", end - begin);
+        let mut tmp_path = profile_path.as_os_str().to_owned();
+        tmp_path.push(format!(".{}.tmp", std::process::id()));
+        let tmp_path = PathBuf::from(tmp_path);
+        std::fs::write(&tmp_path, data).map_err(write_err())?;
+        std::fs::rename(&tmp_path, profile_path).map_err(|err| {
+            let _ = std::fs::remove_file(&tmp_path);
+            write_err()(err)
+        })
     }
 
     /// Add an in-memory font.
@@ -153,6 +325,102 @@ impl SystemFontSearcher {
         }
     }
 
+    /// Add a glyph pack, which provides a subset of glyphs of a font.
+    pub fn add_glyph_pack(&mut self, pack: FontGlyphPack) {
+        self.fonts.push(pack.slot());
+        self.book.push(pack.info);
+    }
+
+    /// Apply the rules in the font config to the searched fonts.
+    pub fn apply_config(&mut self, config: &FontConfig) {
+        if config.is_empty() {
+            return;
+        }
+
+        let lower = |family: &str| family.to_lowercase();
+
+        // the aliased families are replaced by their targets
+        let excludes = config.excludes.iter().chain(config.aliases.keys());
+        let excludes = excludes.map(|f| lower(f)).collect::<HashSet<_>>();
+
+        let fonts = std::mem::take(&mut self.fonts);
+        let mut entries = fonts
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, slot)| {
+                let info = self.book.info(idx)?.clone();
+                let lazy = self.lazy_fonts.contains(&idx);
+                (!excludes.contains(&lower(&info.family))).then_some((info, slot, lazy))
+            })
+            .collect::<Vec<_>>();
+
+        for (alias, target) in &config.aliases {
+            let target = lower(target);
+
+            let mut aliased = vec![];
+            for (info, slot, lazy) in entries.iter_mut() {
+                if lower(&info.family) != target {
+                    continue;
+                }
+
+                // share the font between the target and the alias
+                let shared = Arc::new(std::mem::replace(slot, FontSlot::with_value(None)));
+                *slot = FontSlot::new_boxed(SharedFontLoader {
+                    slot: shared.clone(),
+                });
+
+                let info = FontInfo {
+                    family: alias.clone(),
+                    ..info.clone()
+                };
+                let slot = FontSlot::new_boxed(SharedFontLoader { slot: shared });
+                aliased.push((info, slot, *lazy));
+            }
+
+            if aliased.is_empty() {
+                log::warn!("font alias {alias:?} refers to a missing family {target:?}");
+            }
+            entries.extend(aliased);
+        }
+
+        for (script_name, families) in &config.fallbacks {
+            let Some(script) = Script::from_short_name(script_name) else {
+                log::warn!("unknown script {script_name:?} in font fallbacks");
+                continue;
+            };
+            let families = families.iter().map(|f| lower(f)).collect::<Vec<_>>();
+            let position =
+                |info: &FontInfo| families.iter().position(|f| *f == lower(&info.family));
+
+            // only the listed fonts are used for the script
+            let (mut preferred, mut rest): (Vec<_>, Vec<_>) = std::mem::take(&mut entries)
+                .into_iter()
+                .partition(|(info, ..)| position(info).is_some());
+            for (info, ..) in rest.iter_mut() {
+                let coverage = info
+                    .coverage
+                    .iter()
+                    .filter(|c| char::from_u32(*c).map_or(true, |c| c.script() != script));
+                info.coverage = Coverage::from_vec(coverage.collect());
+            }
+
+            // the listed fonts are inserted in front, preferred in order
+            preferred.sort_by_key(|(info, ..)| position(info));
+            entries = preferred;
+            entries.extend(rest);
+        }
+
+        self.book = FontBook::new();
+        self.lazy_fonts.clear();
+        for (idx, (info, slot, lazy)) in entries.into_iter().enumerate() {
+            self.book.push(info);
+            self.fonts.push(slot);
+            if lazy {
+                self.lazy_fonts.insert(idx);
+            }
+        }
+    }
+
     /// Add fonts that in vanilla style.
     pub fn search_vanilla(&mut self) {
         let program_dir = std::env::current_exe().unwrap();
@@ -227,44 +495,90 @@ impl SystemFontSearcher {
             }
         };
 
-        for dir in font_paths {
-            self.search_dir(dir);
-        }
+        self.search_dirs(font_paths);
     }
 
     /// Search for all fonts in a directory recursively.
     pub fn search_dir(&mut self, path: impl AsRef<Path>) {
-        let entries = WalkDir::new(path)
-            .follow_links(true)
-            .sort_by(|a, b| a.file_name().cmp(b.file_name()))
-            .into_iter()
-            // todo: error handling
-            .filter_map(|e| e.ok());
+        self.search_dirs([path]);
+    }
 
-        for entry in entries {
-            let path = entry.path();
-            if is_font_file_by_name(path) {
-                self.search_file(path);
+    /// Search for all fonts in the directories recursively.
+    fn search_dirs(&mut self, dirs: impl IntoIterator<Item = impl AsRef<Path>>) {
+        let mut paths = vec![];
+        for dir in dirs {
+            let entries = WalkDir::new(dir)
+                .follow_links(true)
+                .sort_by(|a, b| a.file_name().cmp(b.file_name()))
+                .into_iter()
+                // todo: error handling
+                .filter_map(|e| e.ok());
+
+            for entry in entries {
+                if is_font_file_by_name(entry.path()) {
+                    paths.push(entry.into_path());
+                }
             }
         }
+
+        self.search_files(&paths);
     }
 
     /// Index the fonts in the file at the given path.
     pub fn search_file(&mut self, path: impl AsRef<Path>) {
-        let profile_item = match self.profile_rebuilder.search_file(path.as_ref()) {
-            Some(profile_item) => profile_item,
-            None => return,
-        };
+        self.search_files(&[path.as_ref().to_owned()]);
+    }
+
+    /// Index the fonts in the files, which are parsed in parallel.
+    ///
+    /// The fonts are added in order of the files, since the order decides the
+    /// priority of the fonts in the font book.
+    fn search_files(&mut self, paths: &[PathBuf]) {
+        let rebuilder = &self.profile_rebuilder;
+        let lazy = self.lazy;
+        let searched = paths
+            .par_iter()
+            .map(|path| rebuilder.search_file(path, lazy))
+            .collect::<Vec<_>>();
+
+        for (path, searched) in paths.iter().zip(searched) {
+            let Some((canonical_path, searched)) = searched else {
+                continue;
+            };
+
+            let (infos, lazy) = match searched {
+                SearchedFile::Searched(idx) => (self.profiled_infos(idx), false),
+                SearchedFile::Profiled(profile_item) => {
+                    let idx = self
+                        .profile_rebuilder
+                        .add_item(canonical_path, profile_item);
+                    (self.profiled_infos(idx), false)
+                }
+                SearchedFile::Lazy(infos) => (infos, true),
+            };
 
-        for info in profile_item.info.iter() {
-            self.book.push(info.info.clone());
-            self.fonts
-                .push(FontSlot::new_boxed(LazyBufferFontLoader::new(
-                    LazyFile::new(path.as_ref().to_owned()),
-                    info.index().unwrap_or_default(),
-                )));
+            for (index, info) in infos {
+                if lazy {
+                    self.lazy_fonts.insert(self.fonts.len());
+                }
+                self.book.push(info);
+                self.fonts
+                    .push(FontSlot::new_boxed(LazyBufferFontLoader::new(
+                        LazyFile::new(path.clone()),
+                        index,
+                    )));
+            }
         }
     }
+
+    /// Get the fonts in the profiled item.
+    fn profiled_infos(&self, idx: usize) -> Vec<(u32, FontInfo)> {
+        let profile_item = &self.profile_rebuilder.profile.items[idx];
+        let infos = profile_item.info.iter();
+        infos
+            .map(|info| (info.index().unwrap_or_default(), info.info.clone()))
+            .collect()
+    }
 }
 
 impl Default for SystemFontSearcher {
@@ -275,11 +589,160 @@ impl Default for SystemFontSearcher {
 
 impl From<SystemFontSearcher> for FontResolverImpl {
     fn from(searcher: SystemFontSearcher) -> Self {
+        let partial_book = Arc::new(Mutex::new(PartialFontBook::default()));
+
+        // the lazily indexed fonts report their full information on loading
+        let mut fonts = searcher.fonts;
+        for &idx in &searcher.lazy_fonts {
+            let slot = std::mem::replace(&mut fonts[idx], FontSlot::with_value(None));
+            fonts[idx] = FontSlot::new_boxed(PartialInfoFontLoader {
+                slot,
+                idx,
+                family: searcher.book.info(idx).unwrap().family.clone(),
+                partial_book: partial_book.clone(),
+            });
+        }
+
         FontResolverImpl::new(
             searcher.book,
-            Arc::new(Mutex::new(PartialFontBook::default())),
-            searcher.fonts,
+            partial_book,
+            fonts,
             searcher.profile_rebuilder.profile,
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    #[test]
+    fn test_profile_reuse() {
+        let dir =
+            std::env::temp_dir().join(format!("typst-ts-font-profile-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let font_path = dir.join("font.ttf");
+        let profile_path = dir.join("profile.json.gz");
+        std::fs::write(&font_path, b"not a font").unwrap();
+
+        let searcher_with_profile = |profile_path: Option<&Path>| {
+            let mut searcher = SystemFontSearcher::new();
+            searcher.set_can_profile(true);
+            if let Some(profile_path) = profile_path {
+                searcher.add_profile_by_path(profile_path).unwrap();
+            }
+            searcher
+        };
+
+        let mut searcher = searcher_with_profile(None);
+        searcher.search_file(&font_path);
+        searcher.write_profile(&profile_path).unwrap();
+        let hash = searcher.profile_rebuilder.profile.items[0].hash.clone();
+
+        // the unchanged file is reused
+        let searcher = searcher_with_profile(Some(&profile_path));
+        let rebuilder = &searcher.profile_rebuilder;
+        assert!(rebuilder
+            .reuse_file(&font_path.canonicalize().unwrap())
+            .is_some());
+
+        // the touched file is reused by hash
+        let mtime = SystemTime::now() + Duration::from_secs(60);
+        filetime::set_file_mtime(&font_path, filetime::FileTime::from_system_time(mtime)).unwrap();
+        let mut searcher = searcher_with_profile(Some(&profile_path));
+        searcher.search_file(&font_path);
+        let item = &searcher.profile_rebuilder.profile.items[0];
+        assert_eq!(item.hash(), hash);
+        assert!(item.mtime_is_exact(mtime));
+
+        // the modified file is measured again
+        std::fs::write(&font_path, b"still not a font").unwrap();
+        let mut searcher = searcher_with_profile(Some(&profile_path));
+        searcher.search_file(&font_path);
+        let item = &searcher.profile_rebuilder.profile.items[0];
+        assert_ne!(item.hash(), hash);
+
+        // the items of the files not searched are kept on request
+        let other_path = dir.join("other.ttf");
+        std::fs::write(&other_path, b"another non-font").unwrap();
+        let mut searcher = searcher_with_profile(None);
+        searcher.search_file(&font_path);
+        searcher.search_file(&other_path);
+        searcher.write_profile(&profile_path).unwrap();
+        let mut searcher = searcher_with_profile(Some(&profile_path));
+        searcher.search_file(&font_path);
+        assert_eq!(searcher.profile_rebuilder.profile.items.len(), 1);
+        searcher.retain_profile_items();
+        assert_eq!(searcher.profile_rebuilder.profile.items.len(), 2);
+
+        // but not if the files are removed
+        std::fs::remove_file(&other_path).unwrap();
+        let mut searcher = searcher_with_profile(Some(&profile_path));
+        searcher.search_file(&font_path);
+        searcher.retain_profile_items();
+        assert_eq!(searcher.profile_rebuilder.profile.items.len(), 1);
+
+        // the invalid profile is reported
+        std::fs::write(&profile_path, b"not a profile").unwrap();
+        let mut searcher = SystemFontSearcher::new();
+        assert!(searcher.add_profile_by_path(&profile_path).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_config() {
+        use typst::font::{FontFlags, FontVariant};
+
+        let mut searcher = SystemFontSearcher::new();
+        for (family, coverage) in [
+            ("Helvetica", vec!['a' as u32]),
+            ("Liberation Sans", vec!['a' as u32]),
+            ("Comic Sans MS", vec!['a' as u32]),
+            ("Noto Sans CJK SC", vec!['a' as u32, '中' as u32]),
+            ("Source Han Serif SC", vec!['中' as u32]),
+        ] {
+            searcher.book.push(FontInfo {
+                family: family.to_owned(),
+                variant: FontVariant::default(),
+                flags: FontFlags::empty(),
+                coverage: Coverage::from_vec(coverage),
+            });
+            searcher.fonts.push(FontSlot::with_value(None));
+        }
+
+        let config: FontConfig = serde_json::from_str(
+            r#"{
+                "aliases": { "Helvetica": "Liberation Sans" },
+                "fallbacks": { "Hani": ["Source Han Serif SC"] },
+                "excludes": ["Comic Sans MS"]
+            }"#,
+        )
+        .unwrap();
+        searcher.apply_config(&config);
+
+        let families = (0..searcher.fonts.len())
+            .map(|idx| searcher.book.info(idx).unwrap().family.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            families,
+            vec![
+                "Source Han Serif SC",
+                "Liberation Sans",
+                "Noto Sans CJK SC",
+                "Helvetica"
+            ]
+        );
+
+        let fallback = searcher
+            .book
+            .select_fallback(None, FontVariant::default(), "中")
+            .unwrap();
+        assert_eq!(fallback, 0);
+        let noto = searcher.book.info(2).unwrap();
+        assert!(noto.coverage.contains('a' as u32));
+        assert!(!noto.coverage.contains('中' as u32));
+    }
+}
@@ -9,7 +9,7 @@ use wasm_bindgen::prelude::*;
 use typst_ts_core::{
     cache::FontInfoCache,
     error::prelude::*,
-    font::{BufferFontLoader, FontProfile, FontResolverImpl, PartialFontBook},
+    font::{BufferFontLoader, FontGlyphPack, FontProfile, FontResolverImpl, PartialFontBook},
     Bytes, FontLoader, FontSlot,
 };
 
@@ -496,10 +496,10 @@ impl BrowserFontSearcher {
         }
     }
 
-    pub async fn add_glyph_pack(&mut self) -> ZResult<()> {
-        Err(error_once!(
-            "BrowserFontSearcher.add_glyph_pack is not implemented"
-        ))
+    pub async fn add_glyph_pack(&mut self, pack: FontGlyphPack) -> ZResult<()> {
+        self.fonts.push(pack.slot());
+        self.book.push(pack.info);
+        Ok(())
     }
 }
 
@@ -59,6 +59,10 @@ pub use system::TypstSystemWorld;
 pub(crate) mod browser;
 #[cfg(feature = "browser-compile")]
 pub use browser::TypstBrowserWorld;
+
+/// Run the compiler in memory, without accessing the file system.
+pub(crate) mod memory;
+pub use memory::TypstMemoryWorld;
 use typst::{
     diag::{At, FileResult, SourceResult},
     syntax::Span,
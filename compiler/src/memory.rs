@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use typst_ts_core::{font::FontResolverImpl, package::dummy::DummyRegistry};
+
+use crate::vfs::memory::MemoryAccessModel;
+
+/// A world that holds all of the files in memory.
+/// It is useful for server embedders and unit tests.
+pub type TypstMemoryWorld = crate::world::CompilerWorld<MemoryCompilerFeat>;
+
+pub struct MemoryCompilerFeat;
+
+impl crate::world::CompilerFeat for MemoryCompilerFeat {
+    type AccessModel = MemoryAccessModel;
+    type Registry = DummyRegistry;
+}
+
+impl TypstMemoryWorld {
+    /// Create a world over the files in the access model.
+    ///
+    /// Keep a clone of the access model to update the files later.
+    pub fn new(
+        root_dir: PathBuf,
+        access_model: MemoryAccessModel,
+        font_resolver: FontResolverImpl,
+    ) -> Self {
+        let vfs = crate::vfs::Vfs::new(access_model);
+
+        Self::new_raw(root_dir, vfs, DummyRegistry, font_resolver)
+    }
+}
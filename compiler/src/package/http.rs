@@ -47,6 +47,16 @@ impl HttpRegistry {
         res
     }
 
+    /// Get the directories where packages may be stored, whether they exist
+    /// or not.
+    pub fn package_dirs(&self) -> Vec<std::path::PathBuf> {
+        [dirs::data_dir(), dirs::cache_dir()]
+            .into_iter()
+            .flatten()
+            .map(|dir| dir.join("typst/packages"))
+            .collect()
+    }
+
     /// Make a package available in the on-disk cache.
     pub fn prepare_package(&self, spec: &PackageSpec) -> Result<Arc<Path>, PackageError> {
         let subdir = format!(
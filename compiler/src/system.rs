@@ -1,19 +1,30 @@
 use std::borrow::Cow;
 
-use typst_ts_core::{config::CompileOpts, error::prelude::*, font::FontResolverImpl, Bytes};
+use typst_ts_core::{
+    config::CompileOpts,
+    error::prelude::*,
+    font::{FontGlyphPack, FontResolverImpl},
+    Bytes,
+};
 
 use crate::{
     font::system::SystemFontSearcher,
     package::http::HttpRegistry,
-    vfs::{system::SystemAccessModel, Vfs},
+    vfs::{
+        archive::{ArchiveAccessModel, ArchiveMountAccessModel},
+        sandbox::{SandboxAccessModel, SandboxPolicy},
+        system::SystemAccessModel,
+        Vfs,
+    },
 };
 
 /// type trait of [`TypstSystemWorld`].
 pub struct SystemCompilerFeat;
 
 impl crate::world::CompilerFeat for SystemCompilerFeat {
-    /// It accesses a physical file system.
-    type AccessModel = SystemAccessModel;
+    /// It accesses a physical file system, optionally in a sandbox, and the
+    /// workspace may be packed in a mounted archive.
+    type AccessModel = ArchiveMountAccessModel<SandboxAccessModel<SystemAccessModel>>;
     /// It performs native HTTP requests for fetching package data.
     type Registry = HttpRegistry;
 }
@@ -26,26 +37,63 @@ impl TypstSystemWorld {
     /// See SystemCompilerFeat for instantiation details.
     /// See [`CompileOpts`] for available options.
     pub fn new(opts: CompileOpts) -> ZResult<Self> {
+        let registry = HttpRegistry::default();
+        let access_model = SandboxAccessModel::new(SystemAccessModel {})
+            .with_policy(Self::sandbox(&opts, &registry));
+        let archive = opts
+            .workspace_archive
+            .as_ref()
+            .map(|archive| ArchiveAccessModel::from_path(archive, opts.root_dir.clone()))
+            .transpose()?;
+        let access_model = ArchiveMountAccessModel::new(access_model).with_archive(archive);
+
         Ok(Self::new_raw(
             opts.root_dir.clone(),
-            Vfs::new(SystemAccessModel {}),
-            HttpRegistry::default(),
+            Vfs::new(access_model),
+            registry,
             Self::resolve_fonts(opts)?,
         ))
     }
 
+    /// Resolve sandbox policy from given options.
+    fn sandbox(opts: &CompileOpts, registry: &HttpRegistry) -> Option<SandboxPolicy> {
+        if !opts.sandbox {
+            return None;
+        }
+
+        let mut roots = vec![opts.root_dir.clone()];
+        roots.extend(registry.package_dirs());
+        roots.extend(opts.sandbox_read_only_roots.iter().cloned());
+
+        Some(SandboxPolicy::new(roots).with_max_file_size(opts.sandbox_max_file_size))
+    }
+
     /// Resolve fonts from given options.
     fn resolve_fonts(opts: CompileOpts) -> ZResult<FontResolverImpl> {
         let mut searcher = SystemFontSearcher::new();
 
-        if opts
-            .font_profile_cache_path
-            .to_str()
-            .map(|e| !e.is_empty())
-            .unwrap_or_default()
-        {
+        let profile_cache_path = opts.font_profile_cache_path;
+        let profile_cache_path =
+            (!profile_cache_path.as_os_str().is_empty()).then_some(profile_cache_path);
+        if profile_cache_path.is_some() {
             searcher.set_can_profile(true);
         }
+        searcher.set_lazy(opts.lazy_fonts);
+
+        // Load the profiles before searching, so that the unchanged font files
+        // are not measured again. The cache written by the last run is also
+        // reused, if any.
+        let mut profile_paths = opts.font_profile_paths;
+        if let Some(cache_path) = &profile_cache_path {
+            if cache_path.exists() && !profile_paths.contains(cache_path) {
+                profile_paths.push(cache_path.clone());
+            }
+        }
+        for profile_path in profile_paths {
+            if let Err(err) = searcher.add_profile_by_path(&profile_path) {
+                log::warn!("failed to load font profile {profile_path:?}: {err}");
+            }
+        }
 
         // Note: the order of adding fonts is important.
         // See: https://github.com/typst/typst/blob/9c7f31870b4e1bf37df79ebbe1df9a56df83d878/src/font/book.rs#L151-L154
@@ -72,9 +120,27 @@ impl TypstSystemWorld {
                 Cow::Owned(data) => Bytes::from(data),
             });
         }
-        // Source5: add the fonts from the profile cache.
-        for profile_path in opts.font_profile_paths {
-            searcher.add_profile_by_path(&profile_path);
+        // Source5: add the glyph packs, which are preferred less than the full
+        // fonts.
+        for pack_path in opts.glyph_pack_paths {
+            let data = std::fs::read(&pack_path).map_err(error_once_map_string!(
+                "SystemFontSearcher.read_glyph_pack",
+                path: pack_path.display()
+            ))?;
+            let packs: Vec<FontGlyphPack> = serde_json::from_slice(&data).map_err(
+                error_once_map_string!("SystemFontSearcher.parse_glyph_pack", path: pack_path.display()),
+            )?;
+            for pack in packs {
+                searcher.add_glyph_pack(pack);
+            }
+        }
+
+        searcher.apply_config(&opts.font_config);
+
+        if let Some(profile_cache_path) = profile_cache_path {
+            if let Err(err) = searcher.write_profile(&profile_cache_path) {
+                log::warn!("failed to write font profile {profile_cache_path:?}: {err}");
+            }
         }
 
         Ok(searcher.into())
@@ -0,0 +1,476 @@
+use std::{
+    io::{Read, Seek},
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use typst::diag::FileResult;
+
+use typst_ts_core::{error::prelude::*, path::PathClean, Bytes};
+
+use crate::time::SystemTime;
+
+use super::{memory::MemoryAccessModel, AccessModel};
+
+/// The archive formats supported by [`ArchiveAccessModel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// A `.zip` archive.
+    Zip,
+    /// An uncompressed `.tar` archive.
+    Tar,
+    /// A gzip compressed `.tar.gz` or `.tgz` archive.
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Guess the archive format by the extension of the path.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Limits on the data read from an archive, which reject archive bombs before
+/// they exhaust the memory.
+///
+/// The sizes recorded in the archive headers are not trusted, and the limits
+/// are checked against the bytes actually decompressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimits {
+    /// The maximum size of a file in the archive, in bytes.
+    pub max_file_size: u64,
+    /// The maximum total size of the files in the archive, in bytes.
+    pub max_total_size: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_file_size: 64 * 1024 * 1024,
+            max_total_size: 512 * 1024 * 1024,
+        }
+    }
+}
+
+/// Provides access to a workspace packed in an archive, without unpacking it
+/// to disk.
+///
+/// The archive is read into memory once, and all of the regular files are
+/// mounted under the given root path. Directory entries and links are
+/// ignored, and any entry escaping the root results in an error.
+#[derive(Debug, Clone)]
+pub struct ArchiveAccessModel {
+    root: PathBuf,
+    inner: MemoryAccessModel,
+}
+
+impl ArchiveAccessModel {
+    /// Read the archive at the path, guessing the format by its extension.
+    pub fn from_path(archive: &Path, root: PathBuf) -> ZResult<Self> {
+        Self::from_path_with_limits(archive, root, ArchiveLimits::default())
+    }
+
+    /// Read the archive at the path with the given limits, guessing the
+    /// format by its extension.
+    pub fn from_path_with_limits(
+        archive: &Path,
+        root: PathBuf,
+        limits: ArchiveLimits,
+    ) -> ZResult<Self> {
+        let format = ArchiveFormat::from_path(archive).ok_or_else(
+            || error_once!("ArchiveAccessModel.unknown_format", path: archive.display()),
+        )?;
+        let file = std::fs::File::open(archive)
+            .map_err(error_once_map_string!("ArchiveAccessModel.open", path: archive.display()))?;
+
+        Self::from_reader_with_limits(format, std::io::BufReader::new(file), root, limits)
+    }
+
+    /// Read the archive in the given format.
+    pub fn from_reader(
+        format: ArchiveFormat,
+        reader: impl Read + Seek,
+        root: PathBuf,
+    ) -> ZResult<Self> {
+        Self::from_reader_with_limits(format, reader, root, ArchiveLimits::default())
+    }
+
+    /// Read the archive in the given format with the given limits.
+    pub fn from_reader_with_limits(
+        format: ArchiveFormat,
+        reader: impl Read + Seek,
+        root: PathBuf,
+        limits: ArchiveLimits,
+    ) -> ZResult<Self> {
+        let mut this = Self::new(root);
+        match format {
+            ArchiveFormat::Zip => this.read_zip(reader, limits)?,
+            ArchiveFormat::Tar => this.read_tar(reader, limits)?,
+            ArchiveFormat::TarGz => this.read_tar(flate2::read::GzDecoder::new(reader), limits)?,
+        }
+
+        Ok(this)
+    }
+
+    /// Read a `.zip` archive.
+    ///
+    /// Note: zip entries carry no time zone, so their modification time is
+    /// not used.
+    pub fn from_zip(reader: impl Read + Seek, root: PathBuf) -> ZResult<Self> {
+        Self::from_reader(ArchiveFormat::Zip, reader, root)
+    }
+
+    /// Read an uncompressed `.tar` archive. Wrap the reader with a decoder
+    /// to read a compressed one.
+    pub fn from_tar(reader: impl Read, root: PathBuf) -> ZResult<Self> {
+        let mut this = Self::new(root);
+        this.read_tar(reader, ArchiveLimits::default())?;
+        Ok(this)
+    }
+
+    fn read_zip(&mut self, reader: impl Read + Seek, limits: ArchiveLimits) -> ZResult<()> {
+        let mut total_size = 0;
+
+        let mut archive =
+            zip::ZipArchive::new(reader).map_err(map_string_err("ArchiveAccessModel.zip"))?;
+        for idx in 0..archive.len() {
+            let entry = archive
+                .by_index(idx)
+                .map_err(map_string_err("ArchiveAccessModel.zip_entry"))?;
+            if !entry.is_file() {
+                continue;
+            }
+
+            let path = self.mount_path(Path::new(entry.name()))?;
+            let data = Self::read_entry(entry, &path, limits, &mut total_size)?;
+
+            self.inner
+                .insert_file_with_mtime(&path, Bytes::from(data), SystemTime::UNIX_EPOCH);
+        }
+
+        Ok(())
+    }
+
+    fn read_tar(&mut self, reader: impl Read, limits: ArchiveLimits) -> ZResult<()> {
+        let mut total_size = 0;
+
+        let mut archive = tar::Archive::new(reader);
+        let entries = archive
+            .entries()
+            .map_err(map_string_err("ArchiveAccessModel.tar"))?;
+        for entry in entries {
+            let entry = entry.map_err(map_string_err("ArchiveAccessModel.tar_entry"))?;
+            // links are not followed, since they may point outside the archive.
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+
+            let path = self.mount_path(
+                &entry
+                    .path()
+                    .map_err(map_string_err("ArchiveAccessModel.tar_path"))?,
+            )?;
+            let mt = entry.header().mtime().unwrap_or_default();
+            let data = Self::read_entry(entry, &path, limits, &mut total_size)?;
+
+            self.inner.insert_file_with_mtime(
+                &path,
+                Bytes::from(data),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(mt),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Read the content of an entry, failing once the limits are exceeded.
+    fn read_entry(
+        entry: impl Read,
+        path: &Path,
+        limits: ArchiveLimits,
+        total_size: &mut u64,
+    ) -> ZResult<Vec<u8>> {
+        let limit = limits
+            .max_file_size
+            .min(limits.max_total_size.saturating_sub(*total_size));
+
+        // read one more byte to detect the exceeding entry.
+        let mut data = Vec::new();
+        entry
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut data)
+            .map_err(map_string_err("ArchiveAccessModel.read"))?;
+        if data.len() as u64 > limit {
+            return Err(error_once!(
+                "ArchiveAccessModel.too_large",
+                path: path.display(),
+                max_file_size: limits.max_file_size,
+                max_total_size: limits.max_total_size,
+            ));
+        }
+
+        *total_size += data.len() as u64;
+        Ok(data)
+    }
+
+    fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            inner: MemoryAccessModel::new(),
+        }
+    }
+
+    /// The root path where the archive is mounted.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Get the paths of all of the files in the archive, which are mounted
+    /// under the root.
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.inner.file_paths()
+    }
+
+    /// Resolve the path of an archive entry, rejecting entries which are
+    /// absolute or escape the root by `..`.
+    fn mount_path(&self, entry: &Path) -> ZResult<PathBuf> {
+        let mut path = self.root.clone();
+        for comp in entry.components() {
+            match comp {
+                Component::Normal(name) => path.push(name),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(error_once!(
+                        "ArchiveAccessModel.unsafe_path",
+                        path: entry.display()
+                    ));
+                }
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+impl AccessModel for ArchiveAccessModel {
+    type RealPath = PathBuf;
+
+    fn mtime(&self, src: &Path) -> FileResult<SystemTime> {
+        self.inner.mtime(src)
+    }
+
+    fn is_file(&self, src: &Path) -> FileResult<bool> {
+        self.inner.is_file(src)
+    }
+
+    fn real_path(&self, src: &Path) -> FileResult<Self::RealPath> {
+        self.inner.real_path(src)
+    }
+
+    fn content(&self, src: &Path) -> FileResult<Bytes> {
+        self.inner.content(src)
+    }
+}
+
+/// Mounts a workspace archive over the inner access model.
+///
+/// The paths under the root of the archive are served from the archive, and
+/// the others, e.g. the files of packages, from the inner access model.
+#[derive(Debug, Default)]
+pub struct ArchiveMountAccessModel<M: AccessModel> {
+    archive: Option<ArchiveAccessModel>,
+    pub model: M,
+}
+
+impl<M: AccessModel> ArchiveMountAccessModel<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            archive: None,
+            model,
+        }
+    }
+
+    /// Mount the archive, if any.
+    pub fn with_archive(mut self, archive: Option<ArchiveAccessModel>) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    pub fn archive(&self) -> Option<&ArchiveAccessModel> {
+        self.archive.as_ref()
+    }
+
+    /// Get the archive if the path is mounted to it.
+    fn mounted(&self, src: &Path) -> Option<&ArchiveAccessModel> {
+        let archive = self.archive.as_ref()?;
+        src.clean().starts_with(archive.root()).then_some(archive)
+    }
+}
+
+impl<M: AccessModel> AccessModel for ArchiveMountAccessModel<M> {
+    type RealPath = M::RealPath;
+
+    fn clear(&mut self) {
+        self.model.clear();
+    }
+
+    fn mtime(&self, src: &Path) -> FileResult<SystemTime> {
+        match self.mounted(src) {
+            Some(archive) => archive.mtime(src),
+            None => self.model.mtime(src),
+        }
+    }
+
+    fn is_file(&self, src: &Path) -> FileResult<bool> {
+        match self.mounted(src) {
+            Some(archive) => archive.is_file(src),
+            None => self.model.is_file(src),
+        }
+    }
+
+    fn real_path(&self, src: &Path) -> FileResult<Self::RealPath> {
+        match self.mounted(src) {
+            Some(archive) => Ok(archive.real_path(src)?.as_path().into()),
+            None => self.model.real_path(src),
+        }
+    }
+
+    fn content(&self, src: &Path) -> FileResult<Bytes> {
+        match self.mounted(src) {
+            Some(archive) => archive.content(src),
+            None => self.model.content(src),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+
+    fn tar_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mtime(1);
+            header.set_mode(0o644);
+            // bypass the path validation of `set_path`.
+            let name = &mut header.as_old_mut().name;
+            name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_cksum();
+            builder.append(&header, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (path, data) in files {
+            writer
+                .start_file(*path, zip::write::FileOptions::default())
+                .unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_archive_format() {
+        let format = |p: &str| ArchiveFormat::from_path(Path::new(p));
+        assert_eq!(format("project.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(format("project.tar"), Some(ArchiveFormat::Tar));
+        assert_eq!(format("project.TAR.GZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("project.tgz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(format("project.typ"), None);
+    }
+
+    #[test]
+    fn test_archive_access_model() {
+        let files: &[(&str, &[u8])] = &[("main.typ", b"= Hello"), ("lib/lib.typ", b"#let x = 1")];
+        let root = PathBuf::from("/project");
+
+        let tar = tar_archive(files);
+        let zip = zip_archive(files);
+        let models = [
+            ArchiveAccessModel::from_reader(ArchiveFormat::Tar, Cursor::new(tar), root.clone()),
+            ArchiveAccessModel::from_reader(ArchiveFormat::Zip, Cursor::new(zip), root.clone()),
+        ];
+
+        for model in models {
+            let model = model.unwrap();
+            assert_eq!(
+                model.file_paths(),
+                vec![root.join("lib/lib.typ"), root.join("main.typ")]
+            );
+            assert!(model.is_file(&root.join("main.typ")).unwrap());
+            assert!(!model.is_file(&root.join("lib")).unwrap());
+            assert_eq!(
+                model.content(&root.join("lib/lib.typ")).unwrap(),
+                Bytes::from_static(b"#let x = 1")
+            );
+        }
+    }
+
+    #[test]
+    fn test_archive_limits() {
+        let files: &[(&str, &[u8])] = &[("a.typ", &[b'a'; 64]), ("b.typ", &[b'b'; 64])];
+        let root = PathBuf::from("/project");
+        let read = |format, data: &[u8], max_file_size, max_total_size| {
+            let limits = ArchiveLimits {
+                max_file_size,
+                max_total_size,
+            };
+            let reader = Cursor::new(data.to_vec());
+            ArchiveAccessModel::from_reader_with_limits(format, reader, root.clone(), limits)
+        };
+
+        for (format, data) in [
+            (ArchiveFormat::Tar, tar_archive(files)),
+            (ArchiveFormat::Zip, zip_archive(files)),
+        ] {
+            assert!(read(format, &data, 64, 128).is_ok());
+            assert!(read(format, &data, 63, 128).is_err());
+            assert!(read(format, &data, 64, 127).is_err());
+        }
+    }
+
+    #[test]
+    fn test_archive_mount() {
+        let root = PathBuf::from("/project");
+        let zip = zip_archive(&[("main.typ", b"= Hello")]);
+        let archive = ArchiveAccessModel::from_zip(Cursor::new(zip), root.clone()).unwrap();
+
+        let packages = MemoryAccessModel::new();
+        packages.insert_file(
+            Path::new("/packages/lib.typ"),
+            Bytes::from_static(b"#let x = 1"),
+        );
+        let model = ArchiveMountAccessModel::new(packages).with_archive(Some(archive));
+
+        assert!(model.is_file(&root.join("main.typ")).unwrap());
+        assert!(model.is_file(Path::new("/packages/lib.typ")).unwrap());
+        assert!(model.is_file(&root.join("lib.typ")).is_err());
+    }
+
+    #[test]
+    fn test_archive_reject_escape() {
+        let root = PathBuf::from("/project");
+
+        let tar = tar_archive(&[("../escape.typ", b"")]);
+        assert!(ArchiveAccessModel::from_tar(Cursor::new(tar), root.clone()).is_err());
+
+        let zip = zip_archive(&[("/escape.typ", b"")]);
+        assert!(ArchiveAccessModel::from_zip(Cursor::new(zip), root).is_err());
+    }
+}
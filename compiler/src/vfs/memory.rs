@@ -0,0 +1,187 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
+use typst::diag::{FileError, FileResult};
+
+use typst_ts_core::{path::PathClean, Bytes};
+
+use crate::time::SystemTime;
+
+use super::AccessModel;
+
+#[derive(Debug, Clone)]
+struct MemoryFileMeta {
+    mt: SystemTime,
+    content: Bytes,
+}
+
+/// Provides a pure in-memory file system, which is useful for embedding the
+/// compiler in a server or running unit tests.
+///
+/// The access model is cheap to clone, and all of the clones share the same
+/// file tree. Therefore you can keep a clone to update the files after the
+/// access model is moved into the [`super::Vfs`].
+///
+/// A directory exists implicitly if any file is stored under it.
+#[derive(Default, Debug, Clone)]
+pub struct MemoryAccessModel {
+    files: Arc<RwLock<BTreeMap<PathBuf, MemoryFileMeta>>>,
+}
+
+impl MemoryAccessModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create or update a file with the given content.
+    /// The modification time is set to the current time.
+    pub fn insert_file(&self, path: &Path, content: Bytes) {
+        self.insert_file_with_mtime(path, content, SystemTime::now())
+    }
+
+    /// Create or update a file with the given content and modification time.
+    ///
+    /// Note: the modification time of an updated file is always increased, so
+    /// that the compiler can detect the change.
+    pub fn insert_file_with_mtime(&self, path: &Path, content: Bytes, mt: SystemTime) {
+        let path = path.clean();
+
+        let mut files = self.files.write();
+        let mt = match files.get(&path) {
+            Some(prev) if prev.mt >= mt => prev.mt + std::time::Duration::from_micros(1),
+            _ => mt,
+        };
+        files.insert(path, MemoryFileMeta { mt, content });
+    }
+
+    /// Remove a file, returning whether the file existed.
+    pub fn remove_file(&self, path: &Path) -> bool {
+        self.files.write().remove(&path.clean()).is_some()
+    }
+
+    /// Remove a directory and all of the files under it, returning the number
+    /// of removed files.
+    pub fn remove_dir(&self, path: &Path) -> usize {
+        let path = path.clean();
+
+        let mut files = self.files.write();
+        let prev_len = files.len();
+        files.retain(|p, _| !p.starts_with(&path));
+        prev_len - files.len()
+    }
+
+    /// Remove all of the files.
+    pub fn clear_files(&self) {
+        self.files.write().clear();
+    }
+
+    /// Get all of the file paths in sorted order.
+    pub fn file_paths(&self) -> Vec<PathBuf> {
+        self.files.read().keys().cloned().collect()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        // the paths are ordered by components, so the files under the
+        // directory, if any, come right after the directory itself.
+        let files = self.files.read();
+        let mut after =
+            files.range::<Path, _>((std::ops::Bound::Excluded(path), std::ops::Bound::Unbounded));
+        after.next().map_or(false, |(p, _)| p.starts_with(path))
+    }
+
+    fn stat(&self, src: &Path) -> FileResult<Option<MemoryFileMeta>> {
+        let src = src.clean();
+        if let Some(meta) = self.files.read().get(&src) {
+            return Ok(Some(meta.clone()));
+        }
+
+        if self.is_dir(&src) {
+            return Ok(None);
+        }
+
+        Err(FileError::NotFound(src))
+    }
+}
+
+impl AccessModel for MemoryAccessModel {
+    type RealPath = PathBuf;
+
+    fn mtime(&self, src: &Path) -> FileResult<SystemTime> {
+        // directories are never modified in sense of content
+        Ok(self
+            .stat(src)?
+            .map(|meta| meta.mt)
+            .unwrap_or(SystemTime::UNIX_EPOCH))
+    }
+
+    fn is_file(&self, src: &Path) -> FileResult<bool> {
+        Ok(self.stat(src)?.is_some())
+    }
+
+    fn real_path(&self, src: &Path) -> FileResult<Self::RealPath> {
+        Ok(src.clean())
+    }
+
+    fn content(&self, src: &Path) -> FileResult<Bytes> {
+        match self.stat(src)? {
+            Some(meta) => Ok(meta.content),
+            None => Err(FileError::IsDirectory),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_access_model() {
+        let model = MemoryAccessModel::new();
+        let main = Path::new("/root/main.typ");
+        let lib = Path::new("/root/lib/lib.typ");
+
+        model.insert_file(main, Bytes::from_static(b"#import \"lib/lib.typ\""));
+        model.insert_file(lib, Bytes::from_static(b"#let x = 1"));
+
+        assert!(model.is_file(main).unwrap());
+        assert!(!model.is_file(Path::new("/root/lib")).unwrap());
+        assert!(model.is_file(Path::new("/root/li")).is_err());
+        assert!(model.is_file(Path::new("/root/lib/lib")).is_err());
+        assert!(matches!(
+            model.is_file(Path::new("/root/missing.typ")),
+            Err(FileError::NotFound(..))
+        ));
+        assert_eq!(
+            model.content(Path::new("/root/lib/../main.typ")).unwrap(),
+            Bytes::from_static(b"#import \"lib/lib.typ\"")
+        );
+        assert!(matches!(
+            model.content(Path::new("/root/lib")),
+            Err(FileError::IsDirectory)
+        ));
+
+        assert_eq!(model.remove_dir(Path::new("/root/lib")), 1);
+        assert!(model.is_file(lib).is_err());
+        assert!(model.remove_file(main));
+        assert!(!model.remove_file(main));
+    }
+
+    #[test]
+    fn test_memory_access_model_mtime() {
+        let model = MemoryAccessModel::new();
+        let main = Path::new("/root/main.typ");
+
+        let mt = SystemTime::UNIX_EPOCH;
+        model.insert_file_with_mtime(main, Bytes::from_static(b"a"), mt);
+        let prev = model.mtime(main).unwrap();
+        model.insert_file_with_mtime(main, Bytes::from_static(b"b"), mt);
+        let next = model.mtime(main).unwrap();
+
+        assert!(next > prev);
+        assert_eq!(model.content(main).unwrap(), Bytes::from_static(b"b"));
+    }
+}
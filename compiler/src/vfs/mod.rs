@@ -10,9 +10,12 @@ pub mod browser;
 #[cfg(feature = "system-compile")]
 pub mod system;
 
+pub mod archive;
 pub mod cached;
 pub mod dummy;
+pub mod memory;
 pub mod overlay;
+pub mod sandbox;
 pub mod trace;
 
 mod path_abs;
@@ -73,6 +76,28 @@ pub trait AccessModel {
     fn real_path(&self, src: &Path) -> FileResult<Self::RealPath>;
 
     fn content(&self, src: &Path) -> FileResult<Bytes>;
+
+    /// Read the content of the file, failing if it is larger than `limit`
+    /// bytes. An implementation should stop reading once the limit is
+    /// exceeded.
+    fn content_with_limit(&self, src: &Path, limit: u64) -> FileResult<Bytes> {
+        let content = self.content(src)?;
+        if content.len() as u64 > limit {
+            return Err(file_too_large(src, limit));
+        }
+        Ok(content)
+    }
+}
+
+/// The error of reading a file larger than the limit.
+pub(crate) fn file_too_large(src: &Path, limit: u64) -> FileError {
+    FileError::Other(Some(
+        format!(
+            "file {} exceeds the size limit of {limit} bytes",
+            src.display()
+        )
+        .into(),
+    ))
 }
 
 type FileQuery<T> = QueryRef<T, FileError>;
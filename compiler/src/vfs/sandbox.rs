@@ -0,0 +1,208 @@
+use std::path::{Path, PathBuf};
+
+use typst::diag::{FileError, FileResult};
+
+use typst_ts_core::{path::PathClean, Bytes};
+
+use crate::time::SystemTime;
+
+use super::AccessModel;
+
+/// Restricts the files accessible to the compiler.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    /// The directories allowed to be read, e.g. the workspace root and the
+    /// package directories. All other paths are denied.
+    pub roots: Vec<PathBuf>,
+    /// The maximum size of a file in bytes, if any.
+    pub max_file_size: Option<u64>,
+}
+
+impl SandboxPolicy {
+    /// Create a policy allowing to read the given directories.
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            max_file_size: None,
+        }
+    }
+
+    /// Allow to read another directory.
+    pub fn with_root(mut self, root: PathBuf) -> Self {
+        self.roots.push(root);
+        self
+    }
+
+    /// Limit the size of files to read.
+    pub fn with_max_file_size(mut self, max_file_size: Option<u64>) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+}
+
+/// Enforces a [`SandboxPolicy`] on the inner access model.
+///
+/// The paths are checked after resolving symlinks, so that a symlink in the
+/// workspace cannot point out of the allowed roots. Without a policy, all of
+/// the accesses are forwarded to the inner access model.
+///
+/// Note that a path is checked and then opened separately. It keeps documents
+/// from reading files out of the roots, but not a concurrent process, which
+/// may replace a directory on the checked path with a symlink in between.
+#[derive(Debug, Default)]
+pub struct SandboxAccessModel<M: AccessModel<RealPath = PathBuf>> {
+    policy: Option<SandboxPolicy>,
+    pub model: M,
+}
+
+impl<M: AccessModel<RealPath = PathBuf>> SandboxAccessModel<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            policy: None,
+            model,
+        }
+    }
+
+    /// Enable the sandbox with the given policy.
+    pub fn with_policy(mut self, policy: Option<SandboxPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> Option<&SandboxPolicy> {
+        self.policy.as_ref()
+    }
+
+    /// Check whether the path is accessible, returning its real path.
+    ///
+    /// The accesses are forwarded with the returned path, i.e. the path
+    /// with the symlinks resolved at the time of the check.
+    fn check(&self, src: &Path) -> FileResult<PathBuf> {
+        let Some(policy) = &self.policy else {
+            return self.model.real_path(src);
+        };
+
+        // roots are resolved on every check, since a package directory may be
+        // created after the sandbox.
+        let real_path = resolve_symlinks(src);
+        let allowed = policy
+            .roots
+            .iter()
+            .any(|root| real_path.starts_with(resolve_symlinks(root)));
+        if !allowed {
+            return Err(FileError::AccessDenied);
+        }
+
+        Ok(real_path)
+    }
+}
+
+/// Resolve the symlinks of the path.
+///
+/// The path may not exist, e.g. a file provided by an in-memory access model,
+/// in which case its longest existing ancestor is resolved instead.
+fn resolve_symlinks(path: &Path) -> PathBuf {
+    let path = path.clean();
+    for ancestor in path.ancestors() {
+        if let Ok(real_ancestor) = std::fs::canonicalize(ancestor) {
+            // `ancestors` yields the prefixes of the path.
+            let rest = path.strip_prefix(ancestor).unwrap();
+            return real_ancestor.join(rest);
+        }
+    }
+
+    path
+}
+
+impl<M: AccessModel<RealPath = PathBuf>> AccessModel for SandboxAccessModel<M> {
+    type RealPath = PathBuf;
+
+    fn clear(&mut self) {
+        self.model.clear();
+    }
+
+    fn mtime(&self, src: &Path) -> FileResult<SystemTime> {
+        self.model.mtime(&self.check(src)?)
+    }
+
+    fn is_file(&self, src: &Path) -> FileResult<bool> {
+        self.model.is_file(&self.check(src)?)
+    }
+
+    fn real_path(&self, src: &Path) -> FileResult<Self::RealPath> {
+        self.check(src)
+    }
+
+    fn content(&self, src: &Path) -> FileResult<Bytes> {
+        let real_path = self.check(src)?;
+
+        match self.policy.as_ref().and_then(|p| p.max_file_size) {
+            Some(max_file_size) => self.model.content_with_limit(&real_path, max_file_size),
+            None => self.model.content(&real_path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::memory::MemoryAccessModel;
+    use super::*;
+
+    #[test]
+    fn test_sandbox_access_model() {
+        let files = MemoryAccessModel::new();
+        files.insert_file(Path::new("/root/main.typ"), Bytes::from_static(b"= Hello"));
+        files.insert_file(Path::new("/root/big.typ"), Bytes::from(vec![0; 1024]));
+        files.insert_file(Path::new("/fonts/font.ttf"), Bytes::from_static(b""));
+        files.insert_file(Path::new("/secret"), Bytes::from_static(b""));
+
+        let policy = SandboxPolicy::new(vec![PathBuf::from("/root")])
+            .with_root(PathBuf::from("/fonts"))
+            .with_max_file_size(Some(512));
+        let model = SandboxAccessModel::new(files).with_policy(Some(policy));
+
+        assert!(model.is_file(Path::new("/root/main.typ")).unwrap());
+        assert!(model.content(Path::new("/fonts/font.ttf")).is_ok());
+        assert!(matches!(
+            model.content(Path::new("/secret")),
+            Err(FileError::AccessDenied)
+        ));
+        assert!(matches!(
+            model.content(Path::new("/root/../secret")),
+            Err(FileError::AccessDenied)
+        ));
+        assert!(matches!(
+            model.content(Path::new("/root/big.typ")),
+            Err(FileError::Other(..))
+        ));
+    }
+
+    #[cfg(all(unix, feature = "system-compile"))]
+    #[test]
+    fn test_sandbox_system_access_model() {
+        use super::super::system::SystemAccessModel;
+
+        let dir = std::env::temp_dir().join(format!("typst-ts-sandbox-{}", std::process::id()));
+        let root = dir.join("root");
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(dir.join("secret"), b"secret").unwrap();
+        std::fs::write(root.join("main.typ"), b"= Hello").unwrap();
+        std::fs::write(root.join("big.typ"), b"= Hello, World").unwrap();
+        std::os::unix::fs::symlink(dir.join("secret"), root.join("link")).unwrap();
+
+        let policy = SandboxPolicy::new(vec![root.clone()]).with_max_file_size(Some(8));
+        let model = SandboxAccessModel::new(SystemAccessModel).with_policy(Some(policy));
+
+        assert!(model.content(&root.join("main.typ")).is_ok());
+        assert!(matches!(
+            model.content(&root.join("link")),
+            Err(FileError::AccessDenied)
+        ));
+        assert!(matches!(
+            model.content(&root.join("big.typ")),
+            Err(FileError::Other(..))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -8,7 +8,7 @@ use typst::diag::{FileError, FileResult};
 
 use typst_ts_core::{Bytes, ReadAllOnce};
 
-use super::AccessModel;
+use super::{file_too_large, AccessModel};
 
 pub struct LazyFile {
     path: std::path::PathBuf,
@@ -77,4 +77,19 @@ impl AccessModel for SystemAccessModel {
             .map_err(f)?;
         Ok(buf.into())
     }
+
+    fn content_with_limit(&self, src: &Path, limit: u64) -> FileResult<Bytes> {
+        let f = |e| FileError::from_io(e, src);
+        let mut buf = Vec::<u8>::new();
+        // read one more byte to detect the exceeding file.
+        std::fs::File::open(src)
+            .map_err(f)?
+            .take(limit.saturating_add(1))
+            .read_to_end(&mut buf)
+            .map_err(f)?;
+        if buf.len() as u64 > limit {
+            return Err(file_too_large(src, limit));
+        }
+        Ok(buf.into())
+    }
 }
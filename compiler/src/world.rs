@@ -159,6 +159,11 @@ impl<F: CompilerFeat> CompilerWorld<F> {
     pub fn reset(&mut self) {
         self.vfs.reset();
 
+        // the fonts parsed in the last lifecycle update the font book
+        if self.font_resolver.partial_resolved() {
+            self.font_resolver.rebuild();
+        }
+
         self.now.take();
     }
 
@@ -182,7 +187,8 @@ impl<F: CompilerFeat> CompilerWorld<F> {
         };
 
         // Join the path to the root. If it tries to escape, deny
-        // access. Note: It can still escape via symlinks.
+        // access. Note: It can still escape via symlinks, unless the access
+        // model enforces a sandbox, see [`crate::vfs::sandbox`].
         id.vpath().resolve(&root).ok_or(FileError::AccessDenied)
     }
 
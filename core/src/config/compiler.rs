@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::path::PathBuf;
 
+use super::FontConfig;
 use crate::AsCowBytes;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
@@ -35,8 +36,40 @@ pub struct CompileOpts {
     #[serde(rename = "noVanillaFonts")]
     pub no_vanilla_fonts: bool,
 
+    /// Index the fonts without parsing their coverage, unless they are in the
+    /// font profiles
+    #[serde(rename = "lazyFonts", default)]
+    pub lazy_fonts: bool,
+
+    /// Rules to build the font book
+    #[serde(rename = "fontConfig", default)]
+    pub font_config: FontConfig,
+
     /// Include embedded fonts
     #[serde(rename = "withEmbeddedFonts")]
     #[serde_as(as = "Vec<AsCowBytes>")]
     pub with_embedded_fonts: Vec<Cow<'static, [u8]>>,
+
+    /// Paths to glyph packs, each of which is a JSON array of
+    /// [`crate::font::FontGlyphPack`]
+    #[serde(rename = "glyphPackPaths", default)]
+    pub glyph_pack_paths: Vec<PathBuf>,
+
+    /// Deny access to files outside the root directory, the package
+    /// directories, and the extra read-only roots, after resolving symlinks.
+    #[serde(default)]
+    pub sandbox: bool,
+
+    /// Extra directories allowed to be read in sandbox
+    #[serde(rename = "sandboxReadOnlyRoots", default)]
+    pub sandbox_read_only_roots: Vec<PathBuf>,
+
+    /// Maximum size of a file to read in sandbox, in bytes
+    #[serde(rename = "sandboxMaxFileSize", default)]
+    pub sandbox_max_file_size: Option<u64>,
+
+    /// Path to an archive packing the workspace, which is mounted at the root
+    /// directory without unpacking it to disk
+    #[serde(rename = "workspaceArchive", default)]
+    pub workspace_archive: Option<PathBuf>,
 }
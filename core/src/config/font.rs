@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Rules applied to the discovered fonts when building the font book, so that
+/// a document is rendered with the same fonts on different machines.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FontConfig {
+    /// Map a family to another family, e.g. from `Helvetica` to
+    /// `Liberation Sans`. The fonts of the mapped family are replaced by the
+    /// fonts of the target family, even if the mapped family is installed.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+
+    /// The families to render the characters of a script, in order of
+    /// preference, keyed by the ISO 15924 code of the script, e.g. `Hani`.
+    /// The fonts not listed are not used for the script.
+    #[serde(default)]
+    pub fallbacks: BTreeMap<String, Vec<String>>,
+
+    /// The families to exclude from the font book.
+    #[serde(default)]
+    pub excludes: Vec<String>,
+}
+
+impl FontConfig {
+    /// Whether the config changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty() && self.fallbacks.is_empty() && self.excludes.is_empty()
+    }
+}
@@ -1,5 +1,7 @@
 pub mod compiler;
+pub mod font;
 pub mod workspace;
 
 pub use compiler::CompileOpts;
+pub use font::FontConfig;
 pub use workspace::WorkspaceConfig;
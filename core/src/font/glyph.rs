@@ -2,12 +2,13 @@ use std::fmt::Write;
 use std::hash::{Hash, Hasher};
 use std::{ops::Deref, sync::Arc};
 
-pub use ttf_parser::GlyphId;
+pub use ttf_parser::{GlyphId, RgbaColor};
 use typst::font::Font;
 use typst::geom::Axes;
 use typst::image::{Image as TypstImage, RasterFormat};
 
 use crate::hash::item_hash128;
+use crate::vector::ir::Scalar;
 use crate::{HashedTrait, StaticHash128};
 
 /// IGlyphProvider extracts the font data from the font.
@@ -29,6 +30,32 @@ pub trait IGlyphProvider {
     /// The returned data is in Path2D format.
     /// See [`FontGlyphProvider::outline_glyph`] for the default implementation.
     fn outline_glyph(&self, font: &Font, id: GlyphId) -> Option<String>;
+
+    /// With font with glyph id, return the outline path data of the glyph in
+    /// an instance of the variable font, given the coordinates of its
+    /// variation axes in form of `(axis tag, value)`.
+    /// The returned data is in Path2D format.
+    /// See [`FontGlyphProvider::outline_glyph_at`] for the default implementation.
+    /// The provider which does not implement it outlines the default instance.
+    fn outline_glyph_at(
+        &self,
+        font: &Font,
+        id: GlyphId,
+        coords: &[(u32, Scalar)],
+    ) -> Option<String> {
+        let _ = coords;
+        self.outline_glyph(font, id)
+    }
+
+    /// With font with glyph id, return the colored layers of the glyph, from
+    /// bottom to top. Each layer is in form of (outline path data, color),
+    /// where the color is `None` if the layer is painted with the text fill.
+    /// See [`FontGlyphProvider::color_glyph`] for the default implementation.
+    /// The provider which does not implement it renders no color glyph.
+    fn color_glyph(&self, font: &Font, id: GlyphId) -> Option<Vec<(String, Option<RgbaColor>)>> {
+        let _ = (font, id);
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -116,6 +143,110 @@ impl IGlyphProvider for FontGlyphProvider {
         font_face.outline_glyph(id, &mut builder)?;
         Some(builder.0)
     }
+
+    /// See [`IGlyphProvider::outline_glyph_at`] for more information.
+    fn outline_glyph_at(
+        &self,
+        font: &Font,
+        id: GlyphId,
+        coords: &[(u32, Scalar)],
+    ) -> Option<String> {
+        let mut font_face = ttf_parser::Face::parse(font.data(), font.index()).ok()?;
+        for (tag, value) in coords {
+            font_face.set_variation(ttf_parser::Tag(*tag), value.0)?;
+        }
+
+        let mut builder = SvgOutlineBuilder(String::new());
+        font_face.outline_glyph(id, &mut builder)?;
+        Some(builder.0)
+    }
+
+    /// See [`IGlyphProvider::color_glyph`] for more information.
+    /// Note: Only the layers in `COLR` version 0 are supported, and the first
+    /// palette is used. The paint graphs in `COLR` version 1 are rejected, so
+    /// that the glyphs painted only by them fall back to their outlines.
+    fn color_glyph(&self, font: &Font, id: GlyphId) -> Option<Vec<(String, Option<RgbaColor>)>> {
+        let font_face = font.ttf();
+        if !font_face.is_color_glyph(id) {
+            let colr = font_face
+                .raw_face()
+                .table(ttf_parser::Tag::from_bytes(b"COLR"));
+            if colr.map_or(false, |colr| is_colr_v1_glyph(colr, id)) {
+                log::warn!(
+                    "COLR version 1 glyph {} of font {} is not supported, rendered as outline",
+                    id.0,
+                    font.info().family
+                );
+            }
+            return None;
+        }
+
+        let mut painter = ColorGlyphPainter {
+            face: font_face,
+            outline: String::new(),
+            layers: vec![],
+        };
+        font_face.paint_color_glyph(id, 0, &mut painter)?;
+        Some(painter.layers)
+    }
+}
+
+/// Check whether the glyph has a paint graph in the `BaseGlyphList` of the
+/// `COLR` table, which is added in version 1.
+pub(crate) fn is_colr_v1_glyph(colr: &[u8], id: GlyphId) -> bool {
+    let u16_at = |at: usize| Some(u16::from_be_bytes(colr.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_be_bytes(colr.get(at..at + 4)?.try_into().ok()?));
+
+    let find = || {
+        if u16_at(0)? == 0 {
+            return Some(false);
+        }
+        let list = u32_at(14)? as usize;
+        if list == 0 {
+            return Some(false);
+        }
+
+        // the paint records are sorted by glyph id
+        let (mut lo, mut hi) = (0, u32_at(list)? as usize);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            match u16_at(list + 4 + mid * 6)?.cmp(&id.0) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(true),
+            }
+        }
+        Some(false)
+    };
+    find().unwrap_or(false)
+}
+
+/// Collects the colored layers of a glyph.
+struct ColorGlyphPainter<'a> {
+    face: &'a ttf_parser::Face<'a>,
+    /// The outline of the current layer.
+    outline: String,
+    layers: Vec<(String, Option<RgbaColor>)>,
+}
+
+impl ttf_parser::colr::Painter for ColorGlyphPainter<'_> {
+    fn outline(&mut self, glyph_id: GlyphId) {
+        let mut builder = SvgOutlineBuilder(String::new());
+        self.outline = match self.face.outline_glyph(glyph_id, &mut builder) {
+            Some(_) => builder.0,
+            None => String::new(),
+        };
+    }
+
+    fn paint_foreground(&mut self) {
+        let outline = std::mem::take(&mut self.outline);
+        self.layers.push((outline, None));
+    }
+
+    fn paint_color(&mut self, color: RgbaColor) {
+        let outline = std::mem::take(&mut self.outline);
+        self.layers.push((outline, Some(color)));
+    }
 }
 
 #[derive(Default)]
@@ -142,3 +273,31 @@ impl ttf_parser::OutlineBuilder for SvgOutlineBuilder {
         write!(&mut self.0, "Z ").unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colr_v1_glyph() {
+        // a version 1 header, whose base glyph list starts at offset 34.
+        let mut colr = vec![0, 1, 0, 0];
+        colr.extend([0u8; 10]);
+        colr.extend(34u32.to_be_bytes());
+        colr.extend([0u8; 16]);
+        colr.extend(2u32.to_be_bytes());
+        for glyph in [3u16, 7] {
+            colr.extend(glyph.to_be_bytes());
+            colr.extend(0u32.to_be_bytes());
+        }
+
+        assert!(is_colr_v1_glyph(&colr, GlyphId(3)));
+        assert!(is_colr_v1_glyph(&colr, GlyphId(7)));
+        assert!(!is_colr_v1_glyph(&colr, GlyphId(5)));
+        assert!(!is_colr_v1_glyph(&colr[..20], GlyphId(3)));
+
+        // the version 0 table has no base glyph list
+        colr[1] = 0;
+        assert!(!is_colr_v1_glyph(&colr, GlyphId(3)));
+    }
+}
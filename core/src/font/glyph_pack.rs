@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use serde_with::{base64::Base64, serde_as};
+#[cfg(feature = "font-subset")]
+use typst::font::Font;
+use typst::font::FontInfo;
+
+use crate::Bytes;
+
+use super::{BufferFontLoader, FontSlot};
+
+/// A font source carrying a subset of glyphs pre-extracted from a font.
+///
+/// The pack contains the metrics, outlines, and layout tables of the glyphs,
+/// so that a document can be typeset without the full font file, as long as
+/// it only uses the characters covered by the pack.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FontGlyphPack {
+    /// The information of the original font, whose coverage is narrowed to
+    /// the characters in the pack.
+    pub info: FontInfo,
+    /// The subsetted font in OpenType format, which retains the glyph ids of
+    /// the original font.
+    #[serde_as(as = "Base64")]
+    pub data: Vec<u8>,
+}
+
+impl FontGlyphPack {
+    /// Create a lazy font slot loading the font in the pack.
+    pub fn slot(&self) -> FontSlot {
+        FontSlot::new_boxed(BufferFontLoader {
+            buffer: Some(Bytes::from(self.data.clone())),
+            index: 0,
+        })
+    }
+}
+
+#[cfg(feature = "font-subset")]
+impl FontGlyphPack {
+    /// Extract the glyphs of the characters from the font.
+    /// The extra glyphs are also extracted, e.g. the glyphs substituted by
+    /// ligatures, which are not mapped by any character.
+    ///
+    /// Returns `None` if the font cannot be subsetted.
+    pub fn extract(font: &Font, chars: &[char], glyphs: &[u16]) -> Option<Self> {
+        let data = super::subset_font_sfnt(font, chars, glyphs)?;
+
+        // the coverage is computed from the subsetted font.
+        let info = FontInfo::new(&data, 0)?;
+        let info = FontInfo {
+            coverage: info.coverage,
+            ..font.info().clone()
+        };
+
+        Some(Self { info, data })
+    }
+
+    /// Extract the glyph packs used by the document, so that the same
+    /// document can be typeset with the packs only.
+    ///
+    /// The fonts which cannot be subsetted are skipped.
+    pub fn from_document(doc: &typst::doc::Document) -> Vec<Self> {
+        use std::collections::{BTreeSet, HashMap};
+        use typst::doc::{Frame, FrameItem};
+
+        type FontUsage = HashMap<Font, (BTreeSet<char>, BTreeSet<u16>)>;
+
+        fn collect(frame: &Frame, usage: &mut FontUsage) {
+            for (_, item) in frame.items() {
+                match item {
+                    FrameItem::Group(group) => collect(&group.frame, usage),
+                    FrameItem::Text(text) => {
+                        let (chars, glyphs) = usage.entry(text.font.clone()).or_default();
+                        chars.extend(text.text.chars());
+                        glyphs.extend(text.glyphs.iter().map(|g| g.id));
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut usage = FontUsage::new();
+        for page in &doc.pages {
+            collect(page, &mut usage);
+        }
+
+        let mut packs = usage
+            .into_iter()
+            .filter_map(|(font, (chars, glyphs))| {
+                let chars = chars.into_iter().collect::<Vec<_>>();
+                let glyphs = glyphs.into_iter().collect::<Vec<_>>();
+                let pack = Self::extract(&font, &chars, &glyphs);
+                if pack.is_none() {
+                    log::warn!("failed to extract glyph pack from font {:?}", font.info());
+                }
+                pack
+            })
+            .collect::<Vec<_>>();
+        packs.sort_by_cached_key(|pack| {
+            (pack.info.family.clone(), format!("{:?}", pack.info.variant))
+        });
+
+        packs
+    }
+}
@@ -1,6 +1,10 @@
-use typst::font::Font;
+use std::sync::{Arc, Mutex};
 
-use crate::{Bytes, ReadAllOnce};
+use typst::font::{Font, FontInfo};
+
+use crate::{Bytes, FontSlot, ReadAllOnce};
+
+use super::PartialFontBook;
 
 /// A FontLoader would help load a font from somewhere.
 pub trait FontLoader {
@@ -40,3 +44,45 @@ impl<R: ReadAllOnce + Sized> FontLoader for LazyBufferFontLoader<R> {
         Font::new(buf.into(), self.index)
     }
 }
+
+/// Load font from a slot shared with other slots, e.g. the slots of the
+/// aliases of the font.
+pub struct SharedFontLoader {
+    pub slot: Arc<FontSlot>,
+}
+
+impl FontLoader for SharedFontLoader {
+    fn load(&mut self) -> Option<Font> {
+        self.slot.get_or_init()
+    }
+}
+
+/// Load font from a slot whose information in the font book is partial, e.g.
+/// without coverage, and report the full information of the loaded font to
+/// the partial font book, which takes effect after the font book is rebuilt.
+pub struct PartialInfoFontLoader {
+    pub slot: FontSlot,
+    /// The index of the font in the font book.
+    pub idx: usize,
+    /// The family in the font book, which may be an alias of the font.
+    pub family: String,
+    pub partial_book: Arc<Mutex<PartialFontBook>>,
+}
+
+impl FontLoader for PartialInfoFontLoader {
+    fn load(&mut self) -> Option<Font> {
+        let font = self.slot.get_or_init()?;
+
+        let info = FontInfo {
+            family: self.family.clone(),
+            ..font.info().clone()
+        };
+        let slot = FontSlot::with_value(Some(font.clone()));
+        self.partial_book
+            .lock()
+            .unwrap()
+            .push((Some(self.idx), info, slot));
+
+        Some(font)
+    }
+}
@@ -15,3 +15,20 @@ pub use resolver::*;
 
 pub(crate) mod partial_book;
 pub use partial_book::*;
+
+pub(crate) mod glyph_pack;
+pub use glyph_pack::*;
+
+pub(crate) mod report;
+pub use report::*;
+
+pub(crate) mod usage;
+pub use usage::*;
+
+pub(crate) mod sfnt;
+pub use sfnt::font_info_without_coverage;
+
+#[cfg(feature = "font-subset")]
+pub(crate) mod subset;
+#[cfg(feature = "font-subset")]
+pub use subset::*;
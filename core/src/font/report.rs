@@ -0,0 +1,400 @@
+//! Diagnostics about the fonts used by a compiled document.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use typst::{
+    doc::Document,
+    font::FontBook,
+    syntax::{ast, Source, SyntaxNode},
+    World,
+};
+
+use crate::{
+    vector::{
+        ir::{self, span_from_span_id, SpanId, SvgItem},
+        LowerBuilder,
+    },
+    TypstFileId,
+};
+
+/// The families used by typst when no family is requested.
+/// See `typst_library::text::TextElem`, `EquationElem`, and `RawElem`.
+const DEFAULT_FAMILIES: &[&str] = &[
+    "linux libertine",
+    "new computer modern math",
+    "dejavu sans mono",
+];
+
+/// A codepoint which is rendered as `.notdef`, since no font covers it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingGlyph {
+    /// The codepoint which is not covered.
+    pub codepoint: char,
+    /// The family of the font rendering the `.notdef` glyph.
+    pub font: String,
+    /// The number of text items containing the codepoint.
+    pub count: usize,
+    /// The first location rendering the codepoint, in form of `path:line:column`.
+    pub location: Option<String>,
+    /// The span of the first text item containing the codepoint.
+    #[serde(skip)]
+    pub span_id: Option<SpanId>,
+}
+
+/// A font which is used but not requested by the document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FallbackFont {
+    /// The family of the font.
+    pub family: String,
+    /// The number of glyphs rendered by the font.
+    pub glyphs: usize,
+}
+
+/// The report of font resolution of a compiled document, which is collected
+/// by walking the [`ir::TextItem`]s in the lowered document.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontReport {
+    /// The requested families which are not found in the font book.
+    pub unresolved_families: Vec<String>,
+    /// The codepoints rendered as `.notdef`.
+    pub missing_glyphs: Vec<MissingGlyph>,
+    /// The fonts used as fallback.
+    pub fallback_fonts: Vec<FallbackFont>,
+}
+
+impl FontReport {
+    /// Analyze a document compiled by the world.
+    ///
+    /// The requested families are collected from the `font` arguments in the
+    /// sources, e.g. `#set text(font: "Inria Serif")`, see
+    /// [`loaded_sources`] for the scanned sources.
+    pub fn new(world: &dyn World, doc: &Document) -> Self {
+        let mut lower_builder = LowerBuilder::new(doc);
+        let pages = doc.pages.iter().map(|p| lower_builder.lower(p));
+        Self::from_lowered(world, &pages.collect::<Vec<_>>())
+    }
+
+    /// Analyze the lowered pages of a document compiled by the world, which
+    /// saves lowering the document again if the caller has lowered it.
+    pub fn from_lowered(world: &dyn World, pages: &[SvgItem]) -> Self {
+        let sources = loaded_sources(world, pages);
+
+        let requested = sources.values().flat_map(requested_families);
+        let mut report = Self::from_pages(world.book(), requested, pages);
+
+        // resolve the locations of missing glyphs
+        for glyph in report.missing_glyphs.iter_mut() {
+            let Some(span_id) = glyph.span_id else {
+                continue;
+            };
+            let span = span_from_span_id(span_id);
+            let Some(source) = span.id().and_then(|id| sources.get(&id)) else {
+                continue;
+            };
+            let Some(range) = source.range(span) else {
+                continue;
+            };
+            let line = source.byte_to_line(range.start).unwrap_or_default();
+            let column = source.byte_to_column(range.start).unwrap_or_default();
+            glyph.location = Some(format!(
+                "{}:{}:{}",
+                source.id().vpath().as_rootless_path().display(),
+                line + 1,
+                column + 1
+            ));
+        }
+
+        report
+    }
+
+    /// Analyze lowered pages against the font book and the requested
+    /// families.
+    pub fn from_pages<'a>(
+        book: &FontBook,
+        requested: impl IntoIterator<Item = String>,
+        pages: impl IntoIterator<Item = &'a SvgItem>,
+    ) -> Self {
+        let requested = requested
+            .into_iter()
+            .map(|family| family.to_lowercase())
+            .collect::<BTreeSet<_>>();
+
+        let mut missing: BTreeMap<(String, char), (usize, Option<SpanId>)> = BTreeMap::new();
+        let mut used: BTreeMap<String, usize> = BTreeMap::new();
+        for page in pages {
+            collect_text_items(page, &mut |text| {
+                let family = text.font.info().family.clone();
+                *used.entry(family.clone()).or_default() += text.content.glyphs.len();
+
+                let span_id = text.content.span_id;
+                let span_id = (!span_from_span_id(span_id).is_detached()).then_some(span_id);
+                for c in missing_codepoints(text) {
+                    let (count, first_span) = missing.entry((family.clone(), c)).or_default();
+                    *count += 1;
+                    *first_span = first_span.or(span_id);
+                }
+            });
+        }
+
+        let unresolved_families = requested
+            .iter()
+            .filter(|family| book.select_family(family).next().is_none())
+            .cloned()
+            .collect();
+
+        let missing_glyphs = missing
+            .into_iter()
+            .map(|((font, codepoint), (count, span_id))| MissingGlyph {
+                codepoint,
+                font,
+                count,
+                location: None,
+                span_id,
+            })
+            .collect();
+
+        // the families of the fonts selected by the book for the requested
+        // and default families, in the casing of the fonts themselves.
+        let selected = requested
+            .iter()
+            .map(String::as_str)
+            .chain(DEFAULT_FAMILIES.iter().copied())
+            .flat_map(|family| book.select_family(family))
+            .filter_map(|id| book.info(id))
+            .map(|info| info.family.as_str())
+            .collect::<BTreeSet<_>>();
+
+        let fallback_fonts = used
+            .into_iter()
+            .filter(|(family, _)| !selected.contains(family.as_str()))
+            .map(|(family, glyphs)| FallbackFont { family, glyphs })
+            .collect();
+
+        Self {
+            unresolved_families,
+            missing_glyphs,
+            fallback_fonts,
+        }
+    }
+
+    /// Whether nothing is reported.
+    pub fn is_empty(&self) -> bool {
+        self.unresolved_families.is_empty()
+            && self.missing_glyphs.is_empty()
+            && self.fallback_fonts.is_empty()
+    }
+
+    /// Describe the report as warning messages.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        for family in &self.unresolved_families {
+            warnings.push(format!("unknown font family: {family}"));
+        }
+        for glyph in &self.missing_glyphs {
+            let location = match &glyph.location {
+                Some(location) => format!(" at {location}"),
+                None => String::new(),
+            };
+            warnings.push(format!(
+                "no font covers codepoint U+{:04X} ({:?}), rendered as .notdef of {}{}",
+                glyph.codepoint as u32, glyph.codepoint, glyph.font, location
+            ));
+        }
+        for font in &self.fallback_fonts {
+            warnings.push(format!(
+                "font {} is used as fallback for {} glyphs",
+                font.family, font.glyphs
+            ));
+        }
+        warnings
+    }
+}
+
+/// Collect the families requested by the `font` arguments of `text` in the
+/// source, either in calls or in set rules.
+///
+/// Only string literals and arrays of string literals are recognized.
+pub fn requested_families(source: &Source) -> Vec<String> {
+    fn is_text(callee: ast::Expr) -> bool {
+        matches!(callee, ast::Expr::Ident(ident) if ident.as_str() == "text")
+    }
+
+    fn walk(node: &SyntaxNode, families: &mut Vec<String>) {
+        let args = if let Some(set) = node.cast::<ast::SetRule>() {
+            is_text(set.target()).then(|| set.args())
+        } else if let Some(call) = node.cast::<ast::FuncCall>() {
+            is_text(call.callee()).then(|| call.args())
+        } else {
+            None
+        };
+
+        for arg in args.iter().flat_map(|args| args.items()) {
+            let ast::Arg::Named(named) = arg else {
+                continue;
+            };
+            if named.name().as_str() != "font" {
+                continue;
+            }
+            match named.expr() {
+                ast::Expr::Str(family) => families.push(family.get().to_string()),
+                ast::Expr::Array(array) => {
+                    for item in array.items() {
+                        if let ast::ArrayItem::Pos(ast::Expr::Str(family)) = item {
+                            families.push(family.get().to_string());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for child in node.children() {
+            walk(child, families);
+        }
+    }
+
+    let mut families = vec![];
+    walk(source.root(), &mut families);
+    families
+}
+
+/// Collect the sources loaded by the world in compiling the lowered pages.
+///
+/// The sources are the main source, the sources rendering text in the pages,
+/// and the sources imported or included by them with a path literal,
+/// recursively. The entrypoints of packages are not resolved, but the sources
+/// of a package are collected if they are imported by a relative path or
+/// render text.
+pub fn loaded_sources(world: &dyn World, pages: &[SvgItem]) -> HashMap<TypstFileId, Source> {
+    let mut sources = HashMap::new();
+    let main = world.main();
+    sources.insert(main.id(), main);
+    for page in pages {
+        collect_text_items(page, &mut |text| {
+            let Some(id) = span_from_span_id(text.content.span_id).id() else {
+                return;
+            };
+            if !sources.contains_key(&id) {
+                if let Ok(source) = world.source(id) {
+                    sources.insert(id, source);
+                }
+            }
+        });
+    }
+
+    let mut pending = sources.keys().copied().collect::<Vec<_>>();
+    while let Some(id) = pending.pop() {
+        for path in imported_paths(&sources[&id]) {
+            let imported = id.join(&path);
+            if sources.contains_key(&imported) {
+                continue;
+            }
+            if let Ok(source) = world.source(imported) {
+                sources.insert(imported, source);
+                pending.push(imported);
+            }
+        }
+    }
+
+    sources
+}
+
+/// Collect the paths of the files imported or included by the source, which
+/// are given as string literals, except for packages.
+fn imported_paths(source: &Source) -> Vec<String> {
+    fn walk(node: &SyntaxNode, paths: &mut Vec<String>) {
+        let path = if let Some(import) = node.cast::<ast::ModuleImport>() {
+            Some(import.source())
+        } else {
+            node.cast::<ast::ModuleInclude>()
+                .map(|include| include.source())
+        };
+        if let Some(ast::Expr::Str(path)) = path {
+            let path = path.get();
+            if !path.starts_with('@') {
+                paths.push(path.to_string());
+            }
+        }
+
+        for child in node.children() {
+            walk(child, paths);
+        }
+    }
+
+    let mut paths = vec![];
+    walk(source.root(), &mut paths);
+    paths
+}
+
+/// Visit the text items in the item recursively.
+pub(crate) fn collect_text_items(item: &SvgItem, f: &mut impl FnMut(&ir::TextItem)) {
+    match item {
+        SvgItem::Group(group) => {
+            for (_, item) in group.0.iter() {
+                collect_text_items(item, f);
+            }
+        }
+        SvgItem::Transformed(ir::TransformedItem(_, item)) => collect_text_items(item, f),
+        SvgItem::Text(text) => f(text),
+        SvgItem::Image(..) | SvgItem::Link(..) | SvgItem::Path(..) => {}
+    }
+}
+
+/// Get the codepoints of the text item which are rendered as `.notdef`.
+fn missing_codepoints(text: &ir::TextItem) -> Vec<char> {
+    let has_notdef = text
+        .content
+        .glyphs
+        .iter()
+        .any(|(_, _, glyph)| matches!(glyph, ir::GlyphItem::Raw(_, id, _) if id.0 == 0));
+    if !has_notdef {
+        return vec![];
+    }
+
+    let ttf = text.font.ttf();
+    text.content
+        .content
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .filter(|c| ttf.glyph_index(*c).is_none())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requested_families() {
+        let source = Source::detached(
+            r#"#set text(font: "Inria Serif")
+#show raw: set text(font: ("Fira Code", "DejaVu Sans Mono"))
+#text(size: 12pt, font: family)[x]
+#let card(font: "Card Font") = none
+#card(font: "Card Font")"#,
+        );
+        assert_eq!(
+            requested_families(&source),
+            vec!["Inria Serif", "Fira Code", "DejaVu Sans Mono"]
+        );
+    }
+
+    #[test]
+    fn test_imported_paths() {
+        let source = Source::detached(
+            r#"#import "template.typ": conf
+#import "@preview/example:0.1.0": *
+#let chapter = "chapter.typ"
+#include chapter
+#include "appendix/a.typ""#,
+        );
+        assert_eq!(
+            imported_paths(&source),
+            vec!["template.typ", "appendix/a.typ"]
+        );
+    }
+}
@@ -9,7 +9,7 @@ use typst::font::{Font, FontBook, FontInfo};
 
 use crate::{Bytes, FontSlot};
 
-use super::{BufferFontLoader, FontProfile, PartialFontBook};
+use super::{BufferFontLoader, FontGlyphPack, FontProfile, PartialFontBook};
 
 /// A FontResolver can resolve a font by index.
 /// It also reuse FontBook for font-related query.
@@ -152,8 +152,30 @@ impl FontResolverImpl {
         self.fonts = font_slots;
     }
 
-    pub fn add_glyph_packs(&mut self) {
-        todo!()
+    /// Append the glyph packs as font sources.
+    /// The changes take effect after [`Self::rebuild`].
+    pub fn add_glyph_packs(&mut self, packs: Vec<FontGlyphPack>) {
+        let mut font_book = self.partial_book.lock().unwrap();
+        for pack in packs {
+            font_book.push((None, pack.info.clone(), pack.slot()));
+        }
+    }
+
+    /// Create a resolver with the glyph packs as the only font sources.
+    pub fn from_glyph_packs(packs: Vec<FontGlyphPack>) -> Self {
+        let mut book = FontBook::new();
+        let mut fonts = Vec::with_capacity(packs.len());
+        for pack in packs {
+            fonts.push(pack.slot());
+            book.push(pack.info);
+        }
+
+        Self::new(
+            book,
+            Arc::new(Mutex::new(PartialFontBook::default())),
+            fonts,
+            FontProfile::default(),
+        )
     }
 }
 
@@ -0,0 +1,68 @@
+//! Read and write the tables of fonts in OpenType format.
+
+use std::collections::BTreeMap;
+
+use ttf_parser::{RawFace, Tag};
+use typst::font::FontInfo;
+
+/// The tables of a font, indexed by their tags.
+pub(crate) type SfntTables = BTreeMap<[u8; 4], Vec<u8>>;
+
+/// Parse the information of the font in the data, except the coverage, which
+/// is the most costly part for fonts covering many characters.
+///
+/// The coverage of the returned information is empty.
+pub fn font_info_without_coverage(data: &[u8], index: u32) -> Option<FontInfo> {
+    let face = RawFace::parse(data, index).ok()?;
+
+    // the tables read by `FontInfo`, except `cmap`.
+    let mut tables = SfntTables::new();
+    for tag in [
+        b"head", b"hhea", b"maxp", b"name", b"OS/2", b"post", b"MATH", b"fvar",
+    ] {
+        if let Some(table) = face.table(Tag::from_bytes(tag)) {
+            tables.insert(*tag, table.to_vec());
+        }
+    }
+
+    FontInfo::new(&encode_sfnt(0x0001_0000, &tables), 0)
+}
+
+/// Encode the tables in OpenType format.
+pub(crate) fn encode_sfnt(flavor: u32, tables: &SfntTables) -> Vec<u8> {
+    let num_tables = tables.len() as u16;
+    let entry_selector = 15 - num_tables.max(1).leading_zeros() as u16;
+    let search_range = (1 << entry_selector) * 16;
+
+    let mut sfnt = vec![];
+    sfnt.extend(flavor.to_be_bytes());
+    sfnt.extend(num_tables.to_be_bytes());
+    sfnt.extend(search_range.to_be_bytes());
+    sfnt.extend(entry_selector.to_be_bytes());
+    sfnt.extend((num_tables * 16 - search_range).to_be_bytes());
+
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, data) in tables {
+        let checksum = data
+            .chunks(4)
+            .map(|chunk| {
+                let mut word = [0u8; 4];
+                word[..chunk.len()].copy_from_slice(chunk);
+                u32::from_be_bytes(word)
+            })
+            .fold(0u32, u32::wrapping_add);
+
+        sfnt.extend(tag);
+        sfnt.extend(checksum.to_be_bytes());
+        sfnt.extend((offset as u32).to_be_bytes());
+        sfnt.extend((data.len() as u32).to_be_bytes());
+        offset += (data.len() + 3) & !3;
+    }
+
+    for data in tables.values() {
+        sfnt.extend(data);
+        sfnt.resize((sfnt.len() + 3) & !3, 0);
+    }
+
+    sfnt
+}
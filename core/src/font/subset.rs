@@ -0,0 +1,654 @@
+//! Subset fonts into web fonts, which are embedded as `@font-face` data.
+//!
+//! The glyph ids are retained in the subsetted font, and each glyph is mapped
+//! to a codepoint in the supplementary private use area, see
+//! [`subset_glyph_codepoint`]. Thus a renderer can show a glyph with a text
+//! element, regardless of the characters it is shaped from.
+
+use std::io::Write;
+
+use ttf_parser::{GlyphId, RawFace, Tag};
+use typst::font::Font;
+
+use super::{
+    glyph::is_colr_v1_glyph,
+    sfnt::{encode_sfnt, SfntTables},
+};
+
+/// The codepoint where the glyphs start to be mapped.
+pub const SUBSET_CODEPOINT_BASE: u32 = 0xF0000;
+
+/// Get the codepoint mapped to the glyph in the subsetted font.
+pub fn subset_glyph_codepoint(glyph: u16) -> Option<char> {
+    // U+FFFFE and U+FFFFF are noncharacters.
+    if glyph > 0xFFFD {
+        return None;
+    }
+
+    char::from_u32(SUBSET_CODEPOINT_BASE + glyph as u32)
+}
+
+/// Check whether the glyph can be rendered by the subsetted font.
+///
+/// Only outline glyphs in fonts which can be subsetted are supported. Color,
+/// bitmap, and SVG glyphs should be rendered in other ways.
+pub fn is_subsettable_glyph(font: &Font, glyph: u16) -> bool {
+    if subset_glyph_codepoint(glyph).is_none() || !is_subsettable_font(font) {
+        return false;
+    }
+
+    let id = ttf_parser::GlyphId(glyph);
+    let ttf = font.ttf();
+    ttf.glyph_raster_image(id, u16::MAX).is_none() && ttf.glyph_svg_image(id).is_none()
+}
+
+/// Check whether the font can be subsetted.
+pub fn is_subsettable_font(font: &Font) -> bool {
+    can_subset_font(font.clone())
+}
+
+#[comemo::memoize]
+fn can_subset_font(font: Font) -> bool {
+    let tables = font.ttf().tables();
+    if tables.colr.is_some() || (tables.glyf.is_none() && tables.cff.is_none()) {
+        return false;
+    }
+
+    subset_font_woff2(&font, &[0]).is_some()
+}
+
+/// Subset the font to the given glyphs and encode it in WOFF2 format.
+///
+/// Returns `None` if the font cannot be subsetted.
+pub fn subset_font_woff2(font: &Font, glyphs: &[u16]) -> Option<Vec<u8>> {
+    let (flavor, mut tables) = subset_font_tables(font, glyphs, false)?;
+
+    let mut glyphs = glyphs.to_vec();
+    glyphs.sort_unstable();
+    glyphs.dedup();
+    let mapping = glyphs.into_iter().filter_map(|glyph| {
+        let c = subset_glyph_codepoint(glyph)?;
+        Some((c as u32, glyph))
+    });
+    tables.insert(*b"cmap", build_cmap(&mapping.collect::<Vec<_>>()));
+
+    encode_woff2(flavor, &tables)
+}
+
+/// Subset the font to the given glyphs and characters, and encode it in
+/// OpenType format.
+///
+/// Unlike [`subset_font_woff2`], the characters are mapped as is, and the
+/// layout tables are kept, so that the subsetted font can be used to typeset
+/// text consisting of the characters. The glyph ids are retained as well.
+/// The color and bitmap tables are subsetted to the glyphs, or dropped if
+/// none of the glyphs is a color glyph.
+///
+/// Returns `None` if the font cannot be subsetted.
+pub fn subset_font_sfnt(font: &Font, chars: &[char], glyphs: &[u16]) -> Option<Vec<u8>> {
+    let ttf = font.ttf();
+    let mut mapping = chars
+        .iter()
+        .filter_map(|&c| Some((c as u32, ttf.glyph_index(c)?.0)))
+        .collect::<Vec<_>>();
+    mapping.sort_unstable();
+    mapping.dedup();
+
+    let mut glyphs = glyphs.to_vec();
+    glyphs.extend(mapping.iter().map(|(_, glyph)| *glyph));
+    glyphs.push(0);
+    glyphs.sort_unstable();
+    glyphs.dedup();
+
+    let (flavor, mut tables) = subset_font_tables(font, &glyphs, true)?;
+    tables.insert(*b"cmap", build_cmap(&mapping));
+
+    Some(encode_sfnt(flavor, &tables))
+}
+
+/// The tables of color and bitmap glyphs, which are not kept as is, see
+/// [`subset_color_tables`].
+const COLOR_TABLES: [&[u8; 4]; 9] = [
+    b"COLR", b"CPAL", b"SVG ", b"sbix", b"CBDT", b"CBLC", b"EBDT", b"EBLC", b"EBSC",
+];
+
+/// Subset the outlines of the font, and collect the tables of the subsetted
+/// font except `cmap`.
+///
+/// If `keep_all` is set, all of the other tables are kept from the original
+/// font, except that the color and bitmap tables are subsetted to the glyphs,
+/// otherwise only the tables required by web fonts are kept.
+fn subset_font_tables(font: &Font, glyphs: &[u16], keep_all: bool) -> Option<(u32, SfntTables)> {
+    let original = RawFace::parse(font.data(), font.index()).ok()?;
+    let colr = original.table(Tag::from_bytes(b"COLR"));
+
+    // the layers of the color glyphs are kept along with them.
+    let mut glyphs = glyphs.to_vec();
+    if let Some(colr) = colr.filter(|_| keep_all) {
+        let layers = colr_base_glyphs(colr).unwrap_or_default().into_iter();
+        let layers = layers.filter(|(glyph, _)| glyphs.binary_search(glyph).is_ok());
+        let layers = layers.flat_map(|(_, layers)| layers.chunks(4).map(|r| u16_at(r, 0)));
+        let layers = layers.collect::<Option<Vec<_>>>().unwrap_or_default();
+        glyphs.extend(layers);
+        glyphs.sort_unstable();
+        glyphs.dedup();
+    }
+
+    let profile = subsetter::Profile::pdf(&glyphs);
+    let subsetted = subsetter::subset(font.data(), font.index(), profile).ok()?;
+
+    let (flavor, mut tables) = read_sfnt_tables(&subsetted)?;
+
+    // The subsetter only keeps the tables needed by PDF, so the other tables
+    // are restored from the original font.
+    if keep_all {
+        for record in original.table_records {
+            let tag = record.tag.to_bytes();
+            // the signature is invalid after subsetting.
+            if tables.contains_key(&tag) || &tag == b"DSIG" || COLOR_TABLES.contains(&&tag) {
+                continue;
+            }
+            tables.insert(tag, original.table(record.tag)?.to_vec());
+        }
+        subset_color_tables(font, &original, &glyphs, &mut tables);
+    } else {
+        for tag in [b"OS/2", b"head", b"hhea", b"hmtx", b"maxp", b"name"] {
+            if !tables.contains_key(tag) {
+                tables.insert(*tag, original.table(Tag::from_bytes(tag))?.to_vec());
+            }
+        }
+    }
+
+    // Glyph names are dropped, since they cover the glyphs of the original
+    // font.
+    let post = tables.get(b"post").map(Vec::as_slice);
+    let post = post.or_else(|| original.table(Tag::from_bytes(b"post")))?;
+    tables.insert(*b"post", post_v3(post)?);
+
+    tables.remove(b"cmap");
+    Some((flavor, tables))
+}
+
+/// Subset the color and bitmap tables to the sorted glyphs, and drop the
+/// tables not used by any of the glyphs.
+///
+/// `COLR` of version 0, `SVG ` and `sbix` are subsetted. The paint graphs of
+/// `COLR` version 1 and the embedded bitmaps, i.e. `CBDT` and `EBDT`, are
+/// kept as a whole if any of the glyphs uses them.
+fn subset_color_tables(font: &Font, original: &RawFace, glyphs: &[u16], tables: &mut SfntTables) {
+    let table = |tag: &[u8; 4]| original.table(Tag::from_bytes(tag));
+
+    if let Some(colr) = table(b"COLR").and_then(|colr| subset_colr(colr, glyphs)) {
+        tables.insert(*b"COLR", colr);
+        if let Some(cpal) = table(b"CPAL") {
+            tables.insert(*b"CPAL", cpal.to_vec());
+        }
+    }
+    if let Some(svg) = table(b"SVG ").and_then(|svg| subset_svg(svg, glyphs)) {
+        tables.insert(*b"SVG ", svg);
+    }
+
+    let ttf = font.ttf();
+    let sbix = table(b"sbix");
+    if let Some(sbix) = sbix.and_then(|sbix| subset_sbix(sbix, ttf.number_of_glyphs(), glyphs)) {
+        tables.insert(*b"sbix", sbix);
+    }
+
+    // the raster images are looked up in `sbix` first.
+    let has_bitmap = sbix.is_none()
+        && glyphs
+            .iter()
+            .any(|&glyph| ttf.glyph_raster_image(GlyphId(glyph), u16::MAX).is_some());
+    if has_bitmap {
+        for tag in [b"CBDT", b"CBLC", b"EBDT", b"EBLC", b"EBSC"] {
+            if let Some(data) = table(tag) {
+                tables.insert(*tag, data.to_vec());
+            }
+        }
+    }
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// The base glyphs of version 0 in a `COLR` table, with their layer records.
+fn colr_base_glyphs(colr: &[u8]) -> Option<Vec<(u16, &[u8])>> {
+    let num_base_glyphs = u16_at(colr, 2)? as usize;
+    let base_glyphs = u32_at(colr, 4)? as usize;
+    let layers = u32_at(colr, 8)? as usize;
+
+    (0..num_base_glyphs)
+        .map(|i| {
+            let record = base_glyphs + i * 6;
+            let first_layer = layers + u16_at(colr, record + 2)? as usize * 4;
+            let num_layers = u16_at(colr, record + 4)? as usize;
+            let layers = colr.get(first_layer..first_layer + num_layers * 4)?;
+            Some((u16_at(colr, record)?, layers))
+        })
+        .collect()
+}
+
+/// Subset a `COLR` table to the sorted glyphs.
+///
+/// Returns `None` if none of the glyphs is a color glyph.
+fn subset_colr(colr: &[u8], glyphs: &[u16]) -> Option<Vec<u8>> {
+    if u16_at(colr, 0)? != 0 {
+        let colored = glyphs.iter().any(|&g| is_colr_v1_glyph(colr, GlyphId(g)));
+        let colored = colored
+            || colr_base_glyphs(colr)?
+                .iter()
+                .any(|(glyph, _)| glyphs.binary_search(glyph).is_ok());
+        return colored.then(|| colr.to_vec());
+    }
+
+    let mut base_glyphs = vec![];
+    let mut layers = vec![];
+    for (glyph, glyph_layers) in colr_base_glyphs(colr)? {
+        if glyphs.binary_search(&glyph).is_err() {
+            continue;
+        }
+        base_glyphs.extend(glyph.to_be_bytes());
+        base_glyphs.extend((layers.len() as u16 / 4).to_be_bytes());
+        base_glyphs.extend((glyph_layers.len() as u16 / 4).to_be_bytes());
+        layers.extend(glyph_layers);
+    }
+    if base_glyphs.is_empty() {
+        return None;
+    }
+
+    let mut subsetted = vec![];
+    subsetted.extend(0u16.to_be_bytes());
+    subsetted.extend((base_glyphs.len() as u16 / 6).to_be_bytes());
+    subsetted.extend(14u32.to_be_bytes());
+    subsetted.extend((14 + base_glyphs.len() as u32).to_be_bytes());
+    subsetted.extend((layers.len() as u16 / 4).to_be_bytes());
+    subsetted.extend(base_glyphs);
+    subsetted.extend(layers);
+    Some(subsetted)
+}
+
+/// Subset an `SVG ` table to the documents of the sorted glyphs.
+///
+/// Returns `None` if none of the glyphs has a document.
+fn subset_svg(svg: &[u8], glyphs: &[u16]) -> Option<Vec<u8>> {
+    let list = u32_at(svg, 2)? as usize;
+    let num_entries = u16_at(svg, list)? as usize;
+
+    // the documents shared by entries are kept once.
+    let mut documents: Vec<&[u8]> = vec![];
+    let mut entries = vec![];
+    for i in 0..num_entries {
+        let record = list + 2 + i * 12;
+        let start = u16_at(svg, record)?;
+        let end = u16_at(svg, record + 2)?;
+        let next = glyphs.partition_point(|&glyph| glyph < start);
+        if glyphs.get(next).map_or(true, |&glyph| glyph > end) {
+            continue;
+        }
+
+        let offset = list + u32_at(svg, record + 4)? as usize;
+        let document = svg.get(offset..offset + u32_at(svg, record + 8)? as usize)?;
+        let same = |d: &&[u8]| d.as_ptr() == document.as_ptr() && d.len() == document.len();
+        let idx = documents.iter().position(same).unwrap_or_else(|| {
+            documents.push(document);
+            documents.len() - 1
+        });
+        entries.push((start, end, idx));
+    }
+    if entries.is_empty() {
+        return None;
+    }
+
+    let mut offsets = vec![];
+    let mut offset = 2 + 12 * entries.len();
+    for document in &documents {
+        offsets.push(offset);
+        offset += document.len();
+    }
+
+    let mut subsetted = vec![];
+    subsetted.extend(0u16.to_be_bytes());
+    subsetted.extend(10u32.to_be_bytes());
+    subsetted.extend(0u32.to_be_bytes());
+    subsetted.extend((entries.len() as u16).to_be_bytes());
+    for (start, end, idx) in entries {
+        subsetted.extend(start.to_be_bytes());
+        subsetted.extend(end.to_be_bytes());
+        subsetted.extend((offsets[idx] as u32).to_be_bytes());
+        subsetted.extend((documents[idx].len() as u32).to_be_bytes());
+    }
+    for document in documents {
+        subsetted.extend(document);
+    }
+    Some(subsetted)
+}
+
+/// Subset an `sbix` table to the images of the sorted glyphs. The images of
+/// the other glyphs are emptied.
+///
+/// Returns `None` if none of the glyphs has an image.
+fn subset_sbix(sbix: &[u8], num_glyphs: u16, glyphs: &[u16]) -> Option<Vec<u8>> {
+    let num_glyphs = num_glyphs as usize;
+    let num_strikes = u32_at(sbix, 4)? as usize;
+
+    let mut has_image = false;
+    let mut strikes = vec![];
+    for i in 0..num_strikes {
+        let strike = u32_at(sbix, 8 + i * 4)? as usize;
+        let image = |glyph: usize| {
+            let start = u32_at(sbix, strike + 4 + glyph * 4)? as usize;
+            let end = u32_at(sbix, strike + 8 + glyph * 4)? as usize;
+            sbix.get(strike + start..strike + end)
+        };
+
+        let header_len = 4 + (num_glyphs + 1) * 4;
+        let mut subsetted = sbix.get(strike..strike + 4)?.to_vec();
+        let mut images = vec![];
+        for glyph in 0..num_glyphs {
+            subsetted.extend(((header_len + images.len()) as u32).to_be_bytes());
+            if glyphs.binary_search(&(glyph as u16)).is_err() {
+                continue;
+            }
+
+            let mut data = image(glyph)?;
+            // a `dupe` image refers to the image of another glyph, which may
+            // be emptied.
+            if data.get(4..8) == Some(&b"dupe"[..]) {
+                data = image(u16_at(data, 8)? as usize)?;
+            }
+            has_image |= !data.is_empty();
+            images.extend(data);
+        }
+        subsetted.extend(((header_len + images.len()) as u32).to_be_bytes());
+        subsetted.extend(images);
+        strikes.push(subsetted);
+    }
+    if !has_image {
+        return None;
+    }
+
+    let mut subsetted = sbix.get(..8)?.to_vec();
+    let mut offset = 8 + 4 * strikes.len();
+    for strike in &strikes {
+        subsetted.extend((offset as u32).to_be_bytes());
+        offset += strike.len();
+    }
+    for strike in strikes {
+        subsetted.extend(strike);
+    }
+    Some(subsetted)
+}
+
+/// Read the tables of a single font in sfnt format.
+fn read_sfnt_tables(data: &[u8]) -> Option<(u32, SfntTables)> {
+    let u16_at = |i: usize| Some(u16::from_be_bytes(data.get(i..i + 2)?.try_into().ok()?));
+    let u32_at = |i: usize| Some(u32::from_be_bytes(data.get(i..i + 4)?.try_into().ok()?));
+
+    let flavor = u32_at(0)?;
+    let num_tables = u16_at(4)? as usize;
+
+    let mut tables = SfntTables::new();
+    for i in 0..num_tables {
+        let record = 12 + i * 16;
+        let tag = data.get(record..record + 4)?.try_into().ok()?;
+        let offset = u32_at(record + 8)? as usize;
+        let length = u32_at(record + 12)? as usize;
+        tables.insert(tag, data.get(offset..offset + length)?.to_vec());
+    }
+
+    Some((flavor, tables))
+}
+
+/// Convert a `post` table to version 3.0, which has no glyph names.
+fn post_v3(post: &[u8]) -> Option<Vec<u8>> {
+    let mut post = post.get(..32)?.to_vec();
+    post[..4].copy_from_slice(&0x0003_0000u32.to_be_bytes());
+    Some(post)
+}
+
+/// Build a `cmap` table with the mapping from codepoints to glyphs.
+///
+/// The mapping must be sorted and deduplicated.
+fn build_cmap(mapping: &[(u32, u16)]) -> Vec<u8> {
+    // group the codepoints mapped to consecutive glyphs
+    let mut groups: Vec<(u32, u32, u32)> = vec![];
+    for &(c, glyph) in mapping {
+        match groups.last_mut() {
+            Some((start, end, start_glyph))
+                if *end + 1 == c && *start_glyph + (c - *start) == glyph as u32 =>
+            {
+                *end = c
+            }
+            _ => groups.push((c, c, glyph as u32)),
+        }
+    }
+
+    let mut cmap = vec![];
+    let push_u16 = |cmap: &mut Vec<u8>, v: u16| cmap.extend(v.to_be_bytes());
+
+    // header with two encoding records, (3, 1) and (3, 10).
+    push_u16(&mut cmap, 0);
+    push_u16(&mut cmap, 2);
+    push_u16(&mut cmap, 3);
+    push_u16(&mut cmap, 1);
+    cmap.extend(20u32.to_be_bytes());
+    push_u16(&mut cmap, 3);
+    push_u16(&mut cmap, 10);
+    cmap.extend(44u32.to_be_bytes());
+
+    // an empty format 4 subtable, which is required by some platforms.
+    for v in [4, 24, 0, 2, 2, 0, 0, 0xFFFF, 0, 0xFFFF, 1, 0] {
+        push_u16(&mut cmap, v);
+    }
+
+    // a format 12 subtable with the mapping.
+    push_u16(&mut cmap, 12);
+    push_u16(&mut cmap, 0);
+    cmap.extend((16 + 12 * groups.len() as u32).to_be_bytes());
+    cmap.extend(0u32.to_be_bytes());
+    cmap.extend((groups.len() as u32).to_be_bytes());
+    for (start, end, glyph) in groups {
+        cmap.extend(start.to_be_bytes());
+        cmap.extend(end.to_be_bytes());
+        cmap.extend(glyph.to_be_bytes());
+    }
+
+    cmap
+}
+
+/// The known table tags of WOFF2, indexed by their flags.
+const WOFF2_KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Encode the tables in WOFF2 format, without applying any transformation.
+fn encode_woff2(flavor: u32, tables: &SfntTables) -> Option<Vec<u8>> {
+    let mut directory = vec![];
+    let mut font_data = vec![];
+    let mut total_sfnt_size = 12 + 16 * tables.len();
+    for (tag, data) in tables {
+        // the null transform of glyf and loca is version 3, while it is
+        // version 0 for other tables.
+        let transform = if tag == b"glyf" || tag == b"loca" {
+            3 << 6
+        } else {
+            0
+        };
+        match WOFF2_KNOWN_TAGS.iter().position(|t| *t == tag) {
+            Some(idx) => directory.push(transform | idx as u8),
+            None => {
+                directory.push(transform | 63);
+                directory.extend(tag);
+            }
+        }
+        write_uint_base128(&mut directory, data.len() as u32);
+
+        font_data.extend(data);
+        total_sfnt_size += (data.len() + 3) & !3;
+    }
+
+    let compressed = {
+        let mut writer = brotli::CompressorWriter::new(vec![], 4096, 11, 22);
+        writer.write_all(&font_data).ok()?;
+        writer.into_inner()
+    };
+
+    let length = (48 + directory.len() + compressed.len() + 3) & !3;
+    let mut woff2 = Vec::with_capacity(length);
+    woff2.extend(b"wOF2");
+    woff2.extend(flavor.to_be_bytes());
+    woff2.extend((length as u32).to_be_bytes());
+    woff2.extend((tables.len() as u16).to_be_bytes());
+    woff2.extend(0u16.to_be_bytes());
+    woff2.extend((total_sfnt_size as u32).to_be_bytes());
+    woff2.extend((compressed.len() as u32).to_be_bytes());
+    // font version 1.0
+    woff2.extend(1u16.to_be_bytes());
+    woff2.extend(0u16.to_be_bytes());
+    // no metadata and private data
+    woff2.extend([0u8; 20]);
+
+    woff2.extend(directory);
+    woff2.extend(compressed);
+    woff2.resize(length, 0);
+
+    Some(woff2)
+}
+
+/// Write a `UIntBase128` value, see the WOFF2 specification.
+fn write_uint_base128(out: &mut Vec<u8>, value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+    while value != 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    out.extend(bytes.into_iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uint_base128() {
+        let encode = |v| {
+            let mut out = vec![];
+            write_uint_base128(&mut out, v);
+            out
+        };
+
+        assert_eq!(encode(0), vec![0]);
+        assert_eq!(encode(127), vec![127]);
+        assert_eq!(encode(128), vec![0x81, 0]);
+        assert_eq!(encode(63 * 1024), vec![0x83, 0xF8, 0]);
+    }
+
+    #[test]
+    fn test_build_cmap() {
+        let cmap = build_cmap(&[
+            (SUBSET_CODEPOINT_BASE + 1, 1),
+            (SUBSET_CODEPOINT_BASE + 2, 2),
+            (SUBSET_CODEPOINT_BASE + 3, 3),
+            (SUBSET_CODEPOINT_BASE + 7, 7),
+        ]);
+
+        let subtable = &cmap[44..];
+        assert_eq!(&subtable[..2], &12u16.to_be_bytes());
+        // two groups
+        assert_eq!(&subtable[12..16], &2u32.to_be_bytes());
+        assert_eq!(
+            &subtable[16..20],
+            &(SUBSET_CODEPOINT_BASE + 1).to_be_bytes()
+        );
+        assert_eq!(
+            &subtable[20..24],
+            &(SUBSET_CODEPOINT_BASE + 3).to_be_bytes()
+        );
+        assert_eq!(&subtable[24..28], &1u32.to_be_bytes());
+        assert_eq!(
+            &subtable[28..32],
+            &(SUBSET_CODEPOINT_BASE + 7).to_be_bytes()
+        );
+    }
+
+    fn be16(values: &[u16]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn test_subset_colr() {
+        // glyph 3 has layers 10, 11, and glyph 5 has layer 12.
+        let mut colr = be16(&[0, 2, 0, 14, 0, 26, 3]);
+        colr.extend(be16(&[3, 0, 2, 5, 2, 1]));
+        colr.extend(be16(&[10, 0, 11, 1, 12, 2]));
+
+        let base_glyphs = colr_base_glyphs(&colr).unwrap();
+        assert_eq!(base_glyphs.len(), 2);
+        assert_eq!(base_glyphs[0].1, &be16(&[10, 0, 11, 1])[..]);
+
+        let subsetted = subset_colr(&colr, &[5, 12]).unwrap();
+        let mut expected = be16(&[0, 1, 0, 14, 0, 20, 1]);
+        expected.extend(be16(&[5, 0, 1]));
+        expected.extend(be16(&[12, 2]));
+        assert_eq!(subsetted, expected);
+
+        assert_eq!(subset_colr(&colr, &[1, 2, 4]), None);
+    }
+
+    #[test]
+    fn test_subset_svg() {
+        // glyphs 1..=2 and 4 share a document, and glyph 6 has another one.
+        let mut svg = be16(&[0, 0, 10, 0, 0]);
+        svg.extend(be16(&[3]));
+        svg.extend(be16(&[1, 2, 0, 38, 0, 3]));
+        svg.extend(be16(&[4, 4, 0, 38, 0, 3]));
+        svg.extend(be16(&[6, 6, 0, 41, 0, 2]));
+        svg.extend(b"<a>");
+        svg.extend(b"<b");
+
+        let subsetted = subset_svg(&svg, &[0, 2, 4]).unwrap();
+        let mut expected = be16(&[0, 0, 10, 0, 0]);
+        expected.extend(be16(&[2]));
+        expected.extend(be16(&[1, 2, 0, 26, 0, 3]));
+        expected.extend(be16(&[4, 4, 0, 26, 0, 3]));
+        expected.extend(b"<a>");
+        assert_eq!(subsetted, expected);
+
+        assert_eq!(subset_svg(&svg, &[0, 3, 5]), None);
+    }
+
+    #[test]
+    fn test_subset_sbix() {
+        // one strike of three glyphs, where glyph 2 duplicates glyph 0.
+        let mut sbix = be16(&[1, 0, 0, 1, 0, 12]);
+        sbix.extend(be16(&[20, 72]));
+        sbix.extend(be16(&[0, 20, 0, 29, 0, 29, 0, 39]));
+        sbix.extend(be16(&[0, 0]));
+        sbix.extend(b"png \x01");
+        sbix.extend(be16(&[0, 0]));
+        sbix.extend(b"dupe");
+        sbix.extend(be16(&[0]));
+
+        let subsetted = subset_sbix(&sbix, 3, &[2]).unwrap();
+        let mut expected = be16(&[1, 0, 0, 1, 0, 12]);
+        expected.extend(be16(&[20, 72]));
+        expected.extend(be16(&[0, 20, 0, 20, 0, 20, 0, 29]));
+        expected.extend(be16(&[0, 0]));
+        expected.extend(b"png \x01");
+        assert_eq!(subsetted, expected);
+
+        assert_eq!(subset_sbix(&sbix, 3, &[1]), None);
+    }
+}
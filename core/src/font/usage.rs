@@ -0,0 +1,160 @@
+//! Statistics about the font faces used by a compiled document.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use typst::{
+    doc::Document,
+    font::{Font, FontVariant},
+};
+
+use super::{report::collect_text_items, FontGlyphProvider, GlyphProvider};
+use crate::vector::{
+    ir::{GlyphItem, GlyphPackBuilder, SvgItem},
+    GlyphLowerBuilder, LowerBuilder,
+};
+
+/// The usage of a font face in a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFaceUsage {
+    /// The family of the font face.
+    pub family: String,
+    /// The variant of the font face.
+    pub variant: FontVariant,
+    /// The index of the font face in its font file.
+    pub index: u32,
+    /// The number of distinct glyphs used.
+    pub glyphs: usize,
+    /// The number of pages on which the font face is used.
+    pub pages: usize,
+    /// The bytes of the glyphs in the vector output, i.e. the length of the
+    /// outline paths and the size of the images of color glyphs.
+    pub vector_bytes: usize,
+    /// The bytes of the font program embedded in the PDF output, before
+    /// compression.
+    /// It is `None` if the font cannot be subsetted.
+    pub pdf_bytes: Option<usize>,
+}
+
+/// The usage of font faces in a compiled document.
+///
+/// The glyphs are deduplicated by [`GlyphPackBuilder`], as what is done when
+/// building the vector module.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontUsage {
+    /// The used font faces in order of first use, followed by the loaded but
+    /// unused font faces.
+    pub faces: Vec<FontFaceUsage>,
+}
+
+impl FontUsage {
+    /// Collect the usage of font faces in the document.
+    ///
+    /// The `loaded_fonts` are the fonts loaded by the compiler, e.g.
+    /// [`super::FontResolverImpl::loaded_fonts`], which are reported with
+    /// zero usage if no glyph of them is used.
+    pub fn new(doc: &Document, loaded_fonts: impl IntoIterator<Item = Font>) -> Self {
+        let mut lower_builder = LowerBuilder::new(doc);
+        let pages = doc.pages.iter().map(|p| lower_builder.lower(p));
+        let pages = pages.collect::<Vec<_>>();
+
+        Self::from_pages(&pages, loaded_fonts)
+    }
+
+    /// Collect the usage of font faces in the lowered pages.
+    pub fn from_pages<'a>(
+        pages: impl IntoIterator<Item = &'a SvgItem>,
+        loaded_fonts: impl IntoIterator<Item = Font>,
+    ) -> Self {
+        let mut builder = GlyphPackBuilder::default();
+        let mut fonts: BTreeMap<u32, Font> = BTreeMap::new();
+        let mut pages_used: HashMap<u32, usize> = HashMap::new();
+        for page in pages {
+            let mut used = BTreeSet::new();
+            collect_text_items(page, &mut |text| {
+                let font_ref = builder.build_font(&text.font, &text.instance);
+                fonts
+                    .entry(font_ref.idx)
+                    .or_insert_with(|| text.font.clone());
+                used.insert(font_ref.idx);
+
+                for (_, _, glyph) in text.content.glyphs.iter() {
+                    builder.build_glyph(glyph);
+                }
+            });
+            for idx in used {
+                *pages_used.entry(idx).or_default() += 1;
+            }
+        }
+
+        let (_, glyphs) = builder.finalize();
+        let glyph_provider = GlyphProvider::new(FontGlyphProvider::default());
+        let glyph_lower_builder = GlyphLowerBuilder::new(&glyph_provider);
+        let mut glyph_ids: BTreeMap<u32, Vec<u16>> = BTreeMap::new();
+        let mut vector_bytes: BTreeMap<u32, usize> = BTreeMap::new();
+        for (font_idx, glyph) in glyphs {
+            let font_idx = font_idx.0 as u32;
+            if let GlyphItem::Raw(_, id, _) = &glyph {
+                glyph_ids.entry(font_idx).or_default().push(id.0);
+            }
+
+            let bytes = match glyph_lower_builder.lower_glyph(&glyph) {
+                Some(GlyphItem::Outline(outline)) => outline.d.len(),
+                Some(GlyphItem::Image(image)) => image.image.image.data.len(),
+                Some(GlyphItem::Color(color)) => color.layers.iter().map(|l| l.d.len()).sum(),
+                _ => 0,
+            };
+            *vector_bytes.entry(font_idx).or_default() += bytes;
+        }
+
+        let mut faces = fonts
+            .iter()
+            .map(|(idx, font)| {
+                let ids = glyph_ids.remove(idx).unwrap_or_default();
+                FontFaceUsage {
+                    family: font.info().family.clone(),
+                    variant: font.info().variant,
+                    index: font.index(),
+                    glyphs: ids.len(),
+                    pages: pages_used.get(idx).copied().unwrap_or_default(),
+                    vector_bytes: vector_bytes.get(idx).copied().unwrap_or_default(),
+                    pdf_bytes: pdf_font_size(font, &ids),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let used = fonts.into_values().collect::<Vec<_>>();
+        for font in loaded_fonts {
+            if used.contains(&font) {
+                continue;
+            }
+            faces.push(FontFaceUsage {
+                family: font.info().family.clone(),
+                variant: font.info().variant,
+                index: font.index(),
+                glyphs: 0,
+                pages: 0,
+                vector_bytes: 0,
+                pdf_bytes: None,
+            });
+        }
+
+        Self { faces }
+    }
+}
+
+/// Get the size of the font program embedded in PDF, which is subsetted to
+/// the glyphs in the same way as typst.
+#[cfg(feature = "font-subset")]
+fn pdf_font_size(font: &Font, glyphs: &[u16]) -> Option<usize> {
+    let profile = subsetter::Profile::pdf(glyphs);
+    let subsetted = subsetter::subset(font.data(), font.index(), profile).ok()?;
+    Some(subsetted.len())
+}
+
+#[cfg(not(feature = "font-subset"))]
+fn pdf_font_size(_font: &Font, _glyphs: &[u16]) -> Option<usize> {
+    None
+}
@@ -5,6 +5,8 @@ use siphasher::sip128::{Hasher128, SipHasher13};
 
 #[cfg(feature = "rkyv")]
 use rkyv::{Archive, Deserialize as rDeser, Serialize as rSer};
+#[cfg(feature = "vector-serde")]
+use serde::{Deserialize, Serialize};
 
 /// See <https://github.com/rust-lang/rust/blob/master/compiler/rustc_hir/src/stable_hash_impls.rs#L22>
 /// The fingerprint conflicts should be very rare and should be handled by the
@@ -58,6 +60,25 @@ impl Fingerprint {
     }
 }
 
+/// A fingerprint is serialized as a hex string of its 128-bit value, since the
+/// 64-bit integers are not precisely represented by json numbers.
+#[cfg(feature = "vector-serde")]
+impl Serialize for Fingerprint {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:032x}", self.to_u128()))
+    }
+}
+
+#[cfg(feature = "vector-serde")]
+impl<'de> Deserialize<'de> for Fingerprint {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        u128::from_str_radix(&s, 16)
+            .map(Fingerprint::from_u128)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 /// A fingerprint hasher that extends the [`std::hash::Hasher`] trait.
 pub trait FingerprintHasher: std::hash::Hasher {
     /// Finish the fingerprint and return the fingerprint and the data.
@@ -0,0 +1,213 @@
+use std::{collections::HashSet, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use typst::{
+    diag::SourceResult,
+    doc::{Document, Frame, FrameItem, Meta},
+    eval::Value,
+    model::{Content, Introspector, Location},
+    World,
+};
+
+use crate::{vector::LowerBuilder, Exporter};
+
+/// Introspection data of a document, i.e. the pages, the metadata and the
+/// located elements, in a stable schema to be serialized as json.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentIntrospection {
+    /// The title of the document, set by `#set document(title: ..)`.
+    pub title: Option<String>,
+    /// The authors of the document, set by `#set document(author: ..)`.
+    pub author: Vec<String>,
+    /// The pages in order.
+    pub pages: Vec<PageIntrospection>,
+    /// The headings in order of appearance.
+    pub headings: Vec<HeadingIntrospection>,
+    /// The figures in order of appearance.
+    pub figures: Vec<FigureIntrospection>,
+    /// The labelled elements in order of appearance.
+    pub labels: Vec<LabelIntrospection>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PageIntrospection {
+    /// Page width in pt
+    pub width: f32,
+    /// Page height in pt
+    pub height: f32,
+    /// The logical label of the page, e.g. "iv", as shown by PDF viewers.
+    pub label: Option<String>,
+    /// The numbering pattern of the page, e.g. "i".
+    pub numbering: Option<String>,
+}
+
+/// The position of the start of an element.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ElementLocation {
+    /// Which page the element starts on, starting from 1.
+    pub page: u32,
+    /// x coordinate in pt
+    pub x: f32,
+    /// y coordinate in pt
+    pub y: f32,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeadingIntrospection {
+    /// The level of the heading, starting from 1.
+    pub level: u32,
+    /// The plain text of the heading.
+    pub body: String,
+    /// The numbering pattern of the heading, e.g. "1.".
+    pub numbering: Option<String>,
+    /// The label attached to the heading, without angle brackets.
+    pub label: Option<String>,
+    pub location: ElementLocation,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct FigureIntrospection {
+    /// The kind of the figure, e.g. "image", "table" or "raw".
+    pub kind: Option<String>,
+    /// The plain text of the caption.
+    pub caption: Option<String>,
+    /// The label attached to the figure, without angle brackets.
+    pub label: Option<String>,
+    pub location: ElementLocation,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LabelIntrospection {
+    /// The label, without angle brackets.
+    pub label: String,
+    /// The kind of the labelled element, i.e. the name of its element
+    /// function.
+    pub kind: String,
+    pub location: ElementLocation,
+}
+
+impl DocumentIntrospection {
+    pub fn new(doc: &Document) -> Self {
+        let introspector = Introspector::new(&doc.pages);
+
+        let mut res = Self {
+            title: doc.title.as_ref().map(|title| title.to_string()),
+            author: doc.author.iter().map(|author| author.to_string()).collect(),
+            ..Default::default()
+        };
+
+        for frame in &doc.pages {
+            let label = LowerBuilder::lower_page_label(frame);
+            res.pages.push(PageIntrospection {
+                width: frame.width().to_pt() as f32,
+                height: frame.height().to_pt() as f32,
+                label: label.label.map(|label| label.to_string()),
+                numbering: label.numbering.map(|numbering| numbering.to_string()),
+            });
+        }
+
+        // an element broken across regions is visited once per fragment
+        let mut visited = HashSet::new();
+        let mut elements = vec![];
+        for frame in &doc.pages {
+            collect_elements(frame, &mut visited, &mut elements);
+        }
+
+        for elem in elements {
+            let position = introspector.position(elem.location().unwrap());
+            let location = || ElementLocation {
+                page: position.page.get() as u32,
+                x: position.point.x.to_pt() as f32,
+                y: position.point.y.to_pt() as f32,
+            };
+            let label = elem.label().map(|label| label.0.to_string());
+
+            match elem.func().name() {
+                "heading" => res.headings.push(HeadingIntrospection {
+                    level: match elem.field("level") {
+                        Some(Value::Int(level)) => level as u32,
+                        _ => 1,
+                    },
+                    body: field_text(&elem, "body").unwrap_or_default(),
+                    numbering: field_text(&elem, "numbering"),
+                    label: label.clone(),
+                    location: location(),
+                }),
+                "figure" => res.figures.push(FigureIntrospection {
+                    kind: field_text(&elem, "kind"),
+                    caption: field_text(&elem, "caption"),
+                    label: label.clone(),
+                    location: location(),
+                }),
+                _ => {}
+            }
+
+            if let Some(label) = label {
+                res.labels.push(LabelIntrospection {
+                    label,
+                    kind: elem.func().name().to_owned(),
+                    location: location(),
+                });
+            }
+        }
+
+        res
+    }
+}
+
+impl From<&Document> for DocumentIntrospection {
+    fn from(doc: &Document) -> Self {
+        Self::new(doc)
+    }
+}
+
+/// Export the introspection data of a document with an exporter of
+/// [`DocumentIntrospection`], e.g. `JsonExporter<DocumentIntrospection>` of
+/// `typst-ts-serde-exporter`.
+#[derive(Default)]
+pub struct DocumentIntrospectionExporter<E> {
+    exporter: E,
+}
+
+impl<E> DocumentIntrospectionExporter<E> {
+    pub fn new(exporter: E) -> Self {
+        Self { exporter }
+    }
+}
+
+impl<E: Exporter<DocumentIntrospection, T>, T> Exporter<Document, T>
+    for DocumentIntrospectionExporter<E>
+{
+    fn export(&self, world: &dyn World, output: Arc<Document>) -> SourceResult<T> {
+        let introspection = DocumentIntrospection::new(&output);
+        self.exporter.export(world, Arc::new(introspection))
+    }
+}
+
+/// Collect the located elements in a frame, in order of appearance.
+fn collect_elements(frame: &Frame, visited: &mut HashSet<Location>, elements: &mut Vec<Content>) {
+    for (_, item) in frame.items() {
+        match item {
+            FrameItem::Group(group) => collect_elements(&group.frame, visited, elements),
+            FrameItem::Meta(Meta::Elem(elem), _) => {
+                if let Some(location) = elem.location() {
+                    if visited.insert(location) {
+                        elements.push(elem.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Get a field of an element as plain text.
+fn field_text(elem: &Content, name: &str) -> Option<String> {
+    match elem.field(name)? {
+        Value::None | Value::Auto => None,
+        Value::Content(content) => Some(content.plain_text().to_string()),
+        Value::Str(s) => Some(s.as_str().to_owned()),
+        Value::Func(func) => func.name().map(str::to_owned),
+        value => Some(format!("{value:?}")),
+    }
+}
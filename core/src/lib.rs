@@ -12,6 +12,8 @@ pub mod path;
 pub mod annotation;
 pub mod content;
 pub use content::TextContent;
+pub mod introspection;
+pub use introspection::DocumentIntrospection;
 // todo: move me to compiler
 pub mod cache;
 pub mod config;
@@ -43,7 +43,7 @@ impl PathRepr {
     }
 
     #[inline]
-    fn from_item(p: &ir::PathItem) -> Option<Self> {
+    pub(crate) fn from_item(p: &ir::PathItem) -> Option<Self> {
         // todo: stroke
         Self::from_path_data(&p.d)
     }
@@ -128,11 +128,8 @@ pub struct BBoxBuilder {
 impl From<BBoxBuilder> for BBox {
     fn from(s: BBoxBuilder) -> Self {
         let mut grp = BBox::new(BBoxRepr::Group(s.ts.into(), s.inner.into()));
-        if let Some(clipper) = s.clipper {
-            grp = BBox::new(BBoxRepr::Clip((
-                Box::new(PathRepr::from_item(&clipper).unwrap()),
-                grp,
-            )));
+        if let Some(clipper) = s.clipper.as_ref().and_then(PathRepr::from_item) {
+            grp = BBox::new(BBoxRepr::Clip((Box::new(clipper), grp)));
         }
         grp
     }
@@ -144,9 +141,18 @@ impl BBoxBuilder {
         let pos = ir::Point::new(pos, Scalar(0.));
         match glyph {
             ir::GlyphItem::Outline(outline) => {
-                let path = PathRepr::from_path_data(&outline.d).unwrap();
-                self.inner
-                    .push((pos, BBox::new(BBoxRepr::Node(Box::new(path)))))
+                // glyphs without outline, e.g. spaces, have no bbox
+                if let Some(path) = PathRepr::from_path_data(&outline.d) {
+                    self.inner
+                        .push((pos, BBox::new(BBoxRepr::Node(Box::new(path)))))
+                }
+            }
+            ir::GlyphItem::Color(color) => {
+                let d = color.layers.iter().map(|layer| layer.d.as_ref());
+                if let Some(path) = PathRepr::from_path_data(&d.collect::<Vec<_>>().join(" ")) {
+                    self.inner
+                        .push((pos, BBox::new(BBoxRepr::Node(Box::new(path)))))
+                }
             }
             ir::GlyphItem::Image(image_item) => self.inner.push((
                 pos,
@@ -158,6 +164,7 @@ impl BBoxBuilder {
                     })),
                 ))),
             )),
+            ir::GlyphItem::None => {}
             _ => unimplemented!(),
         }
     }
@@ -181,8 +188,9 @@ impl<C> TransformContext<C> for BBoxBuilder {
         self
     }
 
-    fn transform_rotate(self, _ctx: &mut C, _matrix: Scalar) -> Self {
-        todo!()
+    fn transform_rotate(mut self, _ctx: &mut C, matrix: Scalar) -> Self {
+        self.ts = self.ts.post_concat(sk::Transform::from_rotate(matrix.0));
+        self
     }
 
     fn transform_skew(mut self, _ctx: &mut C, matrix: (Ratio, Ratio)) -> Self {
@@ -216,11 +224,12 @@ impl<C: BuildGlyph + RenderVm<Resultant = BBox>> GroupContext<C> for BBoxBuilder
     }
 
     fn render_path(&mut self, _ctx: &mut C, path: &ir::PathItem) {
-        let path = PathRepr::from_item(path).unwrap();
-        self.inner.push((
-            ir::Point::default(),
-            BBox::new(BBoxRepr::Node(Box::new(path))),
-        ))
+        if let Some(path) = PathRepr::from_item(path) {
+            self.inner.push((
+                ir::Point::default(),
+                BBox::new(BBoxRepr::Node(Box::new(path))),
+            ))
+        }
     }
 
     fn render_image(&mut self, _ctx: &mut C, image_item: &ir::ImageItem) {
@@ -339,8 +348,8 @@ impl<'m, 't> FlatIncrRenderVm<'m> for BBoxTask<'m, 't> {
 }
 
 impl BuildGlyph for BBoxTask<'_, '_> {
-    fn build_font(&mut self, font: &Font) -> FontRef {
-        self.glyph_defs.build_font(font)
+    fn build_font(&mut self, font: &Font, instance: &ir::FontInstance) -> FontRef {
+        self.glyph_defs.build_font(font, instance)
     }
 
     fn build_glyph(&mut self, glyph: &ir::GlyphItem) -> GlyphRef {
@@ -0,0 +1,318 @@
+//! Cull the items of a flat module against a viewport, using the bounding
+//! boxes of items cached by fingerprint.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use super::{
+    bbox::{BBox, BBoxTask, ObservableBounds},
+    flat_ir::{FlatSvgItem, GroupRef, Module, Page, TransformedRef},
+    flat_vm::FlatRenderVm,
+    ir::{GlyphPackBuilder, Point, Rect, Scalar, Transform, TransformItem},
+    sk,
+};
+use crate::{
+    font::GlyphProvider,
+    hash::{Fingerprint, FingerprintBuilder},
+};
+
+/// Culls the items of a flat module which do not intersect a viewport.
+///
+/// A group partially inside of the viewport is rebuilt as a new group in the
+/// module, which only keeps the children intersecting the viewport. The
+/// children entirely inside of the viewport are shared with the original
+/// group, so the culled pages can still be rendered incrementally.
+///
+/// The groups built by a call are kept in the module until the next but one
+/// call, since they are referenced by the previous view of the document.
+#[derive(Default)]
+pub struct ViewportCuller {
+    /// Provides glyphs for computing bboxes.
+    glyph_provider: GlyphProvider,
+    /// Stores the fonts and glyphs used by the bbox task.
+    glyph_defs: GlyphPackBuilder,
+    /// Caches the bboxes of items by fingerprint.
+    bbox_cache: HashMap<Fingerprint, BBox>,
+
+    fingerprint_builder: FingerprintBuilder,
+    /// Groups built by the previous call.
+    prev_built: HashSet<Fingerprint>,
+    /// Groups built by the current call.
+    built: HashSet<Fingerprint>,
+}
+
+impl ViewportCuller {
+    /// Get the bounding box of an item in the module.
+    pub fn bbox(&mut self, module: &Module, item: &Fingerprint) -> BBox {
+        if let Some(bbox) = self.bbox_cache.get(item) {
+            return bbox.clone();
+        }
+
+        let mut task = BBoxTask {
+            glyph_provider: self.glyph_provider.clone(),
+            module,
+            glyph_defs: &mut self.glyph_defs,
+            bbox_cache: &mut self.bbox_cache,
+        };
+        task.render_flat_item(item)
+    }
+
+    /// Cull the pages against the viewport, where the pages are placed from
+    /// top to bottom and the viewport is relative to the top of the first
+    /// page.
+    ///
+    /// A page out of the viewport is replaced by an empty page.
+    pub fn cull_pages(&mut self, module: &mut Module, pages: &[Page], viewport: Rect) -> Vec<Page> {
+        self.gc(module);
+
+        let mut page_off = 0.;
+        let mut culled_pages = Vec::with_capacity(pages.len());
+        for page in pages {
+            let ts = Transform::from_translate(Scalar(0.), Scalar(page_off));
+            let page_rect = Rect {
+                lo: Point::new(Scalar(0.), Scalar(page_off)),
+                hi: Point::new(page.size.x, Scalar(page_off + page.size.y.0)),
+            };
+            page_off += page.size.y.0;
+
+            let content = if page_rect.intersect(&viewport).is_empty() {
+                None
+            } else {
+                self.cull_item(module, &page.content, ts, &viewport)
+            };
+            let content = content
+                .unwrap_or_else(|| self.build(module, FlatSvgItem::Group(GroupRef(Arc::new([])))));
+
+            culled_pages.push(Page {
+                content,
+                ..page.clone()
+            });
+        }
+
+        culled_pages
+    }
+
+    /// Cull an item placed by the transform against the viewport.
+    ///
+    /// It returns `None` if the item is entirely out of the viewport, and the
+    /// item itself if nothing is culled.
+    pub fn cull_item(
+        &mut self,
+        module: &mut Module,
+        item: &Fingerprint,
+        ts: Transform,
+        viewport: &Rect,
+    ) -> Option<Fingerprint> {
+        let rect = self.bbox(module, item).realize(ts);
+
+        // the items without bbox, e.g. links, are kept conservatively
+        if rect.is_empty() || rect.intersect(viewport) == rect {
+            return Some(*item);
+        }
+        if rect.intersect(viewport).is_empty() {
+            return None;
+        }
+
+        let culled = match module.get_item(item) {
+            Some(FlatSvgItem::Group(group)) => {
+                let group = group.clone();
+
+                let mut changed = false;
+                let mut children = Vec::with_capacity(group.0.len());
+                for (pos, child) in group.0.iter() {
+                    let ts = ts.pre_translate(pos.x.0, pos.y.0);
+                    match self.cull_item(module, child, ts, viewport) {
+                        Some(culled) => {
+                            changed |= culled != *child;
+                            children.push((*pos, culled));
+                        }
+                        None => changed = true,
+                    }
+                }
+
+                if !changed {
+                    return Some(*item);
+                }
+                FlatSvgItem::Group(GroupRef(children.into()))
+            }
+            Some(FlatSvgItem::Item(TransformedRef(transform, inner))) => {
+                let (transform, inner) = (transform.clone(), *inner);
+
+                let inner_ts = match &transform {
+                    TransformItem::Clip(..) => ts,
+                    TransformItem::Rotate(deg) => {
+                        ts.pre_concat(sk::Transform::from_rotate(deg.0).into())
+                    }
+                    transform => ts.pre_concat(transform.clone().into()),
+                };
+                let culled = self.cull_item(module, &inner, inner_ts, viewport)?;
+
+                if culled == inner {
+                    return Some(*item);
+                }
+                FlatSvgItem::Item(TransformedRef(transform, culled))
+            }
+            _ => return Some(*item),
+        };
+
+        Some(self.build(module, culled))
+    }
+
+    /// Insert a culled item into the module.
+    fn build(&mut self, module: &mut Module, item: FlatSvgItem) -> Fingerprint {
+        let fingerprint = self.fingerprint_builder.resolve(&item);
+
+        // an item is not owned by the culler if it is already in the module
+        let is_new = module.items.insert(fingerprint, item).is_none();
+        if is_new || self.prev_built.contains(&fingerprint) {
+            self.built.insert(fingerprint);
+        }
+
+        fingerprint
+    }
+
+    /// Remove the groups built before the previous call, which are no longer
+    /// referenced by any view of the document.
+    fn gc(&mut self, module: &mut Module) {
+        let stale = std::mem::replace(&mut self.prev_built, std::mem::take(&mut self.built));
+        for fingerprint in stale.difference(&self.prev_built) {
+            module.items.remove(fingerprint);
+        }
+
+        if self.bbox_cache.len() > module.items.len() * 2 {
+            self.bbox_cache.retain(|f, _| module.items.contains_key(f));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vector::{
+        flat_ir::ModuleBuilder,
+        ir::{self, Axes, PathItem, SvgItem},
+        path2d::SvgPath2DBuilder,
+    };
+
+    use super::*;
+
+    fn get_rect_item(x: f32, y: f32, width: f32, height: f32) -> SvgItem {
+        let mut d = SvgPath2DBuilder::default();
+        d.rect(x, y, width, height);
+        let path = PathItem {
+            d: d.0.into(),
+            styles: Default::default(),
+        };
+
+        SvgItem::Path((path, 0))
+    }
+
+    fn viewport(top: f32, bottom: f32) -> Rect {
+        Rect {
+            lo: Point::new(Scalar(0.), Scalar(top)),
+            hi: Point::new(Scalar(10000.), Scalar(bottom)),
+        }
+    }
+
+    fn count_leaves(module: &Module, item: &Fingerprint) -> usize {
+        match module.get_item(item).unwrap() {
+            FlatSvgItem::Group(group) => group.0.iter().map(|(_, c)| count_leaves(module, c)).sum(),
+            FlatSvgItem::Item(TransformedRef(_, inner)) => count_leaves(module, inner),
+            _ => 1,
+        }
+    }
+
+    /// A poster-like page with rows of rects, each row is a nested group.
+    fn build_long_page(rows: usize) -> (Module, Page) {
+        let mut builder = ModuleBuilder::default();
+        let rows = (0..rows).map(|row| {
+            let cells = (0..4).map(|col| {
+                let rect = get_rect_item(col as f32 * 20., 0., 10. + row as f32, 10.);
+                (Point::default(), rect)
+            });
+            let row_item = SvgItem::Group(ir::GroupItem(cells.collect()));
+            (Point::new(Scalar(0.), Scalar(row as f32 * 20.)), row_item)
+        });
+        let content = builder.build(SvgItem::Group(ir::GroupItem(rows.collect())));
+
+        let page = Page {
+            content,
+            size: Axes::new(Scalar(100.), Scalar(20. * 1000.)),
+            label: Default::default(),
+        };
+        (builder.finalize(), page)
+    }
+
+    #[test]
+    fn test_cull_long_page() {
+        let (mut module, page) = build_long_page(1000);
+        assert_eq!(count_leaves(&module, &page.content), 4000);
+
+        let mut culler = ViewportCuller::default();
+        let pages = culler.cull_pages(&mut module, &[page.clone()], viewport(200., 395.));
+        assert_ne!(pages[0].content, page.content);
+        assert_eq!(pages[0].size, page.size);
+        // rows 10 to 19 intersect the viewport
+        assert_eq!(count_leaves(&module, &pages[0].content), 40);
+
+        // the whole page is kept if it is entirely inside of the viewport
+        let pages = culler.cull_pages(&mut module, &[page.clone()], viewport(-1., 20001.));
+        assert_eq!(pages[0].content, page.content);
+    }
+
+    #[test]
+    fn test_cull_transformed_page() {
+        let mut builder = ModuleBuilder::default();
+        let rects = (0..100).map(|i| {
+            let rect = get_rect_item(0., i as f32 * 10., 10., 5.);
+            (Point::default(), rect)
+        });
+        let inner = SvgItem::Group(ir::GroupItem(rects.collect()));
+        let scaled = SvgItem::Transformed(ir::TransformedItem(
+            TransformItem::Scale(Arc::new((2.0.into(), 2.0.into()))),
+            Box::new(inner),
+        ));
+        let content = builder.build(SvgItem::Group(ir::GroupItem(vec![(
+            Point::default(),
+            scaled,
+        )])));
+        let mut module = builder.finalize();
+        let page = Page {
+            content,
+            size: Axes::new(Scalar(100.), Scalar(2000.)),
+            label: Default::default(),
+        };
+
+        // rects 10 to 14 are at 200..210, 220..230, ..., 280..290 in page
+        let mut culler = ViewportCuller::default();
+        let pages = culler.cull_pages(&mut module, &[page], viewport(195., 295.));
+        assert_eq!(count_leaves(&module, &pages[0].content), 5);
+    }
+
+    #[test]
+    fn test_cull_continuous_pages() {
+        let (mut module, page) = build_long_page(10);
+        let pages = vec![page.clone(); 3];
+
+        // the viewport covers the middle of the first page and the top of the
+        // second page, where each page is 20000pt tall.
+        let mut culler = ViewportCuller::default();
+        let culled = culler.cull_pages(&mut module, &pages, viewport(150., 20050.));
+        assert_eq!(count_leaves(&module, &culled[0].content), 8);
+        assert_eq!(count_leaves(&module, &culled[1].content), 12);
+        assert_eq!(count_leaves(&module, &culled[2].content), 0);
+    }
+
+    #[test]
+    fn test_cull_gc() {
+        let (mut module, page) = build_long_page(100);
+        let items = module.items.len();
+
+        let mut culler = ViewportCuller::default();
+        for top in (0..10).map(|i| i as f32 * 100.) {
+            culler.cull_pages(&mut module, &[page.clone()], viewport(top, top + 50.));
+        }
+
+        // only the groups of the last two views are kept
+        assert!(module.items.len() <= items + 4);
+    }
+}
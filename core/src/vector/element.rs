@@ -0,0 +1,115 @@
+//! Map rendered regions back to the located elements of typst.
+
+use typst::doc::{Document, Frame, FrameItem, Meta};
+
+use super::{
+    flat_ir::ElementItem,
+    ir::{EuclidMinMax, Point, Rect, Scalar, Size, Transform},
+};
+
+/// Collects the regions of the located elements in pages, which is attached
+/// to a module as the element layer, i.e.
+/// [`super::flat_ir::ModuleMetadata::Element`].
+///
+/// The region of an element is the bounding box of the size that typst lays
+/// the element out with, transformed to the page and clipped by the enclosing
+/// groups. It is not the bounding box of the rendered content: an inline
+/// element, e.g. a link or a label in text, is located by a zero-sized tag, so
+/// its region is a point at the tag's position. Zero-sized regions are kept,
+/// and only regions clipped out entirely are dropped.
+#[derive(Debug, Default, Clone)]
+pub struct ElementLayerBuilder {
+    items: Vec<ElementItem>,
+}
+
+impl ElementLayerBuilder {
+    /// Build the element layer of a compiled document.
+    pub fn from_document(doc: &Document) -> Self {
+        let mut builder = Self::default();
+        for (page, frame) in doc.pages.iter().enumerate() {
+            builder.build_page(page, frame);
+        }
+        builder
+    }
+
+    /// Collect the located elements in a page frame.
+    pub fn build_page(&mut self, page: usize, frame: &Frame) {
+        self.collect(page as u32, Transform::identity(), None, frame);
+    }
+
+    /// All of the collected elements, in order of appearance.
+    pub fn items(&self) -> &[ElementItem] {
+        &self.items
+    }
+
+    /// Finalize the element layer.
+    pub fn finalize(self) -> Vec<ElementItem> {
+        self.items
+    }
+
+    fn collect(&mut self, page: u32, ts: Transform, clip: Option<Rect>, frame: &Frame) {
+        for (pos, item) in frame.items() {
+            let ts = ts.pre_translate(pos.x.to_f32(), pos.y.to_f32());
+            match item {
+                FrameItem::Group(group) => {
+                    let ts = ts.pre_concat(group.transform.into());
+                    let clip = if group.clips {
+                        let rect = realize_rect(ts, group.frame.size().into());
+                        Some(clip.map_or(rect, |clip| clip.intersect(&rect)))
+                    } else {
+                        clip
+                    };
+                    self.collect(page, ts, clip, &group.frame);
+                }
+                FrameItem::Meta(Meta::Elem(elem), size) if elem.location().is_some() => {
+                    let mut rect = realize_rect(ts, (*size).into());
+                    if let Some(clip) = &clip {
+                        rect = rect.intersect(clip);
+                        // an empty but not inverted region is a point or a line
+                        if rect.lo.x > rect.hi.x || rect.lo.y > rect.hi.y {
+                            continue;
+                        }
+                    }
+
+                    self.items.push(ElementItem {
+                        kind: elem.func().name().into(),
+                        label: elem.label().map(|label| label.0.as_str().into()),
+                        page,
+                        pos: rect.lo,
+                        size: Size::new(
+                            Scalar(rect.hi.x.0 - rect.lo.x.0),
+                            Scalar(rect.hi.y.0 - rect.lo.y.0),
+                        ),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Realize the bounding box of a rectangle at the origin.
+fn realize_rect(ts: Transform, size: Size) -> Rect {
+    let corners = [
+        (0., 0.),
+        (size.x.0, 0.),
+        (0., size.y.0),
+        (size.x.0, size.y.0),
+    ];
+    let mut corners = corners.into_iter().map(|(x, y)| {
+        Point::new(
+            Scalar(ts.sx.0 * x + ts.kx.0 * y + ts.tx.0),
+            Scalar(ts.ky.0 * x + ts.sy.0 * y + ts.ty.0),
+        )
+    });
+
+    let first = corners.next().unwrap();
+    let rect = Rect {
+        lo: first,
+        hi: first,
+    };
+    corners.fold(rect, |rect, p| Rect {
+        lo: rect.lo.min(&p),
+        hi: rect.hi.max(&p),
+    })
+}
@@ -18,6 +18,8 @@ use super::{Module, ModuleView, Page, PageMetadata, SourceMappingNode};
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 #[repr(C)]
 pub enum LayoutRegionNode {
     // next indirection
@@ -138,6 +140,7 @@ pub trait LayoutSelector {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct LayoutRegionRepr<T> {
     pub kind: ImmutStr,
     pub layouts: Vec<(T, LayoutRegionNode)>,
@@ -147,6 +150,8 @@ pub struct LayoutRegionRepr<T> {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum LayoutRegion {
     ByScalar(LayoutRegionRepr<Scalar>),
     ByStr(LayoutRegionRepr<ImmutStr>),
@@ -214,6 +219,7 @@ impl Index<usize> for LayoutRegion {
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct LayoutSourceMapping(pub LayoutRegion);
 
 impl Default for LayoutSourceMapping {
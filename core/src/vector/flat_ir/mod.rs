@@ -28,6 +28,13 @@ pub use layout::*;
 
 #[cfg(feature = "rkyv")]
 use rkyv::{Archive, Deserialize as rDeser, Serialize as rSer};
+#[cfg(feature = "vector-serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "rkyv")]
+use super::stream::ArtifactCompression;
+#[cfg(feature = "rkyv")]
+use crate::error::prelude::ZResult;
 
 use crate::{
     font::{FontGlyphProvider, GlyphProvider},
@@ -39,8 +46,8 @@ use crate::{
 use super::{
     geom::{Abs, Point, Size},
     ir::{
-        DefId, FontItem, FontRef, GlyphItem, GlyphRef, ImageGlyphItem, ImageItem, ImmutStr,
-        LinkItem, OutlineGlyphItem, PathItem, SpanId, TextShape, TransformItem,
+        ColorGlyphItem, DefId, FontItem, FontRef, GlyphItem, GlyphRef, ImageGlyphItem, ImageItem,
+        ImmutStr, LinkItem, OutlineGlyphItem, PathItem, SpanId, TextShape, TransformItem,
     },
 };
 
@@ -48,6 +55,8 @@ use super::{
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum SourceMappingNode {
     Group(Arc<[u64]>),
     Text(SpanId),
@@ -60,6 +69,8 @@ pub enum SourceMappingNode {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum FlatSvgItem {
     None,
     Image(ImageItem),
@@ -74,6 +85,7 @@ pub enum FlatSvgItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct FlatTextItem {
     pub font: FontRef,
     pub content: Arc<FlatTextItemContent>,
@@ -84,6 +96,7 @@ pub struct FlatTextItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct FlatTextItemContent {
     pub content: ImmutStr,
     pub glyphs: Arc<[(Abs, Abs, GlyphRef)]>,
@@ -94,10 +107,13 @@ pub struct FlatTextItemContent {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum FlatGlyphItem {
     None,
     Image(Arc<ImageGlyphItem>),
     Outline(Arc<OutlineGlyphItem>),
+    Color(Arc<ColorGlyphItem>),
 }
 
 impl From<FlatGlyphItem> for GlyphItem {
@@ -105,6 +121,7 @@ impl From<FlatGlyphItem> for GlyphItem {
         match item {
             FlatGlyphItem::Image(item) => GlyphItem::Image(item),
             FlatGlyphItem::Outline(item) => GlyphItem::Outline(item),
+            FlatGlyphItem::Color(item) => GlyphItem::Color(item),
             FlatGlyphItem::None => GlyphItem::None,
         }
     }
@@ -114,24 +131,28 @@ impl From<FlatGlyphItem> for GlyphItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct TransformedRef(pub TransformItem, pub Fingerprint);
 
 /// Flatten group item.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct GroupRef(pub Arc<[(Point, Fingerprint)]>);
 
 /// Flatten mapping fingerprints to svg items.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct ItemPack(pub Vec<(Fingerprint, FlatSvgItem)>);
 
 /// Flatten mapping fingerprints to glyph items.
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct FontPack {
     pub items: Vec<FontItem>,
     pub incremental_base: usize,
@@ -150,6 +171,7 @@ impl From<Vec<FontItem>> for FontPack {
 #[derive(Debug, Clone, Default)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct GlyphPack {
     pub items: Vec<(DefId, FlatGlyphItem)>,
     pub incremental_base: usize,
@@ -178,6 +200,7 @@ impl FromIterator<(GlyphItem, (GlyphRef, FontRef))> for GlyphPack {
                         let t = match t {
                             GlyphItem::Image(i) => FlatGlyphItem::Image(i),
                             GlyphItem::Outline(p) => FlatGlyphItem::Outline(p),
+                            GlyphItem::Color(c) => FlatGlyphItem::Color(c),
                             _ => unreachable!(),
                         };
 
@@ -198,17 +221,57 @@ impl FromIterator<(GlyphItem, (GlyphRef, FontRef))> for GlyphPack {
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct Page {
     /// Unique hash to content
     pub content: Fingerprint,
     /// Page size for cropping content
     pub size: Size,
+    /// Logical label and numbering of the page
+    pub label: PageLabel,
+}
+
+/// The logical label and numbering of a page, which may differ from the
+/// physical index of the page, e.g. "iv" in the front matter.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+pub struct PageLabel {
+    /// The label of the page, e.g. "iv" or "A-3", as shown by PDF viewers.
+    pub label: Option<ImmutStr>,
+    /// The numbering pattern of the page, e.g. "i" or "A-1".
+    /// It is `None` if the page is not numbered or numbered by a function.
+    pub numbering: Option<ImmutStr>,
+}
+
+/// A region on a page rendered from a located element of typst, e.g. a
+/// heading, figure, equation or table.
+///
+/// An element broken across lines or pages results in one item per fragment.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+pub struct ElementItem {
+    /// The kind of the element, i.e. the name of its element function.
+    pub kind: ImmutStr,
+    /// The label attached to the element, without angle brackets.
+    pub label: Option<ImmutStr>,
+    /// The index of the page containing the element.
+    pub page: u32,
+    /// The top-left corner of the bounding box, relative to the page.
+    pub pos: Point,
+    /// The size of the bounding box.
+    pub size: Size,
 }
 
 /// metadata that can be attached to a module.
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 #[repr(C, align(32))]
 pub enum PageMetadata {
     GarbageCollection(Vec<Fingerprint>),
@@ -219,6 +282,7 @@ pub enum PageMetadata {
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct BuildInfo {
     pub version: ImmutStr,
     pub compiler: ImmutStr,
@@ -229,6 +293,8 @@ pub struct BuildInfo {
 #[repr(C, align(32))]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum ModuleMetadata {
     BuildVersion(Arc<BuildInfo>),
     SourceMappingData(Vec<SourceMappingNode>),
@@ -238,6 +304,7 @@ pub enum ModuleMetadata {
     Font(Arc<FontPack>),
     Glyph(Arc<GlyphPack>),
     Layout(Arc<Vec<LayoutRegion>>),
+    Element(Arc<Vec<ElementItem>>),
 }
 
 const _: () = assert!(core::mem::size_of::<ModuleMetadata>() == 32);
@@ -253,6 +320,7 @@ enum MetaIndices {
     Font,
     Glyph,
     Layout,
+    Element,
     Max,
 }
 
@@ -262,11 +330,13 @@ const META_INDICES_MAX: usize = MetaIndices::Max as usize;
 #[derive(Debug)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct FlatModule {
     pub magic: [u8; 8],
     pub metadata: Vec<ModuleMetadata>,
 
     #[with(rkyv::with::Skip)]
+    #[cfg_attr(feature = "vector-serde", serde(skip))]
     meta_indices: [once_cell::sync::OnceCell<usize>; META_INDICES_MAX],
     // pub item_pack: ItemPack,
     // pub glyphs: Vec<(AbsoluteRef, FlatGlyphItem)>,
@@ -292,16 +362,27 @@ impl FlatModule {
         }
     }
 
+    /// Serialize the module into an uncompressed artifact.
     pub fn to_bytes(self: &FlatModule) -> Vec<u8> {
+        self.to_bytes_with(ArtifactCompression::None).unwrap()
+    }
+
+    /// Serialize the module into an artifact, see [`super::stream`] for the
+    /// layout of the artifact.
+    pub fn to_bytes_with(self: &FlatModule, compression: ArtifactCompression) -> ZResult<Vec<u8>> {
+        super::stream::write_artifact(&self.to_archive(), compression)
+    }
+
+    /// Serialize the module into a bare rkyv archive, without the header of
+    /// the artifact.
+    pub fn to_archive(self: &FlatModule) -> rkyv::AlignedVec {
         // Or you can customize your serialization for better performance
         // and compatibility with #![no_std] environments
         use rkyv::ser::{serializers::AllocSerializer, Serializer};
 
         let mut serializer = AllocSerializer::<0>::default();
         serializer.serialize_value(self).unwrap();
-        let bytes = serializer.into_serializer().into_inner();
-
-        bytes.into_vec()
+        serializer.into_serializer().into_inner()
     }
 }
 
@@ -441,12 +522,12 @@ impl Default for MultiSvgDocument {
 
 impl MultiSvgDocument {
     #[cfg(feature = "rkyv")]
-    pub fn from_slice(v: &[u8]) -> Self {
+    pub fn from_slice(v: &[u8]) -> ZResult<Self> {
         type DocStream<'a> = super::stream::BytesModuleStream<'a>;
 
         let mut res = Self::default();
-        res.merge_delta(&DocStream::from_slice(v).checkout_owned());
-        res
+        res.merge_delta(&DocStream::from_slice(v)?.checkout_owned());
+        Ok(res)
     }
 
     pub fn merge_delta(&mut self, v: impl ModuleStream) {
@@ -470,6 +551,7 @@ pub fn flatten_glyphs(
                     let t = match t {
                         GlyphItem::Image(i) => FlatGlyphItem::Image(i),
                         GlyphItem::Outline(p) => FlatGlyphItem::Outline(p),
+                        GlyphItem::Color(c) => FlatGlyphItem::Color(c),
                         _ => unreachable!(),
                     };
 
@@ -525,4 +607,61 @@ mod tests {
         let ret = bytes.into_vec();
         assert_eq!("00010203706e6700f8ffffff04000000f4ffffff030000000a0000000a000000efbeadde000000000000000000000000000000000000000000000000000000000000204100002041c0ffffff", hex::encode(ret));
     }
+
+    /// Test json serialization of the flat module, see `docs/vector-json.md`.
+    #[cfg(feature = "vector-serde")]
+    #[test]
+    fn test_flat_module_json() {
+        use crate::vector::flat_ir::*;
+        use crate::vector::ir::{LinkItem, TransformItem};
+
+        let link = Fingerprint::from_pair(1, 0);
+        let group = Fingerprint::from_pair(2, 0);
+        let items = vec![
+            (
+                link,
+                FlatSvgItem::Link(LinkItem {
+                    href: "https://typst.app".into(),
+                    size: Axes::new(Scalar(10.0), Scalar(5.0)),
+                }),
+            ),
+            (
+                group,
+                FlatSvgItem::Item(TransformedRef(
+                    TransformItem::Scale(Arc::new((Scalar(2.0), Scalar(2.0)))),
+                    link,
+                )),
+            ),
+        ];
+        let page = Page {
+            content: group,
+            size: Axes::new(Scalar(100.0), Scalar(200.0)),
+            label: Default::default(),
+        };
+        let module = FlatModule::new(vec![
+            ModuleMetadata::Item(ItemPack(items)),
+            ModuleMetadata::Layout(Arc::new(vec![LayoutRegion::new_single(
+                LayoutRegionNode::new_pages(vec![page.clone()]),
+            )])),
+        ]);
+
+        let json = serde_json::to_value(&module).unwrap();
+        let items = &json["metadata"][0];
+        assert_eq!(items["t"], "Item");
+        assert_eq!(items["v"][0][0], "00000000000000000000000000000001");
+        assert_eq!(items["v"][0][1]["t"], "Link");
+        assert_eq!(items["v"][0][1]["v"]["href"], "https://typst.app");
+        assert_eq!(items["v"][1][1]["v"][0]["t"], "Scale");
+
+        let layout = &json["metadata"][1]["v"][0];
+        assert_eq!(layout["t"], "ByScalar");
+        let pages = &layout["v"]["layouts"][0][1]["v"][1];
+        assert_eq!(pages[0]["size"]["y"], 200.0);
+
+        let module: FlatModule = serde_json::from_value(json).unwrap();
+        let ModuleMetadata::Layout(layouts) = &module.metadata[1] else {
+            panic!("expected layout metadata");
+        };
+        assert_eq!(layouts[0].unwrap_single().pages_meta().unwrap(), &[page]);
+    }
 }
@@ -11,8 +11,8 @@ use typst::font::Font;
 use crate::{
     hash::{Fingerprint, FingerprintBuilder},
     vector::ir::{
-        AbsoluteRef, BuildGlyph, DefId, FontItem, FontRef, GlyphItem, GlyphPackBuilderImpl,
-        GlyphRef, SvgItem,
+        AbsoluteRef, BuildGlyph, DefId, FontInstance, FontItem, FontRef, GlyphItem,
+        GlyphPackBuilderImpl, GlyphRef, SvgItem,
     },
     TakeAs,
 };
@@ -200,8 +200,8 @@ impl<const ENABLE_REF_CNT: bool> Default for ModuleBuilderImpl<ENABLE_REF_CNT> {
 }
 
 impl<const ENABLE_REF_CNT: bool> BuildGlyph for ModuleBuilderImpl<ENABLE_REF_CNT> {
-    fn build_font(&mut self, font: &Font) -> FontRef {
-        self.glyphs.build_font(font)
+    fn build_font(&mut self, font: &Font, instance: &FontInstance) -> FontRef {
+        self.glyphs.build_font(font, instance)
     }
 
     fn build_glyph(&mut self, glyph: &GlyphItem) -> GlyphRef {
@@ -257,7 +257,7 @@ impl<const ENABLE_REF_CNT: bool> ModuleBuilderImpl<ENABLE_REF_CNT> {
             }
             SvgItem::Link(link) => FlatSvgItem::Link(link),
             SvgItem::Text(text) => {
-                let font = self.build_font(&text.font);
+                let font = self.build_font(&text.font, &text.instance);
                 let glyphs = text
                     .content
                     .glyphs
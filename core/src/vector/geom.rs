@@ -11,12 +11,15 @@ use typst::geom::{
 
 #[cfg(feature = "rkyv")]
 use rkyv::{Archive, Deserialize as rDeser, Serialize as rSer};
+#[cfg(feature = "vector-serde")]
+use serde::{Deserialize, Serialize};
 
 /// Scalar value of Vector representation.
 /// Note: Unlike Typst's Scalar, all lengths with Scalar type are in pt.
 #[derive(Default, Clone, Copy)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct Scalar(pub f32);
 
 impl From<f32> for Scalar {
@@ -133,6 +136,7 @@ pub type Ratio = Scalar;
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct Axes<T> {
     /// The horizontal component.
     pub x: T,
@@ -202,6 +206,7 @@ impl From<TypstPoint> for Point {
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct Transform {
     pub sx: Ratio,
     pub ky: Ratio,
@@ -5,15 +5,17 @@ use typst::doc::Document;
 use crate::{
     error::prelude::*,
     vector::{
+        element::ElementLayerBuilder,
         flat_ir::{flatten_glyphs, FontPack, GlyphPack, ItemPack, LayoutRegion},
+        ir::SvgItem,
         LowerBuilder,
     },
     TakeAs,
 };
 
 use super::flat_ir::{
-    FlatModule, IncrModuleBuilder, LayoutRegionNode, LayoutSourceMapping, Module, ModuleMetadata,
-    MultiSvgDocument, Page, SourceMappingNode, SvgDocument,
+    ElementItem, FlatModule, IncrModuleBuilder, LayoutRegionNode, LayoutSourceMapping, Module,
+    ModuleMetadata, MultiSvgDocument, Page, SourceMappingNode,
 };
 
 /// maintains the data of the incremental rendering at server side
@@ -24,13 +26,19 @@ pub struct IncrDocServer {
 
     /// Expected exact state of the current Compiler.
     /// Initially it is None meaning no completed compilation.
-    doc_view: Option<SvgDocument>,
+    doc_view: Option<Vec<Page>>,
 
     /// Maintaining document build status
     module_builder: IncrModuleBuilder,
 
     /// Optional page source mapping references.
     page_source_mapping: Vec<SourceMappingNode>,
+
+    /// Whether to attach the element layer to the output.
+    should_attach_elements: bool,
+
+    /// The element layer last sent to the client.
+    elements: Arc<Vec<ElementItem>>,
 }
 
 impl IncrDocServer {
@@ -39,8 +47,24 @@ impl IncrDocServer {
         self.should_attach_debug_info = should_attach_debug_info;
     }
 
+    /// Attach the element layer, i.e. the regions of located elements, to
+    /// the output. A delta only carries the layer when it has changed.
+    pub fn set_should_attach_elements(&mut self, should_attach_elements: bool) {
+        self.should_attach_elements = should_attach_elements;
+    }
+
     /// Pack the delta into a binary blob.
     pub fn pack_delta(&mut self, output: Arc<Document>) -> Vec<u8> {
+        let mut lower_builder = LowerBuilder::new(&output);
+        let pages = output.pages.iter().map(|p| lower_builder.lower(p));
+        let pages = pages.collect();
+        self.pack_delta_lowered(output, pages)
+    }
+
+    /// Pack the delta into a binary blob, with the pages of the document
+    /// lowered by the caller, who may use the lowered pages for other
+    /// purposes as well.
+    pub fn pack_delta_lowered(&mut self, output: Arc<Document>, pages: Vec<SvgItem>) -> Vec<u8> {
         self.module_builder.reset();
         self.page_source_mapping.clear();
 
@@ -51,13 +75,13 @@ impl IncrDocServer {
         // it is important to call gc before building pages
         let gc_items = self.module_builder.gc(5 * 2);
 
-        let mut lower_builder = LowerBuilder::new(&output);
         let builder = &mut self.module_builder;
         let pages = output
             .pages
             .iter()
-            .map(|p| {
-                let abs_ref = builder.build(lower_builder.lower(p));
+            .zip(pages)
+            .map(|(p, item)| {
+                let abs_ref = builder.build(item);
                 if self.should_attach_debug_info {
                     self.page_source_mapping.push(SourceMappingNode::Page(
                         (builder.source_mapping.len() - 1) as u64,
@@ -67,10 +91,12 @@ impl IncrDocServer {
                 Page {
                     content: abs_ref,
                     size: p.size().into(),
+                    label: LowerBuilder::lower_page_label(p),
                 }
             })
             .collect::<Vec<_>>();
         let delta = builder.finalize_delta();
+        self.doc_view = Some(pages.clone());
 
         // max, min lifetime current, gc_items
         #[cfg(feature = "debug-gc")]
@@ -113,7 +139,17 @@ impl IncrDocServer {
         let pages = LayoutRegionNode::new_pages(pages.clone());
         let pages = Arc::new(vec![LayoutRegion::new_single(pages)]);
 
-        let delta = FlatModule::new(vec![
+        let elements = if self.should_attach_elements {
+            let elements = ElementLayerBuilder::from_document(&output).finalize();
+            (elements != *self.elements).then(|| {
+                self.elements = Arc::new(elements);
+                self.elements.clone()
+            })
+        } else {
+            None
+        };
+
+        let mut metadata = vec![
             ModuleMetadata::SourceMappingData(delta.source_mapping),
             ModuleMetadata::PageSourceMapping(Arc::new(LayoutSourceMapping::new_single(
                 self.page_source_mapping.clone(),
@@ -123,8 +159,12 @@ impl IncrDocServer {
             ModuleMetadata::Glyph(Arc::new(glyphs)),
             ModuleMetadata::Item(ItemPack(delta.items.clone().into_iter().collect())),
             ModuleMetadata::Layout(pages),
-        ])
-        .to_bytes();
+        ];
+        if let Some(elements) = elements {
+            metadata.push(ModuleMetadata::Element(elements));
+        }
+
+        let delta = FlatModule::new(metadata).to_bytes();
 
         // log::info!("svg render time (incremental bin): {:?}", instant.elapsed());
         [b"diff-v1,", delta.as_slice()].concat()
@@ -132,15 +172,18 @@ impl IncrDocServer {
 
     /// Pack the current entirely into a binary blob.
     pub fn pack_current(&mut self) -> Option<Vec<u8>> {
-        let doc = self.doc_view.as_ref()?;
+        let pages = self.doc_view.as_ref()?;
 
         let (fonts, glyphs) = self.module_builder.glyphs.finalize();
         let glyphs = flatten_glyphs(glyphs);
 
-        let pages = LayoutRegionNode::new_pages(doc.pages.clone());
+        let pages = LayoutRegionNode::new_pages(pages.clone());
         let pages = Arc::new(vec![LayoutRegion::new_single(pages)]);
 
-        let delta = FlatModule::new(vec![
+        let items = self.module_builder.items.iter();
+        let items = items.map(|(f, (_, i))| (*f, i.clone())).collect();
+
+        let mut metadata = vec![
             ModuleMetadata::SourceMappingData(self.module_builder.source_mapping.clone()),
             ModuleMetadata::PageSourceMapping(Arc::new(LayoutSourceMapping::new_single(
                 self.page_source_mapping.clone(),
@@ -148,10 +191,14 @@ impl IncrDocServer {
             // todo: correct incremental_base
             ModuleMetadata::Font(Arc::new(fonts.into())),
             ModuleMetadata::Glyph(Arc::new(glyphs.into())),
-            ModuleMetadata::Item(ItemPack(doc.module.items.clone().into_iter().collect())),
+            ModuleMetadata::Item(ItemPack(items)),
             ModuleMetadata::Layout(pages),
-        ])
-        .to_bytes();
+        ];
+        if self.should_attach_elements {
+            metadata.push(ModuleMetadata::Element(self.elements.clone()));
+        }
+
+        let delta = FlatModule::new(metadata).to_bytes();
         Some([b"new,", delta.as_slice()].concat())
     }
 }
@@ -168,6 +215,8 @@ pub struct IncrDocClient {
     pub source_mapping_data: Vec<SourceMappingNode>,
     /// Optional page source mapping references.
     pub page_source_mappping: LayoutSourceMapping,
+    /// Optional element layer of the document.
+    pub elements: Vec<ElementItem>,
 }
 
 impl IncrDocClient {
@@ -182,6 +231,9 @@ impl IncrDocClient {
                 ModuleMetadata::PageSourceMapping(data) => {
                     self.page_source_mappping = data.take();
                 }
+                ModuleMetadata::Element(data) => {
+                    self.elements = data.take();
+                }
                 _ => {}
             }
         }
@@ -228,6 +280,24 @@ impl<'a> IncrDocClientKern<'a> {
         layout.and_then(LayoutRegionNode::pages_meta)
     }
 
+    /// Get the regions of located elements, if the element layer is
+    /// attached by server.
+    pub fn elements(&self) -> &[ElementItem] {
+        &self.0.elements
+    }
+
+    /// Get the regions of the elements with the given label.
+    pub fn elements_by_label<'b>(
+        &self,
+        label: &'b str,
+    ) -> impl Iterator<Item = &'a ElementItem> + 'b
+    where
+        'a: 'b,
+    {
+        let elements = self.0.elements.iter();
+        elements.filter(move |e| e.label.as_deref() == Some(label))
+    }
+
     /// Get estimated width of the document (in flavor of PDF Viewer).
     pub fn doc_width(&self) -> Option<f32> {
         let view = self.pages_meta()?.iter();
@@ -1,16 +1,22 @@
 use core::fmt;
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 #[cfg(feature = "rkyv")]
 use rkyv::{Archive, Deserialize as rDeser, Serialize as rSer};
+#[cfg(feature = "vector-serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "vector-serde")]
+use serde_with::{base64::Base64, serde_as};
 
 use base64::Engine;
+use once_cell::sync::Lazy;
 use ttf_parser::GlyphId;
 use typst::{
     font::Font,
     image::{ImageFormat, RasterFormat, VectorFormat},
+    syntax::{FileId, Span},
 };
 
 use crate::{
@@ -38,6 +44,7 @@ fn as_svg_id(b: &[u8], prefix: &'static str) -> String {
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct DefId(pub u64);
 
 impl DefId {
@@ -57,6 +64,7 @@ impl DefId {
 #[derive(Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct AbsoluteRef {
     /// The fingerprint of the item.
     pub fingerprint: Fingerprint,
@@ -98,6 +106,7 @@ impl AbsoluteRef {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct FontRef {
     /// The hash of the font to avoid collision.
     pub hash: u32,
@@ -112,6 +121,7 @@ pub struct FontRef {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct GlyphRef {
     /// The hash of the font to avoid collision.
     pub font_hash: u32,
@@ -128,6 +138,83 @@ impl GlyphRef {
 
 pub type SpanId = u64;
 
+const SPAN_BITS: u64 = 48;
+
+/// The files referenced by [`SpanId`]s, indexed by the file part of the ids.
+///
+/// The raw number of a [`FileId`] is private to typst, so the files are
+/// interned again here. Only file ids created by typst are stored, thus a
+/// decoded span never refers to a file unknown to the world's interner.
+///
+/// Like the interner of typst, it lives as long as the process and is never
+/// freed. It holds as many files as typst does, but once it is full, the
+/// spans of new files are encoded as detached with a warning.
+static SPAN_FILES: Lazy<RwLock<SpanFileInterner>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct SpanFileInterner {
+    to_id: HashMap<FileId, u16>,
+    from_id: Vec<FileId>,
+    /// Whether a file is rejected since the interner is full.
+    saturated: bool,
+}
+
+impl SpanFileInterner {
+    fn intern(id: FileId) -> Option<u16> {
+        if let Some(idx) = SPAN_FILES.read().unwrap().to_id.get(&id) {
+            return Some(*idx);
+        }
+
+        let mut interner = SPAN_FILES.write().unwrap();
+        if let Some(idx) = interner.to_id.get(&id) {
+            return Some(*idx);
+        }
+        let Ok(idx) = u16::try_from(interner.from_id.len()) else {
+            if !interner.saturated {
+                interner.saturated = true;
+                log::warn!("too many source files to map spans, spans of new files are detached");
+            }
+            return None;
+        };
+        interner.to_id.insert(id, idx);
+        interner.from_id.push(id);
+        Some(idx)
+    }
+
+    fn get(idx: u16) -> Option<FileId> {
+        SPAN_FILES
+            .read()
+            .unwrap()
+            .from_id
+            .get(idx as usize)
+            .copied()
+    }
+}
+
+/// Encode a [`Span`] into a [`SpanId`], which is stored in the svg items.
+pub fn span_id_from_span(span: &Span) -> SpanId {
+    // detached spans, or spans of too many files, are encoded as detached.
+    let Some(file_id) = span.id().and_then(SpanFileInterner::intern) else {
+        return 0;
+    };
+    ((file_id as u64) << SPAN_BITS) | span.number()
+}
+
+/// Decode a [`SpanId`] back into a [`Span`].
+/// See [`span_id_from_span`].
+pub fn span_from_span_id(span_id: SpanId) -> Span {
+    let number = span_id & ((1 << SPAN_BITS) - 1);
+    // numbers below 2 are reserved for detached spans
+    if number < 2 {
+        return Span::detached();
+    }
+
+    match SpanFileInterner::get((span_id >> SPAN_BITS) as u16) {
+        Some(file_id) => Span::new(file_id, number),
+        None => Span::detached(),
+    }
+}
+
 /// A Svg item that is specialized for representing [`typst::doc::Document`] or
 /// its subtypes.
 #[derive(Debug, Clone)]
@@ -141,11 +228,14 @@ pub enum SvgItem {
 }
 
 /// Data of an `<image/>` element.
+#[cfg_attr(feature = "vector-serde", serde_as)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct Image {
     /// The encoded image data.
+    #[cfg_attr(feature = "vector-serde", serde_as(as = "Base64"))]
     pub data: Vec<u8>,
     /// The format of the encoded `buffer`.
     pub format: ImmutStr,
@@ -213,6 +303,7 @@ impl StaticHash128 for Image {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct ImageItem {
     /// The source image data.
     pub image: Arc<Image>,
@@ -224,6 +315,7 @@ pub struct ImageItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct LinkItem {
     /// The target of the link item.
     pub href: ImmutStr,
@@ -235,6 +327,7 @@ pub struct LinkItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct PathItem {
     /// The path instruction.
     pub d: ImmutStr,
@@ -247,6 +340,8 @@ pub struct PathItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum PathStyle {
     /// `fill` attribute.
     /// See <https://developer.mozilla.org/en-US/docs/Web/SVG/Attribute/fill>
@@ -286,6 +381,8 @@ pub enum PathStyle {
 pub struct TextItem {
     /// The font of the text item.
     pub font: Font,
+    /// The instance of the font if it is a variable font.
+    pub instance: FontInstance,
     /// The content of the text item.
     pub content: Arc<TextItemContent>,
     /// The shape of the text item.
@@ -310,6 +407,7 @@ pub struct TextItemContent {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct ImageGlyphItem {
     pub ts: Transform,
     pub image: ImageItem,
@@ -319,11 +417,38 @@ pub struct ImageGlyphItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct OutlineGlyphItem {
     pub ts: Option<Transform>,
     pub d: ImmutStr,
 }
 
+/// A layer of a [`ColorGlyphItem`].
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+pub struct ColorGlyphLayer {
+    /// The outline of the layer in path instructions.
+    pub d: ImmutStr,
+    /// The fill of the layer in css format.
+    /// It is `None` if the layer is painted with the fill of the text.
+    pub fill: Option<ImmutStr>,
+}
+
+/// A glyph item consisting of colored layers, which is generated from the
+/// `COLR` and `CPAL` tables of color fonts. Only the layers of `COLR` version
+/// 0 are supported, and the glyphs painted by the paint graphs of version 1
+/// fall back to their outlines.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+pub struct ColorGlyphItem {
+    /// The layers painted from bottom to top.
+    pub layers: Vec<ColorGlyphLayer>,
+}
+
 /// A glyph item.
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum GlyphItem {
@@ -331,7 +456,9 @@ pub enum GlyphItem {
 
     /// Raw glyph representation.
     /// The raw glyphs is generated in lowering stage.
-    Raw(Font, GlyphId),
+    /// The glyph is outlined at the instance of the font, so that the glyphs
+    /// of different instances of a variable font are cached separately.
+    Raw(Font, GlyphId, FontInstance),
 
     /// Glyphs in SVG or Bitmap format.
     Image(Arc<ImageGlyphItem>),
@@ -339,6 +466,9 @@ pub enum GlyphItem {
     /// Glyphs in path instructions, known as the "d" attribute of a
     /// `<path/>` element.
     Outline(Arc<OutlineGlyphItem>),
+
+    /// Glyphs in colored layers of path instructions.
+    Color(Arc<ColorGlyphItem>),
 }
 
 impl GlyphItem {
@@ -362,6 +492,7 @@ impl GlyphItem {
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct FontItem {
     /// The hash of the font to avoid global collision.
     pub fingerprint: Fingerprint,
@@ -373,12 +504,15 @@ pub struct FontItem {
     pub descender: Abs,
     pub unit_per_em: Abs,
     pub vertical: bool,
+    /// The instance of the font if it is a variable font.
+    pub instance: FontInstance,
 }
 
-impl From<Font> for FontItem {
-    fn from(font: Font) -> Self {
-        let hash = fxhash::hash32(&font);
-        let fingerprint = Fingerprint::from_u128(item_hash128(&font));
+impl From<(Font, FontInstance)> for FontItem {
+    fn from(key: (Font, FontInstance)) -> Self {
+        let hash = fxhash::hash32(&key);
+        let fingerprint = Fingerprint::from_u128(item_hash128(&key));
+        let (font, instance) = key;
 
         Self {
             fingerprint,
@@ -388,14 +522,49 @@ impl From<Font> for FontItem {
             descender: Scalar(font.metrics().descender.get() as f32),
             unit_per_em: Scalar(font.units_per_em() as f32),
             vertical: false, // todo: check vertical
+            instance,
         }
     }
 }
 
+/// The axis coordinates of an instance of a variable font, in form of
+/// `(axis tag, value)` in the order of the axes in the `fvar` table.
+///
+/// It is empty if the font is not a variable font.
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
+#[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+pub struct FontInstance(pub Arc<[(u32, Scalar)]>);
+
+impl FontInstance {
+    /// Get the default instance of the font, i.e. the default values of its
+    /// variation axes.
+    pub fn default_of(font: &Font) -> Self {
+        let axes = font.ttf().variation_axes().into_iter();
+        Self(
+            axes.map(|axis| (axis.tag.0, Scalar(axis.def_value)))
+                .collect(),
+        )
+    }
+
+    /// Whether the instance is the default instance of the font.
+    pub fn is_default_of(&self, font: &Font) -> bool {
+        self.0.is_empty() || *self == Self::default_of(font)
+    }
+}
+
+impl Default for FontInstance {
+    fn default() -> Self {
+        Self(Arc::new([]))
+    }
+}
+
 /// The shape metadata of a [`TextItem`].
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
 pub struct TextShape {
     /// The direction of the text item.
     pub dir: ImmutStr,
@@ -418,6 +587,8 @@ pub struct GroupItem(pub Vec<(Point, SvgItem)>);
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 #[cfg_attr(feature = "rkyv", derive(Archive, rDeser, rSer))]
 #[cfg_attr(feature = "rkyv-validation", archive(check_bytes))]
+#[cfg_attr(feature = "vector-serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "vector-serde", serde(tag = "t", content = "v"))]
 pub enum TransformItem {
     /// `matrix` transform.
     Matrix(Arc<Transform>),
@@ -464,10 +635,12 @@ pub type GlyphPack = Vec<(DefId, GlyphItem)>;
 
 #[derive(Clone)]
 pub struct GlyphPackBuilderImpl<const ENABLE_REF_CNT: bool = false> {
-    /// Intermediate representation of an incompleted font pack.
-    font_mapping: HashMap<Font, FontRef>,
+    /// Intermediate representation of an incompleted font pack, keyed by the
+    /// font and its instance.
+    font_mapping: HashMap<(Font, FontInstance), FontRef>,
 
-    /// Intermediate representation of an incompleted glyph pack.
+    /// Intermediate representation of an incompleted glyph pack, keyed by
+    /// the glyph and the instance of its font.
     glyph_defs: HashMap<GlyphItem, (GlyphRef, FontRef)>,
 
     pub lifetime: u64,
@@ -506,16 +679,17 @@ impl<const ENABLE_REF_CNT: bool> GlyphPackBuilderImpl<ENABLE_REF_CNT> {
         (fonts, glyphs)
     }
 
-    pub fn build_font(&mut self, font: &Font) -> FontRef {
-        if let Some(id) = self.font_mapping.get(font) {
+    pub fn build_font(&mut self, font: &Font, instance: &FontInstance) -> FontRef {
+        let key = (font.clone(), instance.clone());
+        if let Some(id) = self.font_mapping.get(&key) {
             return id.clone();
         }
 
         let id = FontRef {
-            hash: fxhash::hash32(font),
+            hash: fxhash::hash32(&key),
             idx: self.font_mapping.len() as u32,
         };
-        self.font_mapping.insert(font.clone(), id.clone());
+        self.font_mapping.insert(key, id.clone());
         if ENABLE_REF_CNT {
             self.incr_fonts.push(self.lifetime);
         }
@@ -527,12 +701,13 @@ impl<const ENABLE_REF_CNT: bool> GlyphPackBuilderImpl<ENABLE_REF_CNT> {
             return id.0.clone();
         }
 
-        let g = match glyph {
-            GlyphItem::Raw(g, _) => g,
+        // the raw glyph is keyed by the glyph and the instance of its font
+        let (font, instance) = match glyph {
+            GlyphItem::Raw(font, _, instance) => (font, instance),
             _ => todo!(),
         };
 
-        let font_ref = self.build_font(g);
+        let font_ref = self.build_font(font, instance);
 
         let glyph_idx = self.glyph_defs.len() as u32;
 
@@ -577,7 +752,7 @@ pub trait FontIndice<'m> {
 }
 
 pub trait BuildGlyph {
-    fn build_font(&mut self, font: &Font) -> FontRef;
+    fn build_font(&mut self, font: &Font, instance: &FontInstance) -> FontRef;
     fn build_glyph(&mut self, glyph: &GlyphItem) -> GlyphRef;
 }
 
@@ -4,9 +4,15 @@ use std::io::Read;
 use std::sync::Arc;
 
 use once_cell::sync::OnceCell;
-use typst::doc::{Destination, Document, Frame, FrameItem, GroupItem, Meta, Position, TextItem};
+use typst::doc::{
+    Destination, Document, Frame, FrameItem, GroupItem, Meta, PdfPageLabel, PdfPageLabelStyle,
+    Position, TextItem,
+};
+use typst::eval::Value;
 use typst::font::Font;
-use typst::geom::{Dir, FixedStroke, Geometry, LineCap, LineJoin, Paint, PathItem, Shape, Size};
+use typst::geom::{
+    Color, Dir, FixedStroke, Geometry, LineCap, LineJoin, Paint, PathItem, RgbaColor, Shape, Size,
+};
 use typst::image::Image;
 
 use ttf_parser::OutlineBuilder;
@@ -14,8 +20,12 @@ use typst::model::Introspector;
 use typst::syntax::Span;
 
 use super::{
+    flat_ir::PageLabel,
     geom::Scalar,
-    ir::{self, GlyphItem, ImageGlyphItem, OutlineGlyphItem, SvgItem, TransformItem},
+    ir::{
+        self, span_id_from_span, ColorGlyphItem, ColorGlyphLayer, GlyphItem, ImageGlyphItem,
+        OutlineGlyphItem, SvgItem, TransformItem,
+    },
 };
 use super::{
     path2d::SvgPath2DBuilder,
@@ -53,10 +63,10 @@ impl LowerBuilder {
                 FrameItem::Group(group) => self.lower_group(group),
                 FrameItem::Text(text) => Self::lower_text(text),
                 FrameItem::Shape(shape, span_id) => {
-                    SvgItem::Path((Self::lower_shape(shape), hack_span_id_to_u64(span_id)))
+                    SvgItem::Path((Self::lower_shape(shape), span_id_from_span(span_id)))
                 }
                 FrameItem::Image(image, size, span_id) => {
-                    SvgItem::Image((lower_image(image, *size), hack_span_id_to_u64(span_id)))
+                    SvgItem::Image((lower_image(image, *size), span_id_from_span(span_id)))
                 }
                 FrameItem::Meta(meta, size) => match meta {
                     Meta::Link(lnk) => match lnk {
@@ -68,7 +78,7 @@ impl LowerBuilder {
                             Self::lower_position(dest, *size)
                         }
                     },
-                    // todo: support page label
+                    // page labels are lowered by `lower_page_label`
                     Meta::PdfPageLabel(..)
                     | Meta::Elem(..)
                     | Meta::PageNumbering(..)
@@ -99,6 +109,32 @@ impl LowerBuilder {
         SvgItem::Group(ir::GroupItem(items))
     }
 
+    /// Lower the logical label and numbering of a page frame.
+    pub fn lower_page_label(frame: &Frame) -> PageLabel {
+        fn walk(frame: &Frame, page_label: &mut PageLabel) {
+            for (_, item) in frame.items() {
+                match item {
+                    FrameItem::Group(group) => walk(&group.frame, page_label),
+                    FrameItem::Meta(Meta::PdfPageLabel(label), _) => {
+                        if page_label.label.is_none() {
+                            page_label.label = format_page_label(label).map(From::from);
+                        }
+                    }
+                    FrameItem::Meta(Meta::PageNumbering(Value::Str(numbering)), _) => {
+                        if page_label.numbering.is_none() {
+                            page_label.numbering = Some(numbering.as_str().into());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut page_label = PageLabel::default();
+        walk(frame, &mut page_label);
+        page_label
+    }
+
     /// Lower a group frame with optional transform and clipping into svg item.
     fn lower_group(&mut self, group: &GroupItem) -> SvgItem {
         let mut inner = self.lower_frame(&group.frame);
@@ -159,13 +195,16 @@ impl LowerBuilder {
     /// Lower a text into svg item.
     // #[comemo::memoize]
     pub(super) fn lower_text(text: &TextItem) -> SvgItem {
+        // Typst lays out the text of a variable font at its default instance.
+        let instance = ir::FontInstance::default_of(&text.font);
+
         let mut glyphs = Vec::with_capacity(text.glyphs.len());
         for glyph in &text.glyphs {
             let id = GlyphId(glyph.id);
             glyphs.push((
                 glyph.x_offset.at(text.size).into(),
                 glyph.x_advance.at(text.size).into(),
-                ir::GlyphItem::Raw(text.font.clone(), id),
+                ir::GlyphItem::Raw(text.font.clone(), id, instance.clone()),
             ));
         }
 
@@ -179,12 +218,13 @@ impl LowerBuilder {
             .iter()
             .filter(|g| g.span.0 != Span::detached())
             .map(|g| &g.span.0)
-            .map(hack_span_id_to_u64)
+            .map(span_id_from_span)
             .max()
-            .unwrap_or_else(|| hack_span_id_to_u64(&Span::detached()));
+            .unwrap_or_else(|| span_id_from_span(&Span::detached()));
 
         SvgItem::Text(ir::TextItem {
             font: text.font.clone(),
+            instance,
             content: Arc::new(ir::TextItemContent {
                 content: glyph_chars.into(),
                 glyphs,
@@ -311,14 +351,20 @@ impl<'a> GlyphLowerBuilder<'a> {
 
     pub fn lower_glyph(&self, glyph_item: &GlyphItem) -> Option<GlyphItem> {
         match glyph_item {
-            GlyphItem::Raw(font, id) => {
+            GlyphItem::Raw(font, id, instance) => {
                 let id = *id;
                 self.lower_svg_glyph(font, id)
                     .map(GlyphItem::Image)
                     .or_else(|| self.lower_bitmap_glyph(font, id).map(GlyphItem::Image))
-                    .or_else(|| self.lower_outline_glyph(font, id).map(GlyphItem::Outline))
+                    .or_else(|| self.lower_color_glyph(font, id).map(GlyphItem::Color))
+                    .or_else(|| {
+                        self.lower_outline_glyph(font, id, instance)
+                            .map(GlyphItem::Outline)
+                    })
+            }
+            GlyphItem::Image(..) | GlyphItem::Outline(..) | GlyphItem::Color(..) => {
+                Some(glyph_item.clone())
             }
-            GlyphItem::Image(..) | GlyphItem::Outline(..) => Some(glyph_item.clone()),
             GlyphItem::None => Some(GlyphItem::None),
         }
     }
@@ -401,9 +447,38 @@ impl<'a> GlyphLowerBuilder<'a> {
         }))
     }
 
+    /// Lower a glyph consisting of colored layers, e.g. color emoji in `COLR`
+    /// version 0 format, into svg item.
+    fn lower_color_glyph(&self, font: &Font, id: GlyphId) -> Option<Arc<ColorGlyphItem>> {
+        let layers = self.gp.color_glyph(font, id)?;
+
+        let layers = layers
+            .into_iter()
+            .map(|(d, color)| ColorGlyphLayer {
+                d: d.into(),
+                fill: color.map(|c| {
+                    let color: Color = RgbaColor::new(c.red, c.green, c.blue, c.alpha).into();
+                    color.to_css().into()
+                }),
+            })
+            .collect();
+
+        Some(Arc::new(ColorGlyphItem { layers }))
+    }
+
     /// Lower an outline glyph into svg text. This is the "normal" case.
-    fn lower_outline_glyph(&self, font: &Font, id: GlyphId) -> Option<Arc<OutlineGlyphItem>> {
-        let d = self.gp.outline_glyph(font, id)?.into();
+    fn lower_outline_glyph(
+        &self,
+        font: &Font,
+        id: GlyphId,
+        instance: &ir::FontInstance,
+    ) -> Option<Arc<OutlineGlyphItem>> {
+        let d = if instance.is_default_of(font) {
+            self.gp.outline_glyph(font, id)?
+        } else {
+            self.gp.outline_glyph_at(font, id, &instance.0)?
+        };
+        let d = d.into();
 
         Some(Arc::new(OutlineGlyphItem { ts: None, d }))
     }
@@ -487,6 +562,72 @@ fn extract_svg_glyph(g: &GlyphProvider, font: &Font, id: GlyphId) -> Option<typs
     Some(image)
 }
 
+/// Format a page label in the same way as PDF viewers, i.e. the prefix
+/// followed by the page number in the style.
+fn format_page_label(label: &PdfPageLabel) -> Option<String> {
+    let prefix = label.prefix.as_deref().unwrap_or_default();
+    let number = match (label.style, label.offset) {
+        (Some(style), Some(number)) => format_page_number(style, number.get()),
+        _ => String::new(),
+    };
+
+    if prefix.is_empty() && number.is_empty() {
+        return None;
+    }
+
+    Some(format!("{prefix}{number}"))
+}
+
+/// Format a page number in the style of PDF page labels.
+fn format_page_number(style: PdfPageLabelStyle, number: usize) -> String {
+    match style {
+        PdfPageLabelStyle::Arabic => number.to_string(),
+        PdfPageLabelStyle::LowerRoman => roman_number(number).to_lowercase(),
+        PdfPageLabelStyle::UpperRoman => roman_number(number),
+        PdfPageLabelStyle::LowerAlpha => alpha_number(number).to_lowercase(),
+        PdfPageLabelStyle::UpperAlpha => alpha_number(number),
+    }
+}
+
+/// Format a number in uppercase roman numerals.
+fn roman_number(mut number: usize) -> String {
+    const NUMERALS: &[(usize, &str)] = &[
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut res = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= *value {
+            res.push_str(numeral);
+            number -= value;
+        }
+    }
+    res
+}
+
+/// Format a number in uppercase letters, i.e. `A` to `Z` for the first 26
+/// numbers, `AA` to `ZZ` for the next 26, and so on.
+fn alpha_number(number: usize) -> String {
+    let Some(index) = number.checked_sub(1) else {
+        return String::new();
+    };
+
+    let letter = (b'A' + (index % 26) as u8) as char;
+    letter.to_string().repeat(index / 26 + 1)
+}
+
 /// Lower a raster or SVG image into svg item.
 #[comemo::memoize]
 fn lower_image(image: &Image, size: Size) -> ir::ImageItem {
@@ -496,13 +637,6 @@ fn lower_image(image: &Image, size: Size) -> ir::ImageItem {
     }
 }
 
-fn hack_span_id_to_u64(span_id: &Span) -> u64 {
-    const SPAN_BITS: u64 = 48;
-    // todo: how to get file_id?
-    let file_id = unsafe { std::mem::transmute::<_, &u16>(&span_id.id()) };
-    ((*file_id as u64) << SPAN_BITS) | span_id.number()
-}
-
 struct FindViewBoxResult<'a> {
     start_span: Option<xmlparser::StrSpan<'a>>,
     first_viewbox: Option<(xmlparser::StrSpan<'a>, xmlparser::StrSpan<'a>)>,
@@ -542,3 +676,45 @@ fn find_viewbox_attr(svg_str: &'_ str) -> FindViewBoxResult<'_> {
         first_viewbox,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_page_number() {
+        let format = |style, numbers: &[usize]| {
+            let numbers = numbers.iter().map(|n| format_page_number(style, *n));
+            numbers.collect::<Vec<_>>().join(",")
+        };
+
+        assert_eq!(format(PdfPageLabelStyle::Arabic, &[1, 12]), "1,12");
+        assert_eq!(
+            format(PdfPageLabelStyle::LowerRoman, &[1, 4, 9, 14, 1994]),
+            "i,iv,ix,xiv,mcmxciv"
+        );
+        assert_eq!(format(PdfPageLabelStyle::UpperRoman, &[3, 40]), "III,XL");
+        assert_eq!(
+            format(PdfPageLabelStyle::LowerAlpha, &[1, 26, 27]),
+            "a,z,aa"
+        );
+        assert_eq!(format(PdfPageLabelStyle::UpperAlpha, &[2, 54]), "B,BBB");
+    }
+
+    #[test]
+    fn test_format_page_label() {
+        let label = PdfPageLabel {
+            prefix: Some("A-".into()),
+            style: Some(PdfPageLabelStyle::Arabic),
+            offset: std::num::NonZeroUsize::new(3),
+        };
+        assert_eq!(format_page_label(&label), Some("A-3".to_owned()));
+
+        let label = PdfPageLabel {
+            prefix: None,
+            style: None,
+            offset: None,
+        };
+        assert_eq!(format_page_label(&label), None);
+    }
+}
@@ -20,6 +20,14 @@ pub mod stream;
 #[cfg(feature = "vector-bbox")]
 pub mod bbox;
 
+#[cfg(feature = "vector-bbox")]
+pub mod span;
+
+pub mod element;
+
+#[cfg(feature = "vector-bbox")]
+pub mod cull;
+
 #[cfg(feature = "rkyv")]
 #[allow(dead_code)]
 fn rkyv_assertions() {
@@ -88,6 +96,10 @@ fn rkyv_assertions() {
     const _: () = assert!(core::mem::align_of::<ArchivedImageGlyphItem>() == 4);
     const _: () = assert!(core::mem::size_of::<ArchivedOutlineGlyphItem>() == 36);
     const _: () = assert!(core::mem::align_of::<ArchivedOutlineGlyphItem>() == 4);
+    const _: () = assert!(core::mem::size_of::<ArchivedColorGlyphLayer>() == 20);
+    const _: () = assert!(core::mem::align_of::<ArchivedColorGlyphLayer>() == 4);
+    const _: () = assert!(core::mem::size_of::<ArchivedColorGlyphItem>() == 8);
+    const _: () = assert!(core::mem::align_of::<ArchivedColorGlyphItem>() == 4);
     const _: () = assert!(core::mem::size_of::<ArchivedTextShape>() == 20);
     const _: () = assert!(core::mem::align_of::<ArchivedTextShape>() == 4);
     const _: () = assert!(core::mem::size_of::<ArchivedPathStyle>() == 12);
@@ -96,8 +108,16 @@ fn rkyv_assertions() {
     const _: () = assert!(core::mem::align_of::<ArchivedTransformItem>() == 4);
     const _: () = assert!(core::mem::size_of::<ArchivedGlyphPack>() == 12);
     const _: () = assert!(core::mem::align_of::<ArchivedGlyphPack>() == 4);
-    const _: () = assert!(core::mem::size_of::<ArchivedPage>() == 24);
+    const _: () = assert!(core::mem::size_of::<ArchivedFontItem>() == 56);
+    const _: () = assert!(core::mem::align_of::<ArchivedFontItem>() == 8);
+    const _: () = assert!(core::mem::size_of::<ArchivedFontInstance>() == 8);
+    const _: () = assert!(core::mem::align_of::<ArchivedFontInstance>() == 4);
+    const _: () = assert!(core::mem::size_of::<ArchivedPage>() == 48);
     const _: () = assert!(core::mem::align_of::<ArchivedPage>() == 8);
+    const _: () = assert!(core::mem::size_of::<ArchivedPageLabel>() == 24);
+    const _: () = assert!(core::mem::align_of::<ArchivedPageLabel>() == 4);
+    const _: () = assert!(core::mem::size_of::<ArchivedElementItem>() == 40);
+    const _: () = assert!(core::mem::align_of::<ArchivedElementItem>() == 4);
     const _: () = assert!(core::mem::size_of::<ArchivedLayoutRegion>() == 20);
     const _: () = assert!(core::mem::align_of::<ArchivedLayoutRegion>() == 4);
     const _: () = assert!(core::mem::size_of::<ArchivedBuildInfo>() == 16);
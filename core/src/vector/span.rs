@@ -0,0 +1,318 @@
+//! Map between source locations and rendered items, in flavor of SyncTeX.
+
+use std::{collections::HashMap, ops::Range};
+
+use typst::{
+    doc::Document,
+    syntax::{LinkedNode, Source, Span},
+    World,
+};
+
+use super::{
+    bbox::{BBox, BBoxRepr, ObservableBounds, PathRepr},
+    ir::{self, span_from_span_id, Point, Rect, Scalar, SpanId, SvgItem, Transform},
+    sk, LowerBuilder,
+};
+use crate::TypstFileId;
+
+/// A rendered item that is associated with a source span.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpanRegion {
+    /// The span of the item.
+    pub span_id: SpanId,
+    /// The index of the page containing the item.
+    pub page: usize,
+    /// The bounding box of the item, relative to the page.
+    pub rect: Rect,
+}
+
+/// A position in the rendered document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DocumentPosition {
+    /// The index of the page.
+    pub page: usize,
+    /// The rectangle on the page.
+    pub rect: Rect,
+}
+
+/// A location in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// The file containing the location.
+    pub id: TypstFileId,
+    /// The byte range in the file.
+    pub range: Range<usize>,
+    /// The zero-based line of the start of the range.
+    pub line: usize,
+    /// The zero-based column of the start of the range.
+    pub column: usize,
+}
+
+/// Collects the regions of the items that carry a source span, using the
+/// [`SpanId`]s stored in [`SvgItem`].
+#[derive(Debug, Default, Clone)]
+pub struct SpanIndex {
+    regions: Vec<SpanRegion>,
+}
+
+impl SpanIndex {
+    /// Build the index from a compiled document.
+    pub fn from_document(doc: &Document) -> Self {
+        let mut lower_builder = LowerBuilder::new(doc);
+        let pages = doc.pages.iter().map(|p| lower_builder.lower(p));
+        Self::from_pages(&pages.collect::<Vec<_>>())
+    }
+
+    /// Build the index from lowered pages.
+    pub fn from_pages<'a>(pages: impl IntoIterator<Item = &'a SvgItem>) -> Self {
+        let mut index = Self::default();
+        for (page, item) in pages.into_iter().enumerate() {
+            index.collect(page, Transform::identity(), item);
+        }
+        index
+    }
+
+    /// All of the regions in the index.
+    pub fn regions(&self) -> &[SpanRegion] {
+        &self.regions
+    }
+
+    /// Get the regions rendered from the given span.
+    pub fn regions_by_span(&self, span_id: SpanId) -> impl Iterator<Item = &SpanRegion> {
+        self.regions.iter().filter(move |r| r.span_id == span_id)
+    }
+
+    /// Get the innermost region containing the given point on the page.
+    pub fn region_at(&self, page: usize, point: Point) -> Option<&SpanRegion> {
+        let area = |r: &Rect| (r.hi.x.0 - r.lo.x.0) * (r.hi.y.0 - r.lo.y.0);
+
+        self.regions
+            .iter()
+            .filter(|r| r.page == page)
+            .filter(|r| {
+                let Rect { lo, hi } = r.rect;
+                lo.x <= point.x && point.x <= hi.x && lo.y <= point.y && point.y <= hi.y
+            })
+            .min_by(|x, y| area(&x.rect).total_cmp(&area(&y.rect)))
+    }
+
+    fn push(&mut self, page: usize, span_id: SpanId, rect: Rect) {
+        if span_from_span_id(span_id).is_detached() || rect.is_empty() {
+            return;
+        }
+
+        self.regions.push(SpanRegion {
+            span_id,
+            page,
+            rect,
+        });
+    }
+
+    fn collect(&mut self, page: usize, ts: Transform, item: &SvgItem) {
+        match item {
+            SvgItem::Group(group) => {
+                for (pos, item) in group.0.iter() {
+                    self.collect(page, ts.pre_translate(pos.x.0, pos.y.0), item);
+                }
+            }
+            SvgItem::Transformed(ir::TransformedItem(transform, item)) => {
+                let ts = match transform {
+                    // todo: clip the regions
+                    ir::TransformItem::Clip(..) => ts,
+                    ir::TransformItem::Rotate(deg) => {
+                        ts.pre_concat(sk::Transform::from_rotate(deg.0).into())
+                    }
+                    transform => ts.pre_concat(transform.clone().into()),
+                };
+                self.collect(page, ts, item);
+            }
+            SvgItem::Text(text) => {
+                let metrics = text.font.metrics();
+                let size = text.shape.size.0;
+                let width = text.content.glyphs.iter().map(|g| g.1 .0).sum::<f32>();
+
+                let rect = Rect {
+                    lo: Point::new(Scalar(0.), Scalar(-metrics.ascender.get() as f32 * size)),
+                    hi: Point::new(
+                        Scalar(width),
+                        Scalar(-metrics.descender.get() as f32 * size),
+                    ),
+                };
+                self.push(page, text.content.span_id, realize_rect(ts, rect));
+            }
+            SvgItem::Path((path, span_id)) => {
+                if let Some(path) = PathRepr::from_item(path) {
+                    self.push(page, *span_id, path.realize(ts));
+                }
+            }
+            SvgItem::Image((image, span_id)) => {
+                let rect = Rect {
+                    lo: Point::default(),
+                    hi: image.size,
+                };
+                self.push(page, *span_id, realize_rect(ts, rect));
+            }
+            SvgItem::Link(..) => {}
+        }
+    }
+}
+
+fn realize_rect(ts: Transform, rect: Rect) -> Rect {
+    BBox::new(BBoxRepr::Rect(rect)).realize(ts)
+}
+
+/// Maps source locations to positions in the document and vice versa.
+///
+/// The sources are captured when the mapping is built, so that the mapping
+/// can be queried without accessing the world again.
+#[derive(Default, Clone)]
+pub struct SourceSpanMapping {
+    index: SpanIndex,
+    sources: HashMap<TypstFileId, Source>,
+}
+
+impl SourceSpanMapping {
+    /// Build the mapping for a document compiled by the world.
+    pub fn new(world: &dyn World, doc: &Document) -> Self {
+        Self::from_index(world, SpanIndex::from_document(doc))
+    }
+
+    /// Build the mapping for the lowered pages of a document compiled by the
+    /// world, which saves lowering the document again if the caller has
+    /// lowered it.
+    pub fn from_lowered(world: &dyn World, pages: &[SvgItem]) -> Self {
+        Self::from_index(world, SpanIndex::from_pages(pages))
+    }
+
+    fn from_index(world: &dyn World, index: SpanIndex) -> Self {
+        let mut sources = HashMap::new();
+        for region in index.regions() {
+            let Some(id) = span_from_span_id(region.span_id).id() else {
+                continue;
+            };
+            if sources.contains_key(&id) {
+                continue;
+            }
+            if let Ok(source) = world.source(id) {
+                sources.insert(id, source);
+            }
+        }
+
+        Self { index, sources }
+    }
+
+    /// The underlying span index.
+    pub fn index(&self) -> &SpanIndex {
+        &self.index
+    }
+
+    /// Find the positions in the document rendered from the syntax node at the
+    /// given byte offset of the file.
+    ///
+    /// If the node at the offset is not rendered, its closest rendered
+    /// ancestor is used.
+    pub fn jump_from_cursor(&self, id: TypstFileId, offset: usize) -> Vec<DocumentPosition> {
+        let Some(source) = self.sources.get(&id) else {
+            return vec![];
+        };
+
+        let root = LinkedNode::new(source.root());
+        let mut node = root.leaf_at(offset);
+        while let Some(n) = node {
+            let span_id = ir::span_id_from_span(&n.span());
+            let positions = self
+                .index
+                .regions_by_span(span_id)
+                .map(|r| DocumentPosition {
+                    page: r.page,
+                    rect: r.rect,
+                })
+                .collect::<Vec<_>>();
+            if !positions.is_empty() {
+                return positions;
+            }
+
+            node = n.parent().cloned();
+        }
+
+        vec![]
+    }
+
+    /// Find the source location of the item rendered at the given point on
+    /// the page.
+    pub fn jump_from_click(&self, page: usize, point: Point) -> Option<SourceLocation> {
+        let region = self.index.region_at(page, point)?;
+        self.resolve_span(span_from_span_id(region.span_id))
+    }
+
+    /// Resolve the source location of a span.
+    pub fn resolve_span(&self, span: Span) -> Option<SourceLocation> {
+        let id = span.id()?;
+        let source = self.sources.get(&id)?;
+        let range = source.range(span)?;
+
+        Some(SourceLocation {
+            id,
+            line: source.byte_to_line(range.start)?,
+            column: source.byte_to_column(range.start)?,
+            range,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::syntax::VirtualPath;
+
+    use crate::vector::path2d::SvgPath2DBuilder;
+
+    use super::*;
+
+    fn get_rect_item(x: f32, y: f32, width: f32, height: f32, span_id: SpanId) -> SvgItem {
+        let mut d = SvgPath2DBuilder::default();
+        d.rect(x, y, width, height);
+        let path = ir::PathItem {
+            d: d.0.into(),
+            styles: Default::default(),
+        };
+
+        SvgItem::Path((path, span_id))
+    }
+
+    #[test]
+    fn test_span_id_roundtrip() {
+        let id = TypstFileId::new(None, VirtualPath::new("main.typ"));
+        let span = Span::new(id, 42);
+
+        assert_eq!(span_from_span_id(ir::span_id_from_span(&span)), span);
+    }
+
+    #[test]
+    fn test_span_id_of_unknown_file() {
+        let span_id = ((u16::MAX as SpanId) << 48) | 42;
+
+        assert!(span_from_span_id(span_id).is_detached());
+    }
+
+    #[test]
+    fn test_region_at() {
+        let id = TypstFileId::new(None, VirtualPath::new("main.typ"));
+        let outer = ir::span_id_from_span(&Span::new(id, 42));
+        let inner = ir::span_id_from_span(&Span::new(id, 43));
+
+        let page = SvgItem::Group(ir::GroupItem(vec![
+            (Point::default(), get_rect_item(0., 0., 100., 100., outer)),
+            (
+                Point::new(Scalar(10.), Scalar(20.)),
+                get_rect_item(0., 0., 10., 10., inner),
+            ),
+        ]));
+        let index = SpanIndex::from_pages([&page]);
+
+        let at = |x, y| index.region_at(0, Point::new(Scalar(x), Scalar(y)));
+        assert_eq!(at(15., 25.).map(|r| r.span_id), Some(inner));
+        assert_eq!(at(50., 50.).map(|r| r.span_id), Some(outer));
+        assert_eq!(at(150., 50.), None);
+        assert_eq!(index.regions_by_span(inner).count(), 1);
+    }
+}
@@ -1,12 +1,211 @@
-use super::flat_ir::{ArchivedFlatModule, FlatModule};
+//! Read and write vector artifacts, i.e. serialized [`FlatModule`]s.
+//!
+//! An artifact is stored in a versioned container, so that an artifact
+//! written by an incompatible version of the IR is rejected with a clear
+//! error instead of being misread:
+//!
+//! | offset | size | field                                              |
+//! |--------|------|----------------------------------------------------|
+//! | 0      | 4    | magic, `tsvr`                                      |
+//! | 4      | 2    | version of the container, [`CONTAINER_VERSION`]    |
+//! | 6      | 1    | compression of the payload, [`ArtifactCompression`] |
+//! | 7      | 1    | reserved, `0`                                      |
+//! | 8      | 4    | version of the IR, [`FLAT_MODULE_VERSION`]         |
+//! | 12     | 4    | reserved, `0`                                      |
+//! | 16     | 8    | length of the uncompressed payload                 |
+//! | 24     | 8    | checksum of the stored payload, in SipHash-1-3     |
+//! | 32     | -    | payload, i.e. the rkyv archive of [`FlatModule`]   |
+//!
+//! The integers are in little-endian. An uncompressed payload stays aligned
+//! if the artifact is aligned, so that it can be read without copying.
+//!
+//! The artifacts written before the container was introduced are read if
+//! they are compatible with the current IR.
+
+use std::hash::Hasher;
+#[cfg(feature = "vector-unzstd")]
+use std::io::Read;
+
 use rkyv::de::deserializers::SharedDeserializeMap;
 use rkyv::{AlignedVec, Deserialize};
+use siphasher::sip::SipHasher13;
+
+use super::flat_ir::{ArchivedFlatModule, FlatModule};
+use crate::error::prelude::*;
+use crate::Error;
+
+/// The magic of the container.
+pub const CONTAINER_MAGIC: [u8; 4] = *b"tsvr";
+
+/// The version of the container.
+pub const CONTAINER_VERSION: u16 = 1;
+
+/// The length of the header of the container.
+pub const CONTAINER_HEADER_LEN: usize = 32;
+
+/// The version of the layout of [`FlatModule`].
+///
+/// It must be bumped whenever the archived layout changes, i.e. whenever the
+/// assertions in `rkyv_assertions` are changed.
+pub const FLAT_MODULE_VERSION: u32 = 2;
+
+/// The magic stored in [`FlatModule::magic`].
+const MODULE_MAGIC: [u8; 8] = *b"tsvr\x00\x00\x00\x00";
+
+/// The most bytes preallocated for a decompressed payload before its length
+/// is confirmed by decoding.
+#[cfg(feature = "vector-unzstd")]
+const MAX_PAYLOAD_PREALLOC: usize = 16 * 1024 * 1024;
+
+/// The compression of the payload of an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum ArtifactCompression {
+    /// The payload is not compressed.
+    #[default]
+    None = 0,
+    /// The payload is compressed by zstd.
+    /// Writing it requires the `vector-zstd` feature, and reading it requires
+    /// the `vector-unzstd` feature.
+    Zstd = 1,
+}
+
+impl TryFrom<u8> for ArtifactCompression {
+    type Error = Error;
+
+    fn try_from(value: u8) -> ZResult<Self> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Zstd),
+            _ => Err(error_once!("ArtifactCompression.Unknown", compression: value)),
+        }
+    }
+}
+
+/// The header of an artifact.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArtifactHeader {
+    /// The version of the container.
+    pub container_version: u16,
+    /// The compression of the payload.
+    pub compression: ArtifactCompression,
+    /// The version of the IR.
+    pub ir_version: u32,
+    /// The length of the uncompressed payload.
+    pub payload_len: u64,
+    /// The checksum of the stored payload.
+    pub checksum: u64,
+}
+
+impl ArtifactHeader {
+    /// Parse the header of an artifact.
+    ///
+    /// It returns `None` if the artifact is written before the container was
+    /// introduced.
+    pub fn parse(v: &[u8]) -> ZResult<Option<Self>> {
+        if v.len() < CONTAINER_HEADER_LEN || v[..4] != CONTAINER_MAGIC {
+            return Ok(None);
+        }
+
+        let u16_at = |i: usize| u16::from_le_bytes([v[i], v[i + 1]]);
+        let u32_at = |i: usize| u32::from_le_bytes(v[i..i + 4].try_into().unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(v[i..i + 8].try_into().unwrap());
+
+        let container_version = u16_at(4);
+        if container_version != CONTAINER_VERSION {
+            return Err(error_once!(
+                "ArtifactHeader.IncompatibleContainerVersion",
+                version: container_version,
+                expected: CONTAINER_VERSION,
+            ));
+        }
+
+        Ok(Some(Self {
+            container_version,
+            compression: v[6].try_into()?,
+            ir_version: u32_at(8),
+            payload_len: u64_at(16),
+            checksum: u64_at(24),
+        }))
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&CONTAINER_MAGIC);
+        out.extend_from_slice(&self.container_version.to_le_bytes());
+        out.push(self.compression as u8);
+        out.push(0);
+        out.extend_from_slice(&self.ir_version.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&self.payload_len.to_le_bytes());
+        out.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+}
+
+/// Wrap the rkyv archive of a [`FlatModule`] into an artifact.
+pub fn write_artifact(archive: &[u8], compression: ArtifactCompression) -> ZResult<Vec<u8>> {
+    let payload = match compression {
+        ArtifactCompression::None => None,
+        #[cfg(feature = "vector-zstd")]
+        ArtifactCompression::Zstd => Some(
+            zstd::bulk::compress(archive, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .map_err(error_once_map_string!("write_artifact.Zstd"))?,
+        ),
+        #[cfg(not(feature = "vector-zstd"))]
+        ArtifactCompression::Zstd => {
+            return Err(error_once!(
+                "write_artifact.UnsupportedCompression",
+                compression: "zstd",
+                feature: "vector-zstd",
+            ))
+        }
+    };
+    let payload = payload.as_deref().unwrap_or(archive);
+
+    let header = ArtifactHeader {
+        container_version: CONTAINER_VERSION,
+        compression,
+        ir_version: FLAT_MODULE_VERSION,
+        payload_len: archive.len() as u64,
+        checksum: checksum(payload),
+    };
+
+    let mut out = Vec::with_capacity(CONTAINER_HEADER_LEN + payload.len());
+    header.write(&mut out);
+    out.extend_from_slice(payload);
+    Ok(out)
+}
+
+/// Rewrite an artifact in the current version of the container with the
+/// given compression, e.g. an artifact written before the container was
+/// introduced.
+pub fn migrate_artifact(v: &[u8], compression: ArtifactCompression) -> ZResult<Vec<u8>> {
+    let stream = BytesModuleStream::from_slice(v)?;
+    write_artifact(stream.data.as_ref(), compression)
+}
+
+fn checksum(payload: &[u8]) -> u64 {
+    let mut hasher = SipHasher13::new();
+    hasher.write(payload);
+    hasher.finish()
+}
 
 enum RkyvStreamData<'a> {
     Aligned(&'a [u8]),
     Unaligned(AlignedVec),
 }
 
+impl<'a> RkyvStreamData<'a> {
+    fn new(v: &'a [u8]) -> Self {
+        if (v.as_ptr() as usize) % AlignedVec::ALIGNMENT != 0 {
+            let mut aligned = AlignedVec::with_capacity(v.len());
+            aligned.extend_from_slice(v);
+            RkyvStreamData::Unaligned(aligned)
+        } else {
+            RkyvStreamData::Aligned(v)
+        }
+    }
+}
+
 impl<'a> AsRef<[u8]> for RkyvStreamData<'a> {
     #[inline]
     fn as_ref(&self) -> &[u8] {
@@ -18,24 +217,113 @@ impl<'a> AsRef<[u8]> for RkyvStreamData<'a> {
 }
 
 pub struct BytesModuleStream<'a> {
+    header: Option<ArtifactHeader>,
     data: RkyvStreamData<'a>,
 }
 
 impl<'a> BytesModuleStream<'a> {
-    pub fn from_slice(v: &'a [u8]) -> Self {
-        let v = if (v.as_ptr() as usize) % AlignedVec::ALIGNMENT != 0 {
-            let mut aligned = AlignedVec::with_capacity(v.len());
-            aligned.extend_from_slice(v);
-            RkyvStreamData::Unaligned(aligned)
-        } else {
-            RkyvStreamData::Aligned(v)
+    /// Read an artifact, which is validated against the current IR.
+    pub fn from_slice(v: &'a [u8]) -> ZResult<Self> {
+        let header = ArtifactHeader::parse(v)?;
+        let data = match &header {
+            Some(header) => Self::read_payload(header, &v[CONTAINER_HEADER_LEN..])?,
+            None => RkyvStreamData::new(v),
         };
 
-        Self { data: v }
+        let is_valid = rkyv::check_archived_root::<FlatModule>(data.as_ref())
+            .map_or(false, |module| module.magic == MODULE_MAGIC);
+        if !is_valid {
+            return Err(match &header {
+                Some(header) => error_once!(
+                    "BytesModuleStream.InvalidArtifact",
+                    version: header.ir_version,
+                ),
+                None => error_once!(
+                    "BytesModuleStream.IncompatibleArtifactVersion",
+                    version: "unversioned",
+                    expected: FLAT_MODULE_VERSION,
+                ),
+            });
+        }
+
+        Ok(Self { header, data })
+    }
+
+    fn read_payload(header: &ArtifactHeader, payload: &'a [u8]) -> ZResult<RkyvStreamData<'a>> {
+        if header.ir_version != FLAT_MODULE_VERSION {
+            return Err(error_once!(
+                "BytesModuleStream.IncompatibleArtifactVersion",
+                version: header.ir_version,
+                expected: FLAT_MODULE_VERSION,
+            ));
+        }
+
+        if checksum(payload) != header.checksum {
+            return Err(error_once!(
+                "BytesModuleStream.ChecksumMismatch",
+                expected: format!("{:016x}", header.checksum),
+                actual: format!("{:016x}", checksum(payload)),
+            ));
+        }
+
+        let data = match header.compression {
+            ArtifactCompression::None => RkyvStreamData::new(payload),
+            #[cfg(feature = "vector-unzstd")]
+            ArtifactCompression::Zstd => {
+                let mut source = payload;
+                let mut decoder = ruzstd::StreamingDecoder::new(&mut source)
+                    .map_err(|e| error_once!("BytesModuleStream.Zstd", err: format!("{e:?}")))?;
+
+                // The header is not trusted until the payload is decoded, so
+                // neither the preallocation nor the decoding is bounded by it
+                // alone. Decoding one more byte than declared is enough to
+                // detect a length mismatch.
+                let limit = header.payload_len.saturating_add(1);
+                let capacity = header.payload_len.min(MAX_PAYLOAD_PREALLOC as u64);
+                let mut aligned = AlignedVec::with_capacity(capacity as usize);
+                let mut buf = [0u8; 64 * 1024];
+                while (aligned.len() as u64) < limit {
+                    let want = (limit - aligned.len() as u64).min(buf.len() as u64) as usize;
+                    let n = decoder
+                        .read(&mut buf[..want])
+                        .map_err(error_once_map_string!("BytesModuleStream.Zstd"))?;
+                    if n == 0 {
+                        break;
+                    }
+                    aligned.extend_from_slice(&buf[..n]);
+                }
+                RkyvStreamData::Unaligned(aligned)
+            }
+            #[cfg(not(feature = "vector-unzstd"))]
+            ArtifactCompression::Zstd => {
+                return Err(error_once!(
+                    "BytesModuleStream.UnsupportedCompression",
+                    compression: "zstd",
+                    feature: "vector-unzstd",
+                ))
+            }
+        };
+
+        if data.as_ref().len() as u64 != header.payload_len {
+            return Err(error_once!(
+                "BytesModuleStream.PayloadLengthMismatch",
+                expected: header.payload_len,
+                actual: data.as_ref().len(),
+            ));
+        }
+
+        Ok(data)
+    }
+
+    /// The header of the artifact, which is `None` if the artifact is written
+    /// before the container was introduced.
+    pub fn header(&self) -> Option<&ArtifactHeader> {
+        self.header.as_ref()
     }
 
     pub fn checkout(&self) -> &ArchivedFlatModule {
-        rkyv::check_archived_root::<FlatModule>(self.data.as_ref()).unwrap()
+        // SAFETY: the archive is validated in `from_slice`.
+        unsafe { rkyv::archived_root::<FlatModule>(self.data.as_ref()) }
     }
 
     pub fn checkout_owned(&self) -> FlatModule {
@@ -44,3 +332,104 @@ impl<'a> BytesModuleStream<'a> {
         v.deserialize(&mut dmap).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::vector::flat_ir::{BuildInfo, ModuleMetadata};
+
+    fn module() -> FlatModule {
+        FlatModule::new(vec![ModuleMetadata::BuildVersion(Arc::new(BuildInfo {
+            version: "v0.0.0".into(),
+            compiler: "test".into(),
+        }))])
+    }
+
+    fn build_version(stream: &BytesModuleStream) -> String {
+        match &stream.checkout_owned().metadata[0] {
+            ModuleMetadata::BuildVersion(info) => info.version.to_string(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_artifact_roundtrip() {
+        let artifact = module().to_bytes();
+        assert_eq!(artifact[..4], CONTAINER_MAGIC);
+
+        let stream = BytesModuleStream::from_slice(&artifact).unwrap();
+        let header = stream.header().unwrap();
+        assert_eq!(header.ir_version, FLAT_MODULE_VERSION);
+        assert_eq!(header.compression, ArtifactCompression::None);
+        assert_eq!(build_version(&stream), "v0.0.0");
+    }
+
+    #[test]
+    fn test_artifact_incompatible_version() {
+        let mut artifact = module().to_bytes();
+        artifact[8..12].copy_from_slice(&(FLAT_MODULE_VERSION + 1).to_le_bytes());
+
+        let err = BytesModuleStream::from_slice(&artifact).err().unwrap();
+        let err = format!("{err:?}");
+        assert!(err.contains("IncompatibleArtifactVersion"), "{err}");
+    }
+
+    #[test]
+    fn test_artifact_corrupted() {
+        let mut artifact = module().to_bytes();
+        let last = artifact.len() - 1;
+        artifact[last] ^= 0xff;
+
+        let err = BytesModuleStream::from_slice(&artifact).err().unwrap();
+        let err = format!("{err:?}");
+        assert!(err.contains("ChecksumMismatch"), "{err}");
+    }
+
+    #[test]
+    fn test_artifact_migrate_unversioned() {
+        let artifact = module().to_bytes();
+        let unversioned = artifact[CONTAINER_HEADER_LEN..].to_vec();
+
+        let stream = BytesModuleStream::from_slice(&unversioned).unwrap();
+        assert!(stream.header().is_none());
+        assert_eq!(build_version(&stream), "v0.0.0");
+
+        let migrated = migrate_artifact(&unversioned, ArtifactCompression::None).unwrap();
+        assert_eq!(migrated, artifact);
+
+        let err = BytesModuleStream::from_slice(b"not an artifact")
+            .err()
+            .unwrap();
+        assert!(format!("{err:?}").contains("unversioned"));
+    }
+
+    #[cfg(feature = "vector-zstd")]
+    #[test]
+    fn test_artifact_zstd() {
+        let artifact = module().to_bytes_with(ArtifactCompression::Zstd).unwrap();
+
+        let stream = BytesModuleStream::from_slice(&artifact).unwrap();
+        assert_eq!(
+            stream.header().unwrap().compression,
+            ArtifactCompression::Zstd
+        );
+        assert_eq!(build_version(&stream), "v0.0.0");
+    }
+
+    #[cfg(feature = "vector-zstd")]
+    #[test]
+    fn test_artifact_zstd_length_mismatch() {
+        let mut artifact = module().to_bytes_with(ArtifactCompression::Zstd).unwrap();
+        let payload_len = u64::from_le_bytes(artifact[16..24].try_into().unwrap());
+
+        for declared in [payload_len - 1, payload_len + 1, u64::MAX] {
+            artifact[16..24].copy_from_slice(&declared.to_le_bytes());
+
+            let err = BytesModuleStream::from_slice(&artifact).err().unwrap();
+            let err = format!("{err:?}");
+            assert!(err.contains("PayloadLengthMismatch"), "{err}");
+        }
+    }
+}
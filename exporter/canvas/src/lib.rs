@@ -333,6 +333,13 @@ impl CanvasElem for CanvasGlyphElem {
                 canvas.set_fill_style(&fill.into());
                 canvas.fill_with_path_2d(&Path2d::new_with_path_string(&path.d).unwrap());
             }
+            GlyphItem::Color(glyph) => {
+                for layer in &glyph.layers {
+                    let fill: &str = layer.fill.as_deref().unwrap_or(&self.fill);
+                    canvas.set_fill_style(&fill.into());
+                    canvas.fill_with_path_2d(&Path2d::new_with_path_string(&layer.d).unwrap());
+                }
+            }
             GlyphItem::Image(glyph) => {
                 CanvasImageElem::draw_image(ts.pre_concat(glyph.ts.into()), canvas, &glyph.image)
                     .await
@@ -504,8 +511,8 @@ impl<'m, C: FlatRenderVm<'m, Resultant = CanvasNode> + GlyphIndice<'m>> FlatGrou
 }
 
 impl<'m, 't, Feat: ExportFeature> BuildGlyph for CanvasRenderTask<'m, 't, Feat> {
-    fn build_font(&mut self, font: &typst::font::Font) -> FontRef {
-        self.glyph_defs.build_font(font)
+    fn build_font(&mut self, font: &typst::font::Font, instance: &ir::FontInstance) -> FontRef {
+        self.glyph_defs.build_font(font, instance)
     }
 
     fn build_glyph(&mut self, glyph: &ir::GlyphItem) -> GlyphRef {
@@ -649,7 +656,7 @@ impl IncrementalCanvasExporter {
         let pages = pages
             .iter()
             .enumerate()
-            .map(|(idx, Page { content, size })| {
+            .map(|(idx, Page { content, size, .. })| {
                 if idx < self.pages.len() && self.pages[idx].content == *content {
                     return self.pages[idx].clone();
                 }
@@ -758,6 +765,7 @@ impl IncrCanvasDocClient {
                     next_doc_view.push(Page {
                         content: empty_page,
                         size: page.size,
+                        label: page.label.clone(),
                     });
                     continue;
                 }
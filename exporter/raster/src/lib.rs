@@ -133,6 +133,7 @@ fn render_text(
 
         render_svg_glyph(canvas, ts, mask, text, id)
             .or_else(|| render_bitmap_glyph(canvas, ts, mask, text, id))
+            .or_else(|| render_color_glyph(canvas, ts, mask, text, id))
             .or_else(|| render_outline_glyph(canvas, ts, mask, text, id));
 
         x += glyph.x_advance.at(text.size).to_f32();
@@ -253,6 +254,77 @@ fn render_bitmap_glyph(
     render_image(canvas, ts, mask, &image, Size::new(w, h))
 }
 
+/// Render a glyph consisting of colored layers into the canvas, e.g. color
+/// emoji in `COLR` format.
+fn render_color_glyph(
+    canvas: &mut sk::PixmapMut,
+    ts: sk::Transform,
+    mask: Option<&sk::Mask>,
+    text: &TextItem,
+    id: GlyphId,
+) -> Option<()> {
+    let ttf = text.font.ttf();
+    if !ttf.is_color_glyph(id) {
+        return None;
+    }
+
+    /// Collects the colored layers of a glyph.
+    struct Painter<'a> {
+        face: &'a ttf_parser::Face<'a>,
+        outline: Option<sk::Path>,
+        layers: Vec<(sk::Path, Option<ttf_parser::RgbaColor>)>,
+    }
+
+    impl ttf_parser::colr::Painter for Painter<'_> {
+        fn outline(&mut self, glyph_id: GlyphId) {
+            let mut builder = WrappedPathBuilder(sk::PathBuilder::new());
+            self.outline = self
+                .face
+                .outline_glyph(glyph_id, &mut builder)
+                .and_then(|_| builder.0.finish());
+        }
+
+        fn paint_foreground(&mut self) {
+            if let Some(path) = self.outline.take() {
+                self.layers.push((path, None));
+            }
+        }
+
+        fn paint_color(&mut self, color: ttf_parser::RgbaColor) {
+            if let Some(path) = self.outline.take() {
+                self.layers.push((path, Some(color)));
+            }
+        }
+    }
+
+    let mut painter = Painter {
+        face: ttf,
+        outline: None,
+        layers: vec![],
+    };
+    ttf.paint_color_glyph(id, 0, &mut painter)?;
+
+    // Flip vertically because font design coordinate
+    // system is Y-up.
+    let scale = text.size.to_f32() / text.font.units_per_em() as f32;
+    let ts = ts.pre_scale(scale, -scale);
+    let rule = sk::FillRule::default();
+    for (path, color) in painter.layers {
+        let paint = match color {
+            Some(c) => {
+                let mut paint = sk::Paint::default();
+                paint.set_color_rgba8(c.red, c.green, c.blue, c.alpha);
+                paint.anti_alias = true;
+                paint
+            }
+            None => (&text.fill).into(),
+        };
+        canvas.fill_path(&path, &paint, rule, ts, mask);
+    }
+
+    Some(())
+}
+
 /// Render an outline glyph into the canvas. This is the "normal" case.
 fn render_outline_glyph(
     canvas: &mut sk::PixmapMut,
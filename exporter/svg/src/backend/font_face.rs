@@ -0,0 +1,104 @@
+//! Renders glyphs as text with subsetted web fonts, which is much smaller than
+//! rendering them as outlines for text-heavy documents.
+//! See [`crate::ExportFeature::USE_FONT_FACE`].
+
+use std::collections::HashMap;
+
+use base64::Engine;
+
+use typst::font::Font;
+use typst_ts_core::{
+    font::{is_subsettable_glyph, subset_font_woff2, subset_glyph_codepoint},
+    vector::ir::{self, Scalar},
+};
+
+use super::SvgText;
+
+/// The font family name of the subsetted font, which is also used as the class
+/// name of the text elements.
+pub fn font_face_family(font: &Font) -> String {
+    format!("tsf-{:08x}", fxhash::hash32(font))
+}
+
+/// Whether the glyph is rendered by a subsetted font.
+pub fn is_font_face_glyph(glyph: &ir::GlyphItem) -> bool {
+    match glyph {
+        ir::GlyphItem::Raw(font, id, instance) => {
+            instance.is_default_of(font) && is_subsettable_glyph(font, id.0)
+        }
+        _ => false,
+    }
+}
+
+/// Render a glyph as a text element, if it is rendered by a subsetted font.
+/// The glyph is positioned in font units, as same as the glyph reference.
+pub fn render_glyph_text(pos: Scalar, glyph: &ir::GlyphItem) -> Option<SvgText> {
+    let ir::GlyphItem::Raw(font, id, _) = glyph else {
+        return None;
+    };
+    if !is_font_face_glyph(glyph) {
+        return None;
+    }
+
+    let adjusted_offset = (pos.0 * 2.).round() / 2.;
+    let codepoint = subset_glyph_codepoint(id.0)? as u32;
+    Some(SvgText::Plain(format!(
+        r#"<text x="{}" class="{}">&#x{:X};</text>"#,
+        adjusted_offset,
+        font_face_family(font),
+        codepoint
+    )))
+}
+
+/// Render the `@font-face` rules for the glyphs rendered by subsetted fonts.
+/// <svg> <defs> <style/> </defs> .. </svg>
+///              ^^^^^^^^
+pub fn render_font_faces<'a>(glyphs: impl IntoIterator<Item = &'a ir::GlyphItem>) -> Vec<SvgText> {
+    // collect the glyphs used in each font
+    let mut font_glyphs: HashMap<Font, Vec<u16>> = HashMap::new();
+    for glyph in glyphs {
+        if let ir::GlyphItem::Raw(font, id, _) = glyph {
+            if is_font_face_glyph(glyph) {
+                font_glyphs.entry(font.clone()).or_default().push(id.0);
+            }
+        }
+    }
+
+    if font_glyphs.is_empty() {
+        return vec![];
+    }
+
+    let mut font_faces = font_glyphs
+        .into_iter()
+        .filter_map(|(font, glyphs)| {
+            let family = font_face_family(&font);
+            let Some(woff2) = subset_font_woff2(&font, &glyphs) else {
+                log::warn!("failed to subset font {:?}", font.info().family);
+                return None;
+            };
+            let woff2 = base64::engine::general_purpose::STANDARD.encode(woff2);
+
+            // apply a negative scaleY to flip the text back, since the glyphs
+            // are placed in a flipped coordinate system.
+            let font_face = format!(
+                concat!(
+                    r#"@font-face {{ font-family: "{family}"; "#,
+                    r#"src: url(data:font/woff2;base64,{woff2}) format("woff2"); }} "#,
+                    r#"text.{family} {{ font-family: "{family}"; font-size: {upem}px; "#,
+                    r#"fill: var(--glyph_fill); transform: scale(1,-1); "#,
+                    r#"white-space: pre; user-select: none; }} "#,
+                ),
+                family = family,
+                woff2 = woff2,
+                upem = font.units_per_em(),
+            );
+            Some((family, font_face))
+        })
+        .collect::<Vec<_>>();
+    font_faces.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut svg = vec![r#"<style type="text/css">"#.into()];
+    svg.extend(font_faces.into_iter().map(|(_, v)| SvgText::Plain(v)));
+    svg.push("</style>".into());
+    svg
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{io, sync::Arc};
 
 use base64::Engine;
 
@@ -20,6 +20,8 @@ use typst_ts_core::{
 mod escape;
 use escape::{PcDataEscapes, TextContentDataEscapes};
 
+pub(crate) mod font_face;
+
 use crate::utils::ToCssExt;
 
 pub trait BuildClipPath {
@@ -38,6 +40,8 @@ pub trait DynExportFeature {
     fn should_attach_debug_info(&self) -> bool;
 
     fn should_aware_html_entity(&self) -> bool;
+
+    fn use_font_face(&self) -> bool;
 }
 
 /// A generated text content.
@@ -66,6 +70,14 @@ impl SvgText {
             SvgText::Content(c) => c.write_string_io(string_io),
         }
     }
+
+    /// Recursively write the text content to the writer.
+    pub fn write_io(&self, w: &mut impl io::Write) -> io::Result<()> {
+        match self {
+            SvgText::Plain(c) => w.write_all(c.as_bytes()),
+            SvgText::Content(c) => c.write_io(w),
+        }
+    }
 }
 
 impl From<&str> for SvgText {
@@ -117,6 +129,19 @@ impl SvgTextNode {
         }
         string_io.push_str("</g>");
     }
+
+    /// Recursively write the text content to the writer.
+    pub fn write_io(&self, w: &mut impl io::Write) -> io::Result<()> {
+        w.write_all(b"<g")?;
+        for (attr_name, attr_content) in &self.attributes {
+            write!(w, r#" {attr_name}="{attr_content}""#)?;
+        }
+        w.write_all(b">")?;
+        for c in &self.content {
+            c.write_io(w)?;
+        }
+        w.write_all(b"</g>")
+    }
 }
 
 pub struct SvgGlyphBuilder {
@@ -156,6 +181,7 @@ impl SvgGlyphBuilder {
             ir::GlyphItem::Outline(outline_glyph) => {
                 Self::render_outline_glyph(glyph_id, outline_glyph)
             }
+            ir::GlyphItem::Color(color_glyph) => Self::render_color_glyph(glyph_id, color_glyph),
             ir::GlyphItem::Raw(..) => unreachable!(),
             ir::GlyphItem::None => None,
         }
@@ -185,6 +211,25 @@ impl SvgGlyphBuilder {
         );
         Some(symbol_def)
     }
+
+    /// Render a glyph consisting of colored layers into svg text.
+    /// The layers without fill are painted with the fill of the text.
+    fn render_color_glyph(glyph_id: &str, color_glyph: &ir::ColorGlyphItem) -> Option<String> {
+        let mut symbol_def = format!(
+            r#"<symbol overflow="visible" id="{}" class="color_glyph">"#,
+            glyph_id
+        );
+        for layer in &color_glyph.layers {
+            match &layer.fill {
+                Some(fill) => {
+                    symbol_def.push_str(&format!(r#"<path d="{}" fill="{}"/>"#, layer.d, fill))
+                }
+                None => symbol_def.push_str(&format!(r#"<path d="{}"/>"#, layer.d)),
+            }
+        }
+        symbol_def.push_str("</symbol>");
+        Some(symbol_def)
+    }
 }
 
 /// A builder for [`SvgTextNode`].
@@ -355,6 +400,14 @@ impl<
     fn render_glyph(&mut self, ctx: &mut C, pos: Scalar, glyph: &ir::GlyphItem) {
         let glyph_ref = ctx.build_glyph(glyph);
 
+        // the glyph is still built, so that its font can be subsetted later.
+        if ctx.use_font_face() {
+            if let Some(text) = font_face::render_glyph_text(pos, glyph) {
+                self.content.push(text);
+                return;
+            }
+        }
+
         self.render_glyph_inner(ctx, pos, &glyph_ref)
     }
 
@@ -598,3 +651,12 @@ pub fn generate_text(text_list: Vec<SvgText>) -> String {
     }
     string_io
 }
+
+/// Write a list of [`SvgText`] to the writer incrementally, without
+/// concatenating them into a single string.
+pub fn write_text(text_list: Vec<SvgText>, w: &mut impl io::Write) -> io::Result<()> {
+    for s in text_list {
+        s.write_io(w)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+
+use typst::{diag::SourceResult, World};
+use typst_ts_core::{exporter_utils::map_err, Transformer};
+
+/// Compress the output of a streaming exporter with gzip, e.g. to write
+/// `svg.gz` files by `GzipExporter<PureSvgExporter>`.
+///
+/// The inner exporter writes to the encoder directly, so the uncompressed
+/// output is never held in memory as a whole string.
+#[derive(Default)]
+pub struct GzipExporter<E> {
+    exporter: E,
+    level: flate2::Compression,
+}
+
+impl<E> GzipExporter<E> {
+    pub fn new(exporter: E) -> Self {
+        Self {
+            exporter,
+            level: flate2::Compression::default(),
+        }
+    }
+
+    /// Set the compression level.
+    pub fn with_level(mut self, level: flate2::Compression) -> Self {
+        self.level = level;
+        self
+    }
+}
+
+impl<I, W: Write, E> Transformer<(Arc<I>, W)> for GzipExporter<E>
+where
+    E: for<'w> Transformer<(Arc<I>, &'w mut flate2::write::GzEncoder<W>)>,
+{
+    fn export(&self, world: &dyn World, (output, writer): (Arc<I>, W)) -> SourceResult<()> {
+        let mut encoder = flate2::write::GzEncoder::new(writer, self.level);
+        self.exporter.export(world, (output, &mut encoder))?;
+        encoder.finish().map_err(map_err)?;
+        Ok(())
+    }
+}
+
+/// Compress the output of a streaming exporter with brotli, e.g. to write
+/// `svg.html.br` files by `BrotliExporter<SvgExporter<DefaultExportFeature>>`.
+///
+/// The inner exporter writes to the encoder, as a `&mut dyn Write`, directly,
+/// so the uncompressed output is never held in memory as a whole string.
+pub struct BrotliExporter<E> {
+    exporter: E,
+    /// The compression quality, from 0 to 11.
+    quality: u32,
+}
+
+impl<E> BrotliExporter<E> {
+    pub fn new(exporter: E) -> Self {
+        Self {
+            exporter,
+            // quality 11 is too slow for large documents
+            quality: 9,
+        }
+    }
+
+    /// Set the compression quality, from 0 to 11.
+    pub fn with_quality(mut self, quality: u32) -> Self {
+        self.quality = quality;
+        self
+    }
+}
+
+impl<E: Default> Default for BrotliExporter<E> {
+    fn default() -> Self {
+        Self::new(E::default())
+    }
+}
+
+impl<I, W: Write, E> Transformer<(Arc<I>, W)> for BrotliExporter<E>
+where
+    E: for<'w> Transformer<(Arc<I>, &'w mut dyn Write)>,
+{
+    fn export(&self, world: &dyn World, (output, writer): (Arc<I>, W)) -> SourceResult<()> {
+        let writer = CheckedWriter {
+            inner: writer,
+            error: None,
+        };
+        let mut encoder = brotli::CompressorWriter::new(writer, 4096, self.quality, 22);
+        let sink: &mut dyn Write = &mut encoder;
+        self.exporter.export(world, (output, sink))?;
+
+        // finishes the stream, in which brotli discards the errors of writing
+        let mut writer = encoder.into_inner();
+        if let Some(err) = writer.error.take() {
+            return Err(map_err(err));
+        }
+        writer.inner.flush().map_err(map_err)
+    }
+}
+
+/// A writer keeping the first error of the inner writer, since the errors
+/// are discarded by [`brotli::CompressorWriter::into_inner`].
+struct CheckedWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W> CheckedWriter<W> {
+    fn check<T>(&mut self, res: io::Result<T>) -> io::Result<T> {
+        res.map_err(|err| {
+            if err.kind() == io::ErrorKind::Interrupted {
+                return err;
+            }
+            let copied = io::Error::new(err.kind(), err.to_string());
+            self.error.get_or_insert(copied);
+            err
+        })
+    }
+}
+
+impl<W: Write> Write for CheckedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let res = self.inner.write(buf);
+        self.check(res)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let res = self.inner.flush();
+        self.check(res)
+    }
+}
@@ -80,6 +80,11 @@ impl<'m, 't, Feat: ExportFeature> DynExportFeature for RenderContext<'m, 't, Fea
     fn should_aware_html_entity(&self) -> bool {
         Feat::AWARE_HTML_ENTITY
     }
+
+    #[inline]
+    fn use_font_face(&self) -> bool {
+        Feat::USE_FONT_FACE
+    }
 }
 
 impl<'m, 't, Feat: ExportFeature> FontIndice<'m> for RenderContext<'m, 't, Feat> {
@@ -89,8 +94,8 @@ impl<'m, 't, Feat: ExportFeature> FontIndice<'m> for RenderContext<'m, 't, Feat>
 }
 
 impl<'m, 't, Feat: ExportFeature> BuildGlyph for RenderContext<'m, 't, Feat> {
-    fn build_font(&mut self, font: &typst::font::Font) -> FontRef {
-        self.glyph_defs.build_font(font)
+    fn build_font(&mut self, font: &typst::font::Font, instance: &ir::FontInstance) -> FontRef {
+        self.glyph_defs.build_font(font, instance)
     }
 
     fn build_glyph(&mut self, glyph: &ir::GlyphItem) -> GlyphRef {
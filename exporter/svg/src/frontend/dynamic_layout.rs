@@ -31,6 +31,7 @@ impl DynamicLayoutSvgExporter {
                 Page {
                     content: abs_ref,
                     size: p.size().into(),
+                    label: LowerBuilder::lower_page_label(p),
                 }
             })
             .collect::<Vec<_>>();
@@ -2,11 +2,13 @@ use std::sync::Arc;
 
 use typst::{diag::SourceResult, doc::Document};
 use typst_ts_core::vector::{
+    cull::ViewportCuller,
     flat_ir::{
         flatten_glyphs, FlatModule, ItemPack, LayoutRegion, LayoutRegionNode, LayoutRegionRepr,
         Module, ModuleBuilder, ModuleMetadata, Page, SvgDocument,
     },
     flat_vm::FlatRenderVm,
+    ir::Rect,
     LowerBuilder,
 };
 
@@ -63,6 +65,7 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
                 Page {
                     content: abs_ref,
                     size: p.size().into(),
+                    label: LowerBuilder::lower_page_label(p),
                 }
             })
             .collect::<Vec<_>>();
@@ -84,9 +87,29 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
 
         generate_text(Self::render_svg_template(t, header, svg_body, glyphs))
     }
+
+    /// Render the pages with only the items intersecting the viewport, where
+    /// the viewport is relative to the top of the first page.
+    ///
+    /// The groups of culled items are inserted into the module.
+    /// See [`ViewportCuller`] for details.
+    pub fn render_flat_svg_in_window(
+        module: &mut Module,
+        pages: &[Page],
+        viewport: Rect,
+    ) -> String {
+        let pages = ViewportCuller::default().cull_pages(module, pages, viewport);
+        Self::render_flat_svg(module, &pages)
+    }
 }
 
 pub fn export_module(output: &Document) -> SourceResult<Vec<u8>> {
+    Ok(lower_module(output)?.to_bytes())
+}
+
+/// Lower a document into a flat module, which is serialized as an artifact by
+/// [`export_module`].
+pub fn lower_module(output: &Document) -> SourceResult<FlatModule> {
     let mut t = LowerBuilder::new(output);
 
     let mut builder = ModuleBuilder::default();
@@ -98,6 +121,7 @@ pub fn export_module(output: &Document) -> SourceResult<Vec<u8>> {
         pages.push(Page {
             content,
             size: page.size().into(),
+            label: LowerBuilder::lower_page_label(page),
         });
     }
 
@@ -116,8 +140,7 @@ pub fn export_module(output: &Document) -> SourceResult<Vec<u8>> {
                 LayoutRegionNode::Pages(Arc::new((Default::default(), pages))),
             )],
         })])),
-    ])
-    .to_bytes();
+    ]);
 
     Ok(module_data)
 }
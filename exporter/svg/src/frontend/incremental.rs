@@ -7,10 +7,11 @@ use std::{
 use typst_ts_core::{
     hash::Fingerprint,
     vector::{
-        flat_ir::{LayoutRegionNode, Module, ModuleBuilder, Page},
+        cull::ViewportCuller,
+        flat_ir::{LayoutRegionNode, Module, Page},
         flat_vm::{FlatIncrRenderVm, FlatRenderVm},
         incr::{IncrDocClient, IncrDocServer},
-        ir::{Rect, SvgItem},
+        ir::Rect,
     },
 };
 
@@ -72,6 +73,7 @@ impl<Feat: ExportFeature> SvgTask<Feat> {
             Page {
                 content: entry,
                 size,
+                ..
             },
         ) in ctx.next.iter().enumerate()
         {
@@ -136,9 +138,8 @@ pub struct IncrSvgDocClient {
     /// committed.
     pub glyph_window: usize,
 
-    /// Don't use this
-    /// it is public to make Default happy
-    pub mb: ModuleBuilder,
+    /// Culls the items out of the window, with cached bboxes.
+    pub culler: ViewportCuller,
 }
 
 impl IncrSvgDocClient {
@@ -152,44 +153,25 @@ impl IncrSvgDocClient {
     pub fn render_in_window(&mut self, kern: &mut IncrDocClient, rect: Rect) -> String {
         type IncrExporter = SvgExporter<IncrementalExportFeature>;
 
-        // prepare an empty page for the pages that are not rendered
-        // todo: better solution?
-        let empty_page = self.mb.build(SvgItem::Group(Default::default()));
-        kern.module_mut()
-            .items
-            .extend(self.mb.items.iter().map(|(f, (_, v))| (*f, v.clone())));
-
         // get previous doc_view
         // it is exact state of the current DOM.
         let prev_doc_view = self.doc_view.take().unwrap_or_default();
 
         // render next doc_view
-        // for pages that is not in the view, we use empty_page
+        // the items that are not in the window are culled, and the pages
+        // that are not in the window are replaced by empty pages.
         // otherwise, we keep document layout
-        let mut page_off: f32 = 0.;
         let mut next_doc_view = vec![];
         if let Some(t) = &kern.layout {
             let pages = match t {
                 LayoutRegionNode::Pages(a) => {
                     let (_, pages) = a.deref();
-                    pages
+                    pages.clone()
                 }
                 _ => todo!(),
             };
-            for page in pages.iter() {
-                page_off += page.size.y.0;
-                if page_off < rect.lo.y.0 || page_off - page.size.y.0 > rect.hi.y.0 {
-                    next_doc_view.push(Page {
-                        content: empty_page,
-                        size: page.size,
-                    });
-                    continue;
-                }
-
-                next_doc_view.push(page.clone());
-            }
+            next_doc_view = self.culler.cull_pages(kern.module_mut(), &pages, rect);
         }
-        // todo: fix this
 
         let mut t = SvgTask::<IncrementalExportFeature>::default();
 
@@ -1,6 +1,6 @@
-use std::sync::Arc;
+use std::{io, sync::Arc};
 
-use typst::doc::Document;
+use typst::doc::{Document, Frame};
 use typst_ts_core::{
     font::GlyphProvider,
     hash::FingerprintBuilder,
@@ -23,19 +23,33 @@ pub(crate) mod flat;
 #[cfg(feature = "flat-vector")]
 pub(crate) mod incremental;
 use crate::{
-    backend::{SvgGlyphBuilder, SvgText, SvgTextNode},
+    backend::{font_face, write_text, SvgGlyphBuilder, SvgText, SvgTextNode},
+    transform::{SvgTransform, TransformPipeline},
     utils::AbsExt,
     ExportFeature,
 };
 pub use incremental::{IncrSvgDocClient, IncrSvgDocServer, IncrementalRenderContext};
 
 pub struct SvgExporter<Feat: ExportFeature> {
+    /// The transforms applied to the svg text.
+    /// See [`ExportFeature::transforms`].
+    pub(crate) transforms: TransformPipeline,
+
     pub _feat_phantom: std::marker::PhantomData<Feat>,
 }
 
 impl<Feat: ExportFeature> Default for SvgExporter<Feat> {
     fn default() -> Self {
+        Self::new(Feat::transforms())
+    }
+}
+
+impl<Feat: ExportFeature> SvgExporter<Feat> {
+    /// Create an exporter with the transforms applied to the svg text,
+    /// instead of the ones of the feature set.
+    pub fn new(transforms: TransformPipeline) -> Self {
         Self {
+            transforms,
             _feat_phantom: std::marker::PhantomData,
         }
     }
@@ -111,6 +125,17 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
         mut body: Vec<SvgText>,
         glyphs: impl IntoIterator<Item = SvgText>,
     ) -> Vec<SvgText> {
+        let mut svg = Self::render_svg_head(header);
+        Self::render_svg_defs(t, glyphs, &mut svg);
+        svg.append(&mut body);
+        Self::render_svg_tail(&mut svg);
+        svg
+    }
+
+    /// Render the header and the base style of SVG.
+    /// <svg> <style/> .. </svg>
+    /// ^^^^^^^^^^^^^^
+    fn render_svg_head(header: String) -> Vec<SvgText> {
         let mut svg = vec![
             SvgText::Plain(header),
             // base style
@@ -122,18 +147,31 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
             svg.push("</style>".into());
         }
 
+        svg
+    }
+
+    /// Render the definitions collected by the task.
+    /// <svg> <defs/> <defs/> <style/> .. </svg>
+    ///       ^^^^^^^^^^^^^^^^^^^^^^^^
+    fn render_svg_defs(
+        t: SvgTask<Feat>,
+        glyphs: impl IntoIterator<Item = SvgText>,
+        svg: &mut Vec<SvgText>,
+    ) {
         // attach the glyph defs, clip paths, and style defs
         svg.push(r#"<defs class="glyph">"#.into());
         svg.extend(glyphs);
         svg.push("</defs>".into());
         svg.push(r#"<defs class="clip-path">"#.into());
-        Self::clip_paths(t.clip_paths, &mut svg);
+        Self::clip_paths(t.clip_paths, svg);
         svg.push("</defs>".into());
-        Self::style_defs(t.style_defs, &mut svg);
-
-        // body
-        svg.append(&mut body);
+        Self::style_defs(t.style_defs, svg);
+    }
 
+    /// Render the script and close SVG.
+    /// <svg> .. <script/> </svg>
+    ///          ^^^^^^^^^^^^^^^^
+    fn render_svg_tail(svg: &mut Vec<SvgText>) {
         if Feat::WITH_RESPONSIVE_JS {
             // attach the javascript for animations
             svg.push(r#"<script type="text/javascript">"#.into());
@@ -143,16 +181,12 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
 
         // close SVG
         svg.push("</svg>".into());
-
-        svg
     }
 
     /// Render SVG for [`Document`].
     /// It does not flatten the vector items before rendering so called
     /// "transient".
     pub(crate) fn render_transient_svg(output: &Document) -> Vec<SvgText> {
-        let mut t = SvgTask::<Feat>::default();
-
         // render SVG header
         let header = Self::header_doc(output);
 
@@ -164,18 +198,92 @@ impl<Feat: ExportFeature> SvgExporter<Feat> {
             .map(|p| lower_builder.lower(p))
             .collect::<Vec<_>>();
 
+        Self::render_transient_frames(header, &output.pages, pages)
+    }
+
+    /// Render each page of [`Document`] into a standalone SVG, whose viewBox
+    /// is the page and which only contains the definitions used by the page.
+    /// The pages are rendered lazily in order.
+    pub(crate) fn render_transient_svg_pages(
+        output: &Document,
+    ) -> impl Iterator<Item = (Axes<u32>, Vec<SvgText>)> + '_ {
+        let mut lower_builder = LowerBuilder::new(output);
+        output.pages.iter().map(move |p| {
+            let size = SvgTask::<Feat>::page_size(p.size().into());
+            let header = Self::header_inner(size.x as f32, size.y as f32);
+            let page = lower_builder.lower(p);
+            let svg = Self::render_transient_frames(header, std::slice::from_ref(p), vec![page]);
+            (size, svg)
+        })
+    }
+
+    /// Write SVG for [`Document`] to the writer page by page, so that only the
+    /// svg text of a single page is held in memory at a time.
+    ///
+    /// Unlike [`Self::render_transient_svg`], the definitions are written
+    /// after the pages, since they are collected in rendering the pages. The
+    /// transforms are applied to each page, and to the header and the
+    /// definitions separately.
+    pub(crate) fn write_transient_svg(
+        output: &Document,
+        transforms: &TransformPipeline,
+        w: &mut impl io::Write,
+    ) -> io::Result<()> {
+        let header = Self::render_svg_head(Self::header_doc(output));
+        write_text(transforms.transform(header), w)?;
+
+        let mut t = SvgTask::<Feat>::default();
+        let mut lower_builder = LowerBuilder::new(output);
+        let mut acc_height = 0u32;
+        for frame in &output.pages {
+            let page = lower_builder.lower(frame);
+            let mut svg_body = vec![];
+            acc_height += t.render_frame_transient(frame, &page, acc_height, &mut svg_body);
+            write_text(transforms.transform(svg_body), w)?;
+        }
+
+        let mut svg = vec![];
+        let glyphs = Self::render_glyph_defs(&mut t);
+        Self::render_svg_defs(t, glyphs, &mut svg);
+        Self::render_svg_tail(&mut svg);
+        write_text(transforms.transform(svg), w)
+    }
+
+    /// Render the lowered frames into a SVG, in which the frames are stacked
+    /// vertically.
+    fn render_transient_frames(
+        header: String,
+        frames: &[Frame],
+        pages: Vec<SvgItem>,
+    ) -> Vec<SvgText> {
+        let mut t = SvgTask::<Feat>::default();
+
         // render SVG body
         let mut svg_body = vec![];
-        t.render_pages_transient(output, pages, &mut svg_body);
-
-        // render the glyphs collected from the pages
-        let (_, glyphs) = std::mem::take(&mut t.glyph_defs).finalize();
-        let glyphs = t.render_glyphs(glyphs.iter().enumerate().map(|(x, (_, y))| (x, y)), false);
+        t.render_frames_transient(frames, pages, &mut svg_body);
 
         // template SVG
+        let glyphs = Self::render_glyph_defs(&mut t);
         Self::render_svg_template(t, header, svg_body, glyphs)
     }
 
+    /// Render the glyphs collected from the pages.
+    fn render_glyph_defs(t: &mut SvgTask<Feat>) -> Vec<SvgText> {
+        let (_, glyphs) = t.glyph_defs.finalize();
+        let glyphs = glyphs.iter().enumerate().map(|(x, (_, y))| (x, y));
+        if Feat::USE_FONT_FACE {
+            // the glyphs rendered by subsetted fonts need no definitions.
+            let (font_face_glyphs, glyphs): (Vec<_>, Vec<_>) =
+                glyphs.partition(|(_, y)| font_face::is_font_face_glyph(y));
+            let mut defs =
+                font_face::render_font_faces(font_face_glyphs.into_iter().map(|(_, y)| y));
+            defs.extend(t.render_glyphs(glyphs.into_iter(), false));
+            defs
+        } else {
+            t.render_glyphs(glyphs, false)
+        }
+    }
+
     /// Render SVG wrapped with HTML for [`Document`].
     /// It does not flatten the vector items before rendering so called
     /// "transient".
@@ -337,6 +445,32 @@ impl<Feat: ExportFeature> SvgTask<Feat> {
         pages: Vec<SvgItem>,
         svg_body: &mut Vec<SvgText>,
     ) {
+        self.render_frames_transient(&output.pages, pages, svg_body)
+    }
+
+    /// Render the lowered frames into the svg_body.
+    fn render_frames_transient(
+        &mut self,
+        frames: &[Frame],
+        pages: Vec<SvgItem>,
+        svg_body: &mut Vec<SvgText>,
+    ) {
+        // accumulate the height of pages
+        let mut acc_height = 0u32;
+        for (frame, page) in frames.iter().zip(pages.iter()) {
+            acc_height += self.render_frame_transient(frame, page, acc_height, svg_body);
+        }
+    }
+
+    /// Render a lowered frame at the given height into the svg_body,
+    /// returning the height of the page.
+    fn render_frame_transient(
+        &mut self,
+        frame: &Frame,
+        page: &SvgItem,
+        acc_height: u32,
+        svg_body: &mut Vec<SvgText>,
+    ) -> u32 {
         #[cfg(feature = "flat-vector")]
         let module = Module::default();
         let mut render_task = {
@@ -351,24 +485,20 @@ impl<Feat: ExportFeature> SvgTask<Feat> {
 
         render_task.use_stable_glyph_id = false;
 
-        // accumulate the height of pages
-        let mut acc_height = 0u32;
-        for (idx, page) in pages.iter().enumerate() {
-            let size = Self::page_size(output.pages[idx].size().into());
-
-            let attributes = vec![
-                ("transform", format!("translate(0, {})", acc_height)),
-                ("data-page-width", size.x.to_string()),
-                ("data-page-height", size.y.to_string()),
-            ];
-
-            let page_svg = render_task.render_item(page);
-
-            svg_body.push(SvgText::Content(Arc::new(SvgTextNode {
-                attributes,
-                content: vec![SvgText::Content(page_svg)],
-            })));
-            acc_height += size.y;
-        }
+        let size = Self::page_size(frame.size().into());
+
+        let attributes = vec![
+            ("transform", format!("translate(0, {})", acc_height)),
+            ("data-page-width", size.x.to_string()),
+            ("data-page-height", size.y.to_string()),
+        ];
+
+        let page_svg = render_task.render_item(page);
+
+        svg_body.push(SvgText::Content(Arc::new(SvgTextNode {
+            attributes,
+            content: vec![SvgText::Content(page_svg)],
+        })));
+        size.y
     }
 }
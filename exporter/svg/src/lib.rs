@@ -1,11 +1,12 @@
 //! Rendering into svg text or module.
 
+use std::io::{self, Write};
 use std::sync::Arc;
 
 use typst::doc::Document;
 use typst::{diag::SourceResult, World};
 
-use typst_ts_core::Exporter;
+use typst_ts_core::{exporter_utils::map_err, Exporter, Transformer};
 
 /// re-export the core types.
 pub use typst_ts_core::font::{FontGlyphProvider, GlyphProvider, IGlyphProvider};
@@ -19,8 +20,12 @@ pub(crate) mod utils;
 
 /// (Text) backend of SVG export.
 pub(crate) mod backend;
-use backend::generate_text;
-pub use backend::SvgGlyphBuilder;
+use backend::{generate_text, write_text};
+pub use backend::{SvgGlyphBuilder, SvgText, SvgTextNode};
+
+/// Compressed output of the streaming exporters.
+pub(crate) mod compress;
+pub use compress::{BrotliExporter, GzipExporter};
 
 /// frontend of SVG export, which provides a bunch of approaches to rendering
 /// the document.
@@ -32,7 +37,8 @@ pub use frontend::{
 pub use frontend::{SvgExporter, SvgTask};
 
 /// Useful transform for SVG Items.
-pub(crate) mod transform;
+pub mod transform;
+use transform::{Minify, SvgTransform, TransformPipeline};
 
 /// All the features that can be enabled or disabled.
 pub trait ExportFeature {
@@ -61,6 +67,22 @@ pub trait ExportFeature {
 
     /// Also escape html entity.
     const AWARE_HTML_ENTITY: bool;
+
+    /// Whether to render glyphs as text with subsetted fonts, which are
+    /// embedded as WOFF2 `@font-face` data.
+    /// If disabled, glyphs are rendered as outlines in `<symbol>` definitions.
+    ///
+    /// It is much smaller for text-heavy documents, but glyphs that cannot be
+    /// subsetted, e.g. color and bitmap glyphs, are still rendered as
+    /// definitions. Only effective in transient rendering, since the vector
+    /// artifact carries glyph packs instead of fonts.
+    const USE_FONT_FACE: bool = false;
+
+    /// The transforms applied to the svg text before it is written.
+    /// By default, the svg text is minified.
+    fn transforms() -> TransformPipeline {
+        TransformPipeline::new().then(Minify)
+    }
 }
 
 /// The default feature set which is used for exporting full-fledged svg.
@@ -91,37 +113,162 @@ impl ExportFeature for SvgExportFeature {
     const AWARE_HTML_ENTITY: bool = false;
 }
 
+/// The feature set which is used for exporting full-fledged svg, whose glyphs
+/// are rendered with subsetted fonts.
+/// See [`ExportFeature::USE_FONT_FACE`].
+pub struct FontFaceExportFeature;
+pub type FontFaceSvgTask = SvgTask<FontFaceExportFeature>;
+
+impl ExportFeature for FontFaceExportFeature {
+    const ENABLE_TRACING: bool = false;
+    const SHOULD_ATTACH_DEBUG_INFO: bool = false;
+    const SHOULD_RENDER_TEXT_ELEMENT: bool = true;
+    const USE_STABLE_GLYPH_ID: bool = true;
+    const WITH_BUILTIN_CSS: bool = true;
+    const WITH_RESPONSIVE_JS: bool = true;
+    const AWARE_HTML_ENTITY: bool = true;
+    const USE_FONT_FACE: bool = true;
+}
+
 /// Render SVG wrapped with html for [`Document`].
 pub fn render_svg_html(output: &Document) -> String {
-    type UsingExporter = SvgExporter<DefaultExportFeature>;
-    let svg_text = UsingExporter::render_transient_html(output);
-    generate_text(transform::minify(svg_text))
+    render_svg_html_with::<DefaultExportFeature>(output)
+}
+
+/// Render SVG wrapped with html for [`Document`] with the given feature set.
+pub fn render_svg_html_with<Feat: ExportFeature>(output: &Document) -> String {
+    let svg_text = SvgExporter::<Feat>::render_transient_html(output);
+    generate_text(Feat::transforms().transform(svg_text))
 }
 
 /// Render SVG for [`Document`].
 pub fn render_svg(output: &Document) -> String {
-    type UsingExporter = SvgExporter<SvgExportFeature>;
-    let svg_text = UsingExporter::render_transient_svg(output);
-    generate_text(transform::minify(svg_text))
+    PureSvgExporter::default().render(output)
+}
+
+/// A page of [`Document`] rendered as a standalone SVG.
+pub struct SvgPage {
+    /// The width of the page in pt, rounded up as in the viewBox.
+    pub width: u32,
+    /// The height of the page in pt, rounded up as in the viewBox.
+    pub height: u32,
+    /// The SVG text of the page.
+    pub svg: String,
+}
+
+/// Render each page of [`Document`] into a standalone SVG, which only contains
+/// the glyphs, clip paths and styles used by the page.
+/// The pages are rendered lazily in order.
+pub fn render_svg_pages(output: &Document) -> impl Iterator<Item = SvgPage> + '_ {
+    PureSvgExporter::default().render_pages(output)
+}
+
+/// Write SVG wrapped with html for [`Document`] to the writer with the given
+/// feature set, without building the entire text in memory.
+pub fn write_svg_html_with<Feat: ExportFeature>(
+    output: &Document,
+    w: &mut impl io::Write,
+) -> io::Result<()> {
+    let svg_text = SvgExporter::<Feat>::render_transient_html(output);
+    write_text(Feat::transforms().transform(svg_text), w)
+}
+
+/// Write SVG for [`Document`] to the writer, without building the entire text
+/// in memory.
+pub fn write_svg(output: &Document, w: &mut impl io::Write) -> io::Result<()> {
+    PureSvgExporter::default().write(output, w)
 }
 
 #[cfg(feature = "flat-vector")]
-pub use frontend::flat::export_module;
+pub use frontend::flat::{export_module, lower_module};
 
 impl<Feat: ExportFeature> Exporter<Document, String> for SvgExporter<Feat> {
     fn export(&self, _world: &dyn World, output: Arc<Document>) -> SourceResult<String> {
         // html wrap
-        Ok(render_svg_html(&output))
+        let svg_text = Self::render_transient_html(&output);
+        Ok(generate_text(self.transforms.transform(svg_text)))
     }
 }
 
-#[derive(Default)]
-pub struct PureSvgExporter;
+/// Write the html to the writer without building the final string.
+///
+/// Note that the svg text tree of the whole document is still built before
+/// writing, since the glyph definitions are shared by all of the pages, so
+/// the peak memory is only roughly halved rather than bounded by a page.
+impl<Feat: ExportFeature, W: io::Write> Transformer<(Arc<Document>, W)> for SvgExporter<Feat> {
+    fn export(&self, _world: &dyn World, (output, writer): (Arc<Document>, W)) -> SourceResult<()> {
+        // the text is written in many small pieces
+        let mut writer = io::BufWriter::new(writer);
+        let svg_text = Self::render_transient_html(&output);
+        write_text(self.transforms.transform(svg_text), &mut writer).map_err(map_err)?;
+        writer.flush().map_err(map_err)
+    }
+}
+
+/// Export SVG for [`Document`], in which the pages are stacked vertically.
+pub struct PureSvgExporter {
+    transforms: TransformPipeline,
+}
+
+impl Default for PureSvgExporter {
+    fn default() -> Self {
+        Self::new(SvgExportFeature::transforms())
+    }
+}
+
+impl PureSvgExporter {
+    /// Create an exporter with the transforms applied to the svg text.
+    pub fn new(transforms: TransformPipeline) -> Self {
+        Self { transforms }
+    }
+
+    /// Render SVG for [`Document`].
+    pub fn render(&self, output: &Document) -> String {
+        // render through the writer, so that the rendered and the written svg
+        // are identical.
+        let mut svg = vec![];
+        self.write(output, &mut svg)
+            .expect("writing to a vector never fails");
+        String::from_utf8(svg).expect("svg text is valid utf-8")
+    }
+
+    /// Write SVG for [`Document`] to the writer page by page, so that the svg
+    /// text of only one page is held in memory at a time.
+    ///
+    /// The glyph definitions, clip paths and styles are written after the
+    /// pages, since they are collected in rendering the pages.
+    pub fn write(&self, output: &Document, w: &mut impl io::Write) -> io::Result<()> {
+        type UsingExporter = SvgExporter<SvgExportFeature>;
+        UsingExporter::write_transient_svg(output, &self.transforms, w)
+    }
+
+    /// Render each page of [`Document`] into a standalone SVG.
+    /// See [`render_svg_pages`].
+    pub fn render_pages<'a>(&self, output: &'a Document) -> impl Iterator<Item = SvgPage> + 'a {
+        type UsingExporter = SvgExporter<SvgExportFeature>;
+        let transforms = self.transforms.clone();
+        UsingExporter::render_transient_svg_pages(output).map(move |(size, svg_text)| SvgPage {
+            width: size.x,
+            height: size.y,
+            svg: generate_text(transforms.transform(svg_text)),
+        })
+    }
+}
 
 impl Exporter<Document, String> for PureSvgExporter {
     fn export(&self, _world: &dyn World, output: Arc<Document>) -> SourceResult<String> {
-        // html wrap
-        Ok(render_svg(&output))
+        Ok(self.render(&output))
+    }
+}
+
+/// Write the svg to the writer page by page.
+/// See [`PureSvgExporter::write`].
+impl<W: io::Write> Transformer<(Arc<Document>, W)> for PureSvgExporter {
+    fn export(&self, _world: &dyn World, (output, writer): (Arc<Document>, W)) -> SourceResult<()> {
+        // the text is written in many small pieces
+        let mut writer = io::BufWriter::new(writer);
+        self.write(&output, &mut writer).map_err(map_err)?;
+        writer.flush().map_err(map_err)
     }
 }
 
@@ -133,3 +280,27 @@ impl Exporter<Document, Vec<u8>> for SvgModuleExporter {
         export_module(&output)
     }
 }
+
+/// Export the flat module of a document with an exporter of [`FlatModule`],
+/// e.g. `JsonExporter<FlatModule>` of `typst-ts-serde-exporter` when the
+/// `vector-serde` feature of `typst-ts-core` is enabled.
+#[cfg(feature = "flat-vector")]
+#[derive(Default)]
+pub struct FlatModuleExporter<E> {
+    exporter: E,
+}
+
+#[cfg(feature = "flat-vector")]
+impl<E> FlatModuleExporter<E> {
+    pub fn new(exporter: E) -> Self {
+        Self { exporter }
+    }
+}
+
+#[cfg(feature = "flat-vector")]
+impl<E: Exporter<FlatModule, T>, T> Exporter<Document, T> for FlatModuleExporter<E> {
+    fn export(&self, world: &dyn World, output: Arc<Document>) -> SourceResult<T> {
+        let module = lower_module(&output)?;
+        self.exporter.export(world, Arc::new(module))
+    }
+}
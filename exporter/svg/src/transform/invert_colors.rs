@@ -0,0 +1,149 @@
+use crate::backend::SvgText;
+
+use super::{
+    rewrite::{rewrite, AttributeRewrite, Rewrite},
+    SvgTransform,
+};
+
+/// The attributes consisting of a color.
+const COLOR_ATTRIBUTES: &[&str] = &["fill", "stroke", "color", "stop-color", "flood-color"];
+
+/// Invert the colors of shapes and text for dark mode, i.e. the colors in the
+/// color attributes and in the style definitions. Images are kept as is.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InvertColors;
+
+impl SvgTransform for InvertColors {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText> {
+        rewrite(svg, self)
+    }
+}
+
+impl AttributeRewrite for InvertColors {
+    fn rewrite_attribute(&self, name: &str, value: &str) -> Rewrite {
+        if !COLOR_ATTRIBUTES.contains(&name) {
+            return Rewrite::Keep;
+        }
+
+        match invert_color(value) {
+            Some(color) => Rewrite::Replace(color),
+            None => Rewrite::Keep,
+        }
+    }
+
+    fn rewrite_style(&self, css: &str) -> Option<String> {
+        let mut res = String::with_capacity(css.len());
+        let mut rest = css;
+        // the depth of blocks, and whether we are in the value of a declaration
+        let mut depth = 0usize;
+        let mut in_value = false;
+        while let Some(start) =
+            rest.find(|c: char| matches!(c, '{' | '}' | ';' | ':' | '#') || c.is_ascii_alphabetic())
+        {
+            res.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let len = match rest.as_bytes()[0] {
+                b'{' => {
+                    depth += 1;
+                    in_value = false;
+                    res.push('{');
+                    1
+                }
+                b'}' => {
+                    depth = depth.saturating_sub(1);
+                    in_value = false;
+                    res.push('}');
+                    1
+                }
+                b';' => {
+                    in_value = false;
+                    res.push(';');
+                    1
+                }
+                b':' => {
+                    // a colon in a block starts a value, unless it belongs to
+                    // the selector of a nested rule, e.g. `a:hover { .. }`.
+                    let is_selector = rest
+                        .find(|c: char| matches!(c, '{' | '}' | ';'))
+                        .map_or(false, |i| rest.as_bytes()[i] == b'{');
+                    in_value |= depth > 0 && !is_selector;
+                    res.push(':');
+                    1
+                }
+                _ => {
+                    // a hex color or a word
+                    let len = rest[1..]
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+                        .map_or(rest.len(), |i| i + 1);
+                    let word = &rest[..len];
+                    if word == "url" && rest[len..].starts_with('(') {
+                        // keep the references to ids, e.g. `url(#fed)`
+                        let len = rest.find(')').map_or(rest.len(), |i| i + 1);
+                        res.push_str(&rest[..len]);
+                        len
+                    } else {
+                        match invert_color(word).filter(|_| in_value) {
+                            Some(color) => res.push_str(&color),
+                            None => res.push_str(word),
+                        }
+                        len
+                    }
+                }
+            };
+            rest = &rest[len..];
+        }
+        res.push_str(rest);
+        Some(res)
+    }
+}
+
+/// Invert a color in hex notation, `black` or `white`, keeping the alpha
+/// channel.
+fn invert_color(color: &str) -> Option<String> {
+    match color {
+        "black" => return Some("white".to_owned()),
+        "white" => return Some("black".to_owned()),
+        _ => {}
+    }
+
+    let hex = color.strip_prefix('#')?;
+    if !matches!(hex.len(), 3 | 4 | 6 | 8) || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // the rgb channels are the first 3 or 6 digits
+    let rgb_len = if hex.len() <= 4 { 3 } else { 6 };
+    let inverted = hex[..rgb_len]
+        .chars()
+        .map(|c| {
+            let v = c.to_digit(16).unwrap();
+            char::from_digit(15 - v, 16).unwrap()
+        })
+        .collect::<String>();
+    Some(format!("#{inverted}{}", &hex[rgb_len..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invert_style() {
+        let invert = |css: &str| InvertColors.rewrite_style(css).unwrap();
+
+        assert_eq!(
+            invert(".a { fill: #000; stroke: black } #fed { color: #ff000080 }"),
+            ".a { fill: #fff; stroke: white } #fed { color: #00ffff80 }"
+        );
+        // the selectors and the references to ids are kept
+        assert_eq!(
+            invert("g#abc:hover, .black { fill: url(#abc) } #fed:hover{fill:#fed}"),
+            "g#abc:hover, .black { fill: url(#abc) } #fed:hover{fill:#012}"
+        );
+        assert_eq!(
+            invert("@media (prefers-color-scheme: dark) { a:hover #fed { fill: white } }"),
+            "@media (prefers-color-scheme: dark) { a:hover #fed { fill: black } }"
+        );
+    }
+}
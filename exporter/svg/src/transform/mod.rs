@@ -1,2 +1,86 @@
+//! Post-processing transforms over the generated svg text.
+//!
+//! A transform is applied to the text of a document before the text is
+//! written, and transforms are composed in order by [`TransformPipeline`]:
+//!
+//! ```ignore
+//! let pipeline = TransformPipeline::new()
+//!     .then(Minify)
+//!     .then(PrefixIds::new("doc1-"))
+//!     .then(RoundPrecision::new(2));
+//! ```
+
+use std::sync::Arc;
+
+use crate::backend::SvgText;
+
 mod minify;
 pub use minify::minify;
+
+mod rewrite;
+
+mod invert_colors;
+pub use invert_colors::InvertColors;
+mod prefix_ids;
+pub use prefix_ids::PrefixIds;
+mod precision;
+pub use precision::RoundPrecision;
+mod strip_data;
+pub use strip_data::StripDataAttributes;
+
+/// A post-processing transform over the generated svg text.
+///
+/// The svg may be transformed in several chunks, e.g. page by page in
+/// [`crate::PureSvgExporter::write`], each of which consists of complete
+/// elements.
+pub trait SvgTransform: Send + Sync {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText>;
+}
+
+/// Do semantic-aware minification of SVG.
+/// See [`minify`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Minify;
+
+impl SvgTransform for Minify {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText> {
+        minify(svg)
+    }
+}
+
+/// A sequence of transforms which are applied in order.
+/// The pipeline is also a transform, so that pipelines can be nested.
+#[derive(Clone, Default)]
+pub struct TransformPipeline {
+    transforms: Vec<Arc<dyn SvgTransform>>,
+}
+
+impl TransformPipeline {
+    /// Create an empty pipeline, which keeps the svg text as is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a transform to the pipeline.
+    pub fn then(mut self, transform: impl SvgTransform + 'static) -> Self {
+        self.push(transform);
+        self
+    }
+
+    /// Append a transform to the pipeline.
+    pub fn push(&mut self, transform: impl SvgTransform + 'static) {
+        self.transforms.push(Arc::new(transform));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+}
+
+impl SvgTransform for TransformPipeline {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText> {
+        self.transforms
+            .iter()
+            .fold(svg, |svg, transform| transform.transform(svg))
+    }
+}
@@ -0,0 +1,120 @@
+use crate::backend::SvgText;
+
+use super::{
+    rewrite::{rewrite, AttributeRewrite, Rewrite},
+    SvgTransform,
+};
+
+/// The attributes consisting of geometric numbers.
+///
+/// Note that `transform` is not rounded, since its coefficients scale all of
+/// the geometry inside, e.g. the text is scaled by `ppem`, which is usually
+/// far less than `1`.
+const GEOMETRY_ATTRIBUTES: &[&str] = &[
+    "d",
+    "viewBox",
+    "x",
+    "y",
+    "width",
+    "height",
+    "stroke-width",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-miterlimit",
+];
+
+/// Round the numbers in geometric attributes, e.g. the paths and sizes, to the
+/// given number of decimal digits, which reduces the size of svg.
+#[derive(Debug, Clone, Copy)]
+pub struct RoundPrecision {
+    digits: usize,
+}
+
+impl RoundPrecision {
+    pub fn new(digits: usize) -> Self {
+        Self { digits }
+    }
+
+    fn round_numbers(&self, value: &str) -> String {
+        let mut res = String::with_capacity(value.len());
+        let mut rest = value;
+        while let Some(start) = find_number(rest) {
+            res.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let len = number_len(rest);
+            match rest[..len].parse::<f64>() {
+                Ok(x) => self.write_number(x, &mut res),
+                Err(_) => res.push_str(&rest[..len]),
+            }
+            rest = &rest[len..];
+        }
+        res.push_str(rest);
+        res
+    }
+
+    fn write_number(&self, x: f64, res: &mut String) {
+        let x = format!("{:.*}", self.digits, x);
+        let x = if x.contains('.') {
+            x.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            &x
+        };
+        res.push_str(if x == "-0" { "0" } else { x });
+    }
+}
+
+impl SvgTransform for RoundPrecision {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText> {
+        rewrite(svg, self)
+    }
+}
+
+impl AttributeRewrite for RoundPrecision {
+    fn rewrite_attribute(&self, name: &str, value: &str) -> Rewrite {
+        if GEOMETRY_ATTRIBUTES.contains(&name) {
+            Rewrite::Replace(self.round_numbers(value))
+        } else {
+            Rewrite::Keep
+        }
+    }
+}
+
+/// Find the start of a number.
+/// Note that a number may follow a path command immediately, e.g. `M1 2`.
+fn find_number(value: &str) -> Option<usize> {
+    let bytes = value.as_bytes();
+    (0..bytes.len()).find(|&i| match bytes[i] {
+        b'0'..=b'9' => true,
+        b'-' | b'.' => bytes.get(i + 1).map_or(false, u8::is_ascii_digit),
+        _ => false,
+    })
+}
+
+/// The length of the number at the beginning of the value, with an optional
+/// sign, fraction and exponent.
+fn number_len(value: &str) -> usize {
+    let bytes = value.as_bytes();
+    let digits = |mut i: usize| {
+        while bytes.get(i).map_or(false, u8::is_ascii_digit) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut i = usize::from(bytes[0] == b'-');
+    i = digits(i);
+    if bytes.get(i) == Some(&b'.') {
+        i = digits(i + 1);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'-' | b'+')) {
+            j += 1;
+        }
+        if bytes.get(j).map_or(false, u8::is_ascii_digit) {
+            i = digits(j);
+        }
+    }
+    i
+}
@@ -0,0 +1,44 @@
+use crate::backend::SvgText;
+
+use super::{
+    rewrite::{rewrite, AttributeRewrite, Rewrite},
+    SvgTransform,
+};
+
+/// Prefix the ids and the references to them, so that multiple documents can
+/// be embedded in one html page without conflicts, since the entire html
+/// document shares namespace for ids.
+#[derive(Debug, Clone)]
+pub struct PrefixIds {
+    prefix: String,
+}
+
+impl PrefixIds {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl SvgTransform for PrefixIds {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText> {
+        rewrite(svg, self)
+    }
+}
+
+impl AttributeRewrite for PrefixIds {
+    fn rewrite_attribute(&self, name: &str, value: &str) -> Rewrite {
+        match name {
+            "id" | "data-reuse-from" => Rewrite::Replace(format!("{}{value}", self.prefix)),
+            "href" | "xlink:href" => match value.strip_prefix('#') {
+                Some(id) => Rewrite::Replace(format!("#{}{id}", self.prefix)),
+                None => Rewrite::Keep,
+            },
+            _ if value.contains("url(#") => {
+                Rewrite::Replace(value.replace("url(#", &format!("url(#{}", self.prefix)))
+            }
+            _ => Rewrite::Keep,
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::{borrow::Cow, sync::Arc};
+
+use crate::backend::{SvgText, SvgTextNode};
+
+/// The edit to an attribute.
+///
+/// A replaced value is escaped when written, as the value passed to
+/// [`AttributeRewrite::rewrite_attribute`] is unescaped.
+pub(crate) enum Rewrite {
+    Keep,
+    Replace(String),
+    Remove,
+}
+
+/// Rewrites the attributes of the svg text, i.e. the attributes of
+/// [`SvgTextNode`] and of the tags in [`SvgText::Plain`].
+///
+/// The tags in comments are left as is. A tag or a raw text element split
+/// across plain texts is rewritten as a whole.
+pub(crate) trait AttributeRewrite {
+    fn rewrite_attribute(&self, name: &str, value: &str) -> Rewrite;
+
+    /// Rewrite the content of a `<style>` element.
+    fn rewrite_style(&self, _css: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Apply the rewrite to the svg text.
+pub(crate) fn rewrite(svg: Vec<SvgText>, r: &impl AttributeRewrite) -> Vec<SvgText> {
+    let mut rewriter = Rewriter {
+        r,
+        raw_until: None,
+        pending: String::new(),
+    };
+    rewriter.rewrite_texts(svg)
+}
+
+struct Rewriter<'a, R> {
+    r: &'a R,
+    /// The end of the raw text which is being copied, i.e. the end tag of a
+    /// raw text element or the end of a comment. The raw text, e.g. the
+    /// bundled javascript, may be split across plain texts.
+    raw_until: Option<&'static str>,
+    /// The text at the end of the last plain text that cannot be rewritten
+    /// alone, e.g. an incomplete tag. It is rewritten together with the next
+    /// plain text.
+    pending: String,
+}
+
+impl<R: AttributeRewrite> Rewriter<'_, R> {
+    fn rewrite_texts(&mut self, texts: Vec<SvgText>) -> Vec<SvgText> {
+        let mut res = Vec::with_capacity(texts.len());
+        for text in texts {
+            match text {
+                SvgText::Plain(p) => {
+                    let p = self.rewrite_plain(&p);
+                    if !p.is_empty() {
+                        res.push(SvgText::Plain(p));
+                    }
+                }
+                SvgText::Content(mut c) => {
+                    self.flush_pending(&mut res);
+                    self.rewrite_node(Arc::make_mut(&mut c));
+                    res.push(SvgText::Content(c));
+                }
+            }
+        }
+        self.flush_pending(&mut res);
+        res
+    }
+
+    /// Write the pending text as is, since a tag never spans a text node.
+    fn flush_pending(&mut self, res: &mut Vec<SvgText>) {
+        if !self.pending.is_empty() {
+            res.push(SvgText::Plain(std::mem::take(&mut self.pending)));
+        }
+    }
+
+    fn rewrite_node(&mut self, node: &mut SvgTextNode) {
+        node.attributes.retain_mut(|(name, value)| {
+            match self.r.rewrite_attribute(name, &unescape(value)) {
+                Rewrite::Keep => {}
+                Rewrite::Replace(v) => *value = escape(&v).into_owned(),
+                Rewrite::Remove => return false,
+            }
+            true
+        });
+        let content = std::mem::take(&mut node.content);
+        node.content = self.rewrite_texts(content);
+    }
+
+    fn rewrite_plain(&mut self, text: &str) -> String {
+        let text = if self.pending.is_empty() {
+            Cow::Borrowed(text)
+        } else {
+            let mut pending = std::mem::take(&mut self.pending);
+            pending.push_str(text);
+            Cow::Owned(pending)
+        };
+
+        let mut res = String::with_capacity(text.len());
+        let mut rest = text.as_ref();
+        while !rest.is_empty() {
+            if let Some(end) = self.raw_until {
+                let Some(raw_len) = rest.find(end) else {
+                    // the style is rewritten as a whole, and the other raw
+                    // texts are copied except a possibly split end
+                    let keep = if end == "</style" {
+                        rest.len()
+                    } else {
+                        partial_suffix_len(rest, end)
+                    };
+                    res.push_str(&rest[..rest.len() - keep]);
+                    self.pending.push_str(&rest[rest.len() - keep..]);
+                    break;
+                };
+                let raw = &rest[..raw_len];
+                let css = (end == "</style")
+                    .then(|| self.r.rewrite_style(raw))
+                    .flatten();
+                res.push_str(css.as_deref().unwrap_or(raw));
+                res.push_str(end);
+                rest = &rest[raw_len + end.len()..];
+                self.raw_until = None;
+                continue;
+            }
+
+            let Some(lt) = rest.find('<') else {
+                res.push_str(rest);
+                break;
+            };
+            res.push_str(&rest[..lt]);
+            rest = &rest[lt..];
+
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                res.push_str("<!--");
+                rest = comment;
+                self.raw_until = Some("-->");
+            } else if "<!--".starts_with(rest) {
+                // a possibly split start of comment
+                self.pending.push_str(rest);
+                break;
+            } else if !rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+                // not a start tag, e.g. an end tag
+                res.push('<');
+                rest = &rest[1..];
+            } else if let Some(tag_len) = find_tag_end(rest) {
+                self.rewrite_start_tag(&rest[..tag_len], &mut res);
+                rest = &rest[tag_len..];
+            } else {
+                self.pending.push_str(rest);
+                break;
+            }
+        }
+        res
+    }
+
+    /// Rewrite a complete start tag.
+    fn rewrite_start_tag(&mut self, tag: &str, res: &mut String) {
+        let name_len = tag[1..]
+            .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+            .map_or(tag.len(), |i| i + 1);
+        let tag_name = &tag[1..name_len];
+        res.push_str(&tag[..name_len]);
+
+        let mut rest = &tag[name_len..];
+        loop {
+            let trimmed = rest.trim_start();
+            let Some((name, value, next)) = parse_attribute(trimmed) else {
+                // malformed attributes
+                res.push_str(rest);
+                return;
+            };
+            if name.is_empty() {
+                res.push_str(&rest[..rest.len() - trimmed.len()]);
+                rest = trimmed;
+                break;
+            }
+
+            let space = &rest[..rest.len() - trimmed.len()];
+            let raw = &trimmed[..trimmed.len() - next.len()];
+            let value = value.map(unescape);
+            match value.map(|value| self.r.rewrite_attribute(name, &value)) {
+                Some(Rewrite::Remove) => {}
+                Some(Rewrite::Replace(value)) => {
+                    res.push_str(space);
+                    res.push_str(name);
+                    res.push_str("=\"");
+                    res.push_str(&escape(&value));
+                    res.push('"');
+                }
+                Some(Rewrite::Keep) | None => {
+                    res.push_str(space);
+                    res.push_str(raw);
+                }
+            }
+            rest = next;
+        }
+
+        // the end of tag
+        let self_closing = rest.starts_with('/');
+        res.push_str(rest);
+        if !self_closing {
+            self.raw_until = match tag_name {
+                "style" => Some("</style"),
+                "script" => Some("</script"),
+                _ => None,
+            };
+        }
+    }
+}
+
+/// Find the end of a start tag at the beginning of the text, i.e. the
+/// position after the `>` which is not in a quoted value.
+fn find_tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut after_eq = false;
+    for (i, c) in text.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '>' => return Some(i + 1),
+            None if after_eq && matches!(c, '"' | '\'') => quote = Some(c),
+            None => {}
+        }
+        if !c.is_ascii_whitespace() {
+            after_eq = quote.is_none() && c == '=';
+        }
+    }
+    None
+}
+
+/// The length of the longest suffix of the text that is a proper prefix of
+/// `end`, i.e. the part of `end` which may be completed by the next text.
+fn partial_suffix_len(text: &str, end: &str) -> usize {
+    (1..end.len().min(text.len() + 1))
+        .rev()
+        .find(|&len| text.ends_with(&end[..len]))
+        .unwrap_or(0)
+}
+
+/// Parse an attribute at the beginning of the text, and return the name, the
+/// unquoted value and the rest text.
+/// An empty name is returned at the end of tag.
+fn parse_attribute(text: &str) -> Option<(&str, Option<&str>, &str)> {
+    if text.starts_with('>') || text.starts_with('/') {
+        return Some(("", None, text));
+    }
+
+    let name_len = text.find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))?;
+    let (name, rest) = text.split_at(name_len);
+    if name.is_empty() {
+        return None;
+    }
+    let Some(rest) = rest.strip_prefix('=') else {
+        return Some((name, None, rest));
+    };
+
+    let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+    let value_len = rest[1..].find(quote)?;
+    let value = &rest[1..value_len + 1];
+    Some((name, Some(value), &rest[value_len + 2..]))
+}
+
+/// Escape an attribute value to be quoted by `"`.
+fn escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(['&', '"', '<']) {
+        return Cow::Borrowed(value);
+    }
+
+    let mut res = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '"' => res.push_str("&quot;"),
+            '<' => res.push_str("&lt;"),
+            c => res.push(c),
+        }
+    }
+    Cow::Owned(res)
+}
+
+/// Unescape the predefined entities of xml in an attribute value. The other
+/// references are left as is.
+fn unescape(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return Cow::Borrowed(value);
+    }
+
+    const ENTITIES: [(&str, char); 5] = [
+        ("&amp;", '&'),
+        ("&quot;", '"'),
+        ("&apos;", '\''),
+        ("&lt;", '<'),
+        ("&gt;", '>'),
+    ];
+
+    let mut res = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        res.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        match ENTITIES.iter().find(|(e, _)| rest.starts_with(e)) {
+            Some((e, c)) => {
+                res.push(*c);
+                rest = &rest[e.len()..];
+            }
+            None => {
+                res.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    res.push_str(rest);
+    Cow::Owned(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::generate_text;
+
+    struct TestRewrite;
+
+    impl AttributeRewrite for TestRewrite {
+        fn rewrite_attribute(&self, name: &str, value: &str) -> Rewrite {
+            match name {
+                "id" => Rewrite::Replace(format!("x-{value}")),
+                "title" => Rewrite::Replace(format!("{value} <\"c\">")),
+                "data-drop" => Rewrite::Remove,
+                _ => Rewrite::Keep,
+            }
+        }
+
+        fn rewrite_style(&self, css: &str) -> Option<String> {
+            Some(css.replace("red", "blue"))
+        }
+    }
+
+    fn rewrite_plain(texts: &[&str]) -> String {
+        let texts = texts.iter().map(|t| SvgText::Plain(t.to_string()));
+        generate_text(rewrite(texts.collect(), &TestRewrite))
+    }
+
+    #[test]
+    fn test_rewrite_tag() {
+        assert_eq!(
+            rewrite_plain(&[r#"<rect id="a" data-drop="1" fill='red'/><g>"#]),
+            r#"<rect id="x-a" fill='red'/><g>"#
+        );
+        assert_eq!(
+            rewrite_plain(&[r#"<a href="?a>b" id='b'>"#]),
+            r#"<a href="?a>b" id="x-b">"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_split_tag() {
+        assert_eq!(
+            rewrite_plain(&["<re", r#"ct i"#, r#"d="a" data-dr"#, r#"op="1"/"#, "><g>"]),
+            r#"<rect id="x-a"/><g>"#
+        );
+        assert_eq!(
+            rewrite_plain(&["a <", "rect id=\"a\">"]),
+            "a <rect id=\"x-a\">"
+        );
+        // an incomplete tag at the end is kept as is
+        assert_eq!(rewrite_plain(&["<rect id=\"a\""]), "<rect id=\"a\"");
+    }
+
+    #[test]
+    fn test_rewrite_comment() {
+        assert_eq!(
+            rewrite_plain(&[r#"<!-- <rect id="a"/> --><rect id="b"/>"#]),
+            r#"<!-- <rect id="a"/> --><rect id="x-b"/>"#
+        );
+        assert_eq!(
+            rewrite_plain(&[
+                "<!",
+                "-- <rect ",
+                r#"id="a"/> -"#,
+                "-",
+                r#"><rect id="b"/>"#
+            ]),
+            r#"<!-- <rect id="a"/> --><rect id="x-b"/>"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_raw_text() {
+        assert_eq!(
+            rewrite_plain(&["<style>.a{fill:", "red}</sty", r#"le><rect id="c"/>"#]),
+            r#"<style>.a{fill:blue}</style><rect id="x-c"/>"#
+        );
+        assert_eq!(
+            rewrite_plain(&[
+                r#"<script>let a = '<rect id="a">';</scr"#,
+                r#"ipt><g id="b">"#
+            ]),
+            r#"<script>let a = '<rect id="a">';</script><g id="x-b">"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_escape() {
+        assert_eq!(
+            rewrite_plain(&[r#"<a title="a &amp; b">"#]),
+            r#"<a title="a &amp; b &lt;&quot;c&quot;>">"#
+        );
+
+        let node = SvgTextNode {
+            attributes: vec![("title", "&lt;a&gt;".to_owned()), ("id", "b".to_owned())],
+            content: vec![SvgText::Plain(r#"<rect id="c"/>"#.to_owned())],
+        };
+        let texts = rewrite(vec![SvgText::Content(Arc::new(node))], &TestRewrite);
+        assert_eq!(
+            generate_text(texts),
+            r#"<g title="&lt;a> &lt;&quot;c&quot;>" id="x-b"><rect id="x-c"/></g>"#
+        );
+    }
+}
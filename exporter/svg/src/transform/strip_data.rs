@@ -0,0 +1,50 @@
+use crate::backend::SvgText;
+
+use super::{
+    rewrite::{rewrite, AttributeRewrite, Rewrite},
+    SvgTransform,
+};
+
+/// Strip the `data-*` attributes, e.g. `data-span` attached for debugging.
+///
+/// By default, the page sizes read by the responsive script of the html
+/// output, i.e. `data-page-width` and `data-page-height`, are kept.
+#[derive(Debug, Clone)]
+pub struct StripDataAttributes {
+    keep: Vec<String>,
+}
+
+impl Default for StripDataAttributes {
+    fn default() -> Self {
+        Self::new().keep("data-page-width").keep("data-page-height")
+    }
+}
+
+impl StripDataAttributes {
+    /// Strip all of the `data-*` attributes.
+    pub fn new() -> Self {
+        Self { keep: vec![] }
+    }
+
+    /// Keep the attribute of the given name.
+    pub fn keep(mut self, name: impl Into<String>) -> Self {
+        self.keep.push(name.into());
+        self
+    }
+}
+
+impl SvgTransform for StripDataAttributes {
+    fn transform(&self, svg: Vec<SvgText>) -> Vec<SvgText> {
+        rewrite(svg, self)
+    }
+}
+
+impl AttributeRewrite for StripDataAttributes {
+    fn rewrite_attribute(&self, name: &str, _value: &str) -> Rewrite {
+        if name.starts_with("data-") && !self.keep.iter().any(|k| k == name) {
+            Rewrite::Remove
+        } else {
+            Rewrite::Keep
+        }
+    }
+}
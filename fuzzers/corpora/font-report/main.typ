@@ -0,0 +1,6 @@
+#import "template.typ": conf
+#show: conf
+
+= Introduction
+
+The fonts of this document are requested by the template only.
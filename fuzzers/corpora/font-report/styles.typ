@@ -0,0 +1 @@
+#let heading-style(it) = text(font: "Another Missing Family", it.body)
@@ -0,0 +1,7 @@
+#import "styles.typ": *
+
+#let conf(doc) = {
+  set text(font: ("No Such Family", "Linux Libertine"))
+  show heading: heading-style
+  doc
+}
@@ -0,0 +1,6 @@
+#set page(width: 200pt, height: auto, margin: 10pt)
+
+// A long single page, e.g. a poster or a continuous layout.
+#for i in range(200) [
+  #rect(width: 100%, height: 20pt)[Row #i]
+]
@@ -0,0 +1,11 @@
+#set page(width: 150pt, height: auto, margin: 10pt)
+#set heading(numbering: "1.")
+
+= Introduction <sec-intro>
+
+#figure(
+  rect(width: 60pt, height: 30pt),
+  caption: [A rectangle.],
+) <fig-rect>
+
+#rotate(90deg, box[$ a + b $ <eq-sum>])
@@ -0,0 +1,16 @@
+#set document(title: "Report", author: ("Alice", "Bob"))
+#set page(width: 200pt, height: 200pt, margin: 10pt, numbering: "i")
+#set heading(numbering: "1.")
+
+= Introduction <sec-intro>
+
+== Motivation
+
+#figure(
+  rect(width: 60pt, height: 30pt),
+  caption: [A rectangle.],
+) <fig-rect>
+
+#pagebreak()
+
+= Conclusion
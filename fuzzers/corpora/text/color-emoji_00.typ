@@ -0,0 +1,7 @@
+#set page(width: 120pt, height: auto, margin: 10pt)
+
+// Glyphs in SVG format.
+#text(font: "Twitter Color Emoji")[😀🐢🌈]
+
+// Glyphs in bitmap format.
+#text(font: "Noto Color Emoji")[😀🐢🌈]
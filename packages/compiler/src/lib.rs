@@ -248,7 +248,7 @@ mod tests {
     }
 
     fn render_svg(artifact: &[u8]) -> String {
-        let doc = MultiSvgDocument::from_slice(artifact);
+        let doc = MultiSvgDocument::from_slice(artifact).unwrap();
         type UsingExporter =
             typst_ts_svg_exporter::SvgExporter<typst_ts_svg_exporter::SvgExportFeature>;
 
@@ -4,7 +4,11 @@ use std::sync::{Arc, Mutex};
 use typst_ts_canvas_exporter::IncrCanvasDocClient;
 use typst_ts_core::{
     error::prelude::*,
-    vector::{flat_ir::Page, incr::IncrDocClient, ir::Scalar},
+    vector::{
+        flat_ir::{ElementItem, Page},
+        incr::IncrDocClient,
+        ir::Scalar,
+    },
 };
 #[cfg(feature = "render_svg")]
 use typst_ts_svg_exporter::IncrSvgDocClient;
@@ -94,6 +98,8 @@ pub struct PageInfo {
     pub(crate) page_off: usize,
     pub(crate) width: f64,
     pub(crate) height: f64,
+    pub(crate) label: Option<String>,
+    pub(crate) numbering: Option<String>,
 }
 
 #[wasm_bindgen]
@@ -112,6 +118,83 @@ impl PageInfo {
     pub fn height_pt(&self) -> f64 {
         self.height
     }
+
+    /// The logical label of the page, e.g. `iv` or `A-3`.
+    #[wasm_bindgen(getter)]
+    pub fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    /// The numbering pattern of the page, e.g. `i` or `1 / 1`.
+    #[wasm_bindgen(getter)]
+    pub fn numbering(&self) -> Option<String> {
+        self.numbering.clone()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct ElementInfo {
+    pub(crate) kind: String,
+    pub(crate) label: Option<String>,
+    pub(crate) page_off: usize,
+    pub(crate) x: f64,
+    pub(crate) y: f64,
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+}
+
+impl From<&ElementItem> for ElementInfo {
+    fn from(item: &ElementItem) -> Self {
+        Self {
+            kind: item.kind.as_ref().into(),
+            label: item.label.as_deref().map(From::from),
+            page_off: item.page as usize,
+            x: item.pos.x.0 as f64,
+            y: item.pos.y.0 as f64,
+            width: item.size.x.0 as f64,
+            height: item.size.y.0 as f64,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ElementInfo {
+    /// The kind of the element, e.g. `heading` or `figure`.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn page_off(&self) -> usize {
+        self.page_off
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x_pt(&self) -> f64 {
+        self.x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y_pt(&self) -> f64 {
+        self.y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width_pt(&self) -> f64 {
+        self.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height_pt(&self) -> f64 {
+        self.height
+    }
 }
 
 #[wasm_bindgen]
@@ -226,6 +309,14 @@ impl RenderSession {
         self.client().kern().source_span(path)
     }
 
+    /// Get the first region of the element with the given label, if the
+    /// element layer is attached to the document.
+    pub fn element_by_label(&self, label: &str) -> Option<ElementInfo> {
+        let client = self.client();
+        let mut elements = client.kern().elements_by_label(label);
+        elements.next().map(From::from)
+    }
+
     pub(crate) fn reset(&mut self) {
         let mut client = self.client.lock().unwrap();
         *client = IncrDocClient::default();
@@ -249,7 +340,7 @@ impl RenderSession {
     ) -> ZResult<()> {
         use typst_ts_core::vector::stream::BytesModuleStream;
 
-        let delta = BytesModuleStream::from_slice(delta).checkout_owned();
+        let delta = BytesModuleStream::from_slice(delta)?.checkout_owned();
         let _delta_ref = &delta;
 
         #[cfg(feature = "debug_delta_update")]
@@ -280,11 +371,13 @@ impl RenderSession {
             if let Some(view) = view {
                 // Vec::with_capacity(client.elements.pages.len());
                 pages.reserve(view.pages().len());
-                for (i, Page { size, .. }) in view.pages().iter().enumerate() {
+                for (i, Page { size, label, .. }) in view.pages().iter().enumerate() {
                     pages.push(PageInfo {
                         page_off: i,
                         width: size.x.0 as f64,
                         height: size.y.0 as f64,
+                        label: label.label.as_deref().map(From::from),
+                        numbering: label.numbering.as_deref().map(From::from),
                     });
                 }
             }
@@ -5,11 +5,35 @@ export const kObject = Symbol.for('reflexo-obj');
  * @property {number} pageOffset - The offset of the page.
  * @property {number} width - The width of the page in pt.
  * @property {number} height - The height of the page in pt.
+ * @property {string} label - The logical label of the page, e.g. `iv`.
+ * @property {string} numbering - The numbering pattern of the page.
  */
 export class PageInfo {
   pageOffset: number;
   width: number;
   height: number;
+  label?: string;
+  numbering?: string;
+}
+
+/**
+ * The region of a located element in a Typst document.
+ * @property {string} kind - The kind of the element, e.g. `figure`.
+ * @property {string} label - The label attached to the element.
+ * @property {number} pageOffset - The offset of the page.
+ * @property {number} x - The x coordinate of the region in pt.
+ * @property {number} y - The y coordinate of the region in pt.
+ * @property {number} width - The width of the region in pt.
+ * @property {number} height - The height of the region in pt.
+ */
+export class ElementInfo {
+  kind: string;
+  label?: string;
+  pageOffset: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
 }
 
 export interface FsAccessModel {
@@ -11,6 +11,7 @@ import * as renderer from './renderer';
 export type { RenderSession, TypstRenderer } from './renderer';
 export { rendererBuildInfo, createTypstRenderer, createTypstSvgRenderer } from './renderer';
 import { RenderView, renderTextLayer } from './render/canvas/view';
+import { patchRoot } from './render/svg/patch';
 import * as compiler from './compiler';
 import { FetchAccessModel } from './fs';
 export { FetchAccessModel } from './fs';
@@ -23,6 +24,7 @@ if (window) {
   (window as any).TypstRenderModule = {
     RenderView,
     renderTextLayer,
+    patchRoot,
 
     createTypstRenderer: renderer.createTypstRenderer,
     createTypstSvgRenderer: renderer.createTypstRenderer,
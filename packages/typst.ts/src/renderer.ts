@@ -3,7 +3,7 @@ import typstInit, * as typst from '@myriaddreamin/typst-ts-renderer';
 
 import type { InitOptions } from './options.init';
 import { PageViewport } from './render/canvas/viewport';
-import { PageInfo, TransformMatrix, kObject } from './internal.types';
+import { ElementInfo, PageInfo, TransformMatrix, kObject } from './internal.types';
 import {
   CreateSessionOptions,
   RenderToCanvasOptions,
@@ -146,6 +146,8 @@ export class RenderSession {
         pageOffset: pageAst.page_off,
         width: pageAst.width_pt,
         height: pageAst.height_pt,
+        label: pageAst.label,
+        numbering: pageAst.numbering,
       });
     }
 
@@ -156,6 +158,27 @@ export class RenderSession {
     return (this[kObject] as typst.RenderSession).source_span(path);
   }
 
+  /**
+   * Get the region of the element with the given label, which is available
+   * if the element layer is attached to the document.
+   */
+  getElementByLabel(label: string): ElementInfo | undefined {
+    const elem = (this[kObject] as typst.RenderSession).element_by_label(label);
+    if (!elem) {
+      return undefined;
+    }
+
+    return {
+      kind: elem.kind,
+      label: elem.label,
+      pageOffset: elem.page_off,
+      x: elem.x_pt,
+      y: elem.y_pt,
+      width: elem.width_pt,
+      height: elem.height_pt,
+    };
+  }
+
   /**
    * See {@link TypstRenderer.renderToSvg} for more details.
    */
@@ -36,6 +36,8 @@ fn run(args: RunArgs) -> ! {
 
     let compile_opts = CompileOpts {
         font_paths: args.font_paths,
+        // the workspaces are provided by remote clients.
+        sandbox: true,
         ..Default::default()
     };
 
@@ -1,11 +1,17 @@
 mod tests {
 
-    use std::collections::HashMap;
+    use std::{collections::HashMap, sync::Arc};
 
     use anyhow::Ok;
     use base64::Engine;
     use image::codecs::png::PngDecoder;
     use serde::{Deserialize, Serialize};
+    use typst::doc::Document;
+    use typst_ts_compiler::{
+        service::{CompileDriver, Compiler},
+        TypstSystemWorld,
+    };
+    use typst_ts_core::config::CompileOpts;
     use typst_ts_dev_server::{http::run_http, RunHttpArgs};
     use typst_ts_integration_test::wasm::wasm_pack_test;
     use typst_ts_test_common::{corpus_root, package_renderer_dir};
@@ -22,6 +28,55 @@ mod tests {
         verbose: HashMap<String, String>,
     }
 
+    /// Compile the entry file of a workspace in the corpus, without system
+    /// fonts.
+    fn compile_corpus(workspace: &str, entry: &str) -> (TypstSystemWorld, Arc<Document>) {
+        compile_corpus_with(workspace, entry, CompileOpts::default())
+    }
+
+    /// Compile the entry file of a workspace in the corpus with the options,
+    /// without system fonts.
+    fn compile_corpus_with(
+        workspace: &str,
+        entry: &str,
+        opts: CompileOpts,
+    ) -> (TypstSystemWorld, Arc<Document>) {
+        let workspace_dir = corpus_root().join(workspace);
+        let world = TypstSystemWorld::new(CompileOpts {
+            root_dir: workspace_dir.clone(),
+            no_system_fonts: true,
+            ..opts
+        })
+        .unwrap();
+        let mut driver = CompileDriver {
+            world,
+            entry_file: workspace_dir.join(entry),
+        };
+        let doc = Arc::new(driver.compile().unwrap());
+        (driver.world, doc)
+    }
+
+    /// The text items in the pages of a document, in order of appearance.
+    fn text_items(doc: &Document) -> Vec<&typst::doc::TextItem> {
+        use typst::doc::{Frame, FrameItem, TextItem};
+
+        fn collect<'a>(frame: &'a Frame, texts: &mut Vec<&'a TextItem>) {
+            for (_, item) in frame.items() {
+                match item {
+                    FrameItem::Group(group) => collect(&group.frame, texts),
+                    FrameItem::Text(text) => texts.push(text),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut texts = vec![];
+        for page in &doc.pages {
+            collect(page, &mut texts);
+        }
+        texts
+    }
+
     fn hash_image_data_url(data_url: &str) -> String {
         use image_hasher::HasherConfig;
 
@@ -362,6 +417,666 @@ mod tests {
         }
     }
 
+    /// Reports the size savings of rendering glyphs with subsetted fonts
+    /// instead of outlines on the corpus.
+    #[test]
+    fn test_font_face_size_savings() {
+        use typst_ts_svg_exporter::{render_svg_html, render_svg_html_with, FontFaceExportFeature};
+
+        let corpora = [
+            ("skyzh-cv", "main"),
+            ("book", "summary"),
+            ("text", "baseline_00"),
+            ("text", "chinese_00"),
+            ("text", "emoji_00"),
+        ];
+
+        let mut outline_total = 0;
+        let mut font_face_total = 0;
+        println!("::group::Font face size savings");
+        for (workspace, name) in corpora {
+            let (_, doc) = compile_corpus(workspace, &format!("{name}.typ"));
+
+            let outline = render_svg_html(&doc).len();
+            let font_face = render_svg_html_with::<FontFaceExportFeature>(&doc).len();
+            println!(
+                "{workspace}/{name}.typ: outline {outline} bytes, font face {font_face} bytes ({:+.1}%)",
+                (font_face as f64 / outline as f64 - 1.) * 100.
+            );
+
+            outline_total += outline;
+            font_face_total += font_face;
+        }
+        println!("::endgroup::");
+
+        assert!(
+            font_face_total < outline_total,
+            "font face ({font_face_total} bytes) is not smaller than outline ({outline_total} bytes)"
+        );
+    }
+
+    /// Checks that each page is rendered into a standalone svg with the size of
+    /// the page.
+    #[test]
+    fn test_svg_pages_export() {
+        use typst_ts_svg_exporter::{render_svg, render_svg_pages};
+
+        let (_, doc) = compile_corpus("book", "summary.typ");
+
+        let full = render_svg(&doc);
+        let pages = render_svg_pages(&doc).collect::<Vec<_>>();
+        assert_eq!(pages.len(), doc.pages.len());
+        for (page, frame) in pages.iter().zip(doc.pages.iter()) {
+            assert_eq!(page.width, frame.width().to_pt().ceil() as u32);
+            assert_eq!(page.height, frame.height().to_pt().ceil() as u32);
+            let view_box = format!(r#"viewBox="0 0 {}.000 {}.000""#, page.width, page.height);
+            assert!(page.svg.contains(&view_box), "{view_box} is not found");
+            assert!(page.svg.ends_with("</svg>"));
+            // only the definitions used by the page are included
+            if doc.pages.len() > 1 {
+                assert!(page.svg.len() < full.len());
+            }
+        }
+    }
+
+    /// Checks the built-in post-processing transforms of svg.
+    #[test]
+    fn test_svg_transforms() {
+        use typst_ts_core::Exporter;
+        use typst_ts_svg_exporter::{
+            render_svg_html,
+            transform::{
+                InvertColors, Minify, PrefixIds, RoundPrecision, StripDataAttributes,
+                TransformPipeline,
+            },
+            DefaultExportFeature, ExportFeature, SvgExporter,
+        };
+
+        let (world, doc) = compile_corpus("skyzh-cv", "main.typ");
+
+        // the given transforms are applied after the default ones
+        let render = |transforms: TransformPipeline| {
+            let transforms = DefaultExportFeature::transforms().then(transforms);
+            let exporter = SvgExporter::<DefaultExportFeature>::new(transforms);
+            Exporter::<_, String>::export(&exporter, &world, doc.clone()).unwrap()
+        };
+        let attributes = |svg: &str, name: &str| {
+            let pat = format!(r#" {name}=""#);
+            svg.split(pat.as_str())
+                .skip(1)
+                .map(|s| s[..s.find('"').unwrap()].to_owned())
+                .collect::<Vec<_>>()
+        };
+        let script = |svg: &str| {
+            let start = svg.find("<script").unwrap();
+            svg[start..start + svg[start..].find("</script>").unwrap()].to_owned()
+        };
+
+        let original = render_svg_html(&doc);
+        assert_eq!(render(TransformPipeline::new()), original);
+        // minification is idempotent
+        assert_eq!(render(TransformPipeline::new().then(Minify)), original);
+
+        let prefixed = render(TransformPipeline::new().then(PrefixIds::new("doc1-")));
+        let ids = attributes(&prefixed, "id");
+        assert!(!ids.is_empty());
+        assert!(ids.iter().all(|id| id.starts_with("doc1-")));
+        assert!(attributes(&prefixed, "xlink:href")
+            .iter()
+            .filter(|href| href.starts_with('#'))
+            .all(|href| href.starts_with("#doc1-")));
+        assert_eq!(
+            prefixed.matches("url(#").count(),
+            prefixed.matches("url(#doc1-").count()
+        );
+        assert_eq!(script(&prefixed), script(&original));
+
+        let rounded = render(TransformPipeline::new().then(RoundPrecision::new(1)));
+        assert!(rounded.len() < original.len());
+        for d in attributes(&rounded, "d") {
+            let has_two_decimals = d
+                .split('.')
+                .skip(1)
+                .any(|frac| frac.len() >= 2 && frac.as_bytes()[..2].iter().all(u8::is_ascii_digit));
+            assert!(!has_two_decimals, "not rounded: {d}");
+        }
+        // the text is still scaled by its ppem
+        let scales = attributes(&rounded, "transform")
+            .into_iter()
+            .filter_map(|t| Some(t.strip_prefix("scale(")?.strip_suffix(')')?.to_owned()))
+            .collect::<Vec<_>>();
+        assert!(!scales.is_empty());
+        for scale in scales {
+            assert!(
+                scale
+                    .split(',')
+                    .all(|x| x.trim().parse::<f64>().unwrap() != 0.),
+                "zero scale: {scale}"
+            );
+        }
+        assert_eq!(script(&rounded), script(&original));
+
+        let inverted = render(TransformPipeline::new().then(InvertColors));
+        assert_eq!(
+            original.matches("--glyph_fill: #000000;").count(),
+            inverted.matches("--glyph_fill: #ffffff;").count(),
+        );
+        assert!(!inverted.contains("--glyph_fill: black;"));
+
+        let stripped = render(TransformPipeline::new().then(StripDataAttributes::new()));
+        assert!(!stripped.replace(&script(&stripped), "").contains(" data-"));
+        let stripped = render(TransformPipeline::new().then(StripDataAttributes::default()));
+        assert!(stripped.contains(" data-page-width="));
+    }
+
+    /// Checks that the streaming and compressed svg exporters write the same
+    /// text as the in-memory rendering.
+    #[test]
+    fn test_streaming_svg_export() {
+        use std::io::Read;
+
+        use typst_ts_core::Transformer;
+        use typst_ts_svg_exporter::{
+            render_svg, render_svg_html, BrotliExporter, DefaultExportFeature, GzipExporter,
+            PureSvgExporter, SvgExporter,
+        };
+
+        let (world, doc) = compile_corpus("skyzh-cv", "main.typ");
+
+        let mut svg = vec![];
+        let exporter = PureSvgExporter::default();
+        Transformer::export(&exporter, &world, (doc.clone(), &mut svg)).unwrap();
+        assert_eq!(String::from_utf8(svg).unwrap(), render_svg(&doc));
+
+        let mut svg_html = vec![];
+        let exporter = SvgExporter::<DefaultExportFeature>::default();
+        Transformer::export(&exporter, &world, (doc.clone(), &mut svg_html)).unwrap();
+        assert_eq!(String::from_utf8(svg_html).unwrap(), render_svg_html(&doc));
+
+        let mut svg_gz = vec![];
+        let exporter = GzipExporter::<PureSvgExporter>::default();
+        exporter.export(&world, (doc.clone(), &mut svg_gz)).unwrap();
+        let mut svg = String::new();
+        flate2::read::GzDecoder::new(svg_gz.as_slice())
+            .read_to_string(&mut svg)
+            .unwrap();
+        assert_eq!(svg, render_svg(&doc));
+
+        // the pages are written before the definitions collected from them
+        let first_page = svg.find("data-page-width").unwrap();
+        let glyph_defs = svg.find(r#"<defs class="glyph">"#).unwrap();
+        assert!(first_page < glyph_defs);
+        assert_eq!(svg.matches("data-page-width").count(), doc.pages.len());
+
+        let mut svg_html_br = vec![];
+        let exporter = BrotliExporter::<SvgExporter<DefaultExportFeature>>::default();
+        exporter
+            .export(&world, (doc.clone(), &mut svg_html_br))
+            .unwrap();
+        let mut svg_html = String::new();
+        brotli::Decompressor::new(svg_html_br.as_slice(), 4096)
+            .read_to_string(&mut svg_html)
+            .unwrap();
+        assert_eq!(svg_html, render_svg_html(&doc));
+
+        // the errors of writing are reported, even in finishing the stream
+        struct FullWriter;
+        impl std::io::Write for FullWriter {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::WriteZero.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        assert!(exporter.export(&world, (doc.clone(), FullWriter)).is_err());
+    }
+
+    /// Checks that color glyphs, e.g. emoji, are lowered into images or
+    /// colored layers instead of monochrome outlines.
+    #[test]
+    fn test_color_glyph_lowering() {
+        use typst_ts_core::{
+            font::GlyphProvider,
+            vector::{
+                ir::{GlyphItem, SvgItem, TransformedItem},
+                GlyphLowerBuilder, LowerBuilder,
+            },
+        };
+        use typst_ts_svg_exporter::render_svg_html;
+
+        fn collect_glyphs(item: &SvgItem, glyphs: &mut Vec<GlyphItem>) {
+            match item {
+                SvgItem::Group(group) => {
+                    for (_, item) in group.0.iter() {
+                        collect_glyphs(item, glyphs);
+                    }
+                }
+                SvgItem::Transformed(TransformedItem(_, item)) => collect_glyphs(item, glyphs),
+                SvgItem::Text(text) => {
+                    glyphs.extend(text.content.glyphs.iter().map(|(_, _, g)| g.clone()))
+                }
+                _ => {}
+            }
+        }
+
+        let corpora = [("text", "emoji_00"), ("text", "color-emoji_00")];
+
+        let glyph_provider = GlyphProvider::default();
+        let glyph_lower_builder = GlyphLowerBuilder::new(&glyph_provider);
+        let mut color_glyphs = 0;
+        for (workspace, name) in corpora {
+            let (_, doc) = compile_corpus(workspace, &format!("{name}.typ"));
+            assert!(!render_svg_html(&doc).is_empty());
+
+            let mut glyphs = vec![];
+            let mut lower_builder = LowerBuilder::new(&doc);
+            for page in &doc.pages {
+                collect_glyphs(&lower_builder.lower(page), &mut glyphs);
+            }
+
+            for glyph in glyphs {
+                let GlyphItem::Raw(font, id, _) = &glyph else {
+                    continue;
+                };
+                let ttf = font.ttf();
+                let is_color = ttf.is_color_glyph(*id)
+                    || ttf.glyph_svg_image(*id).is_some()
+                    || ttf.glyph_raster_image(*id, u16::MAX).is_some();
+                if !is_color {
+                    continue;
+                }
+
+                color_glyphs += 1;
+                match glyph_lower_builder.lower_glyph(&glyph) {
+                    Some(GlyphItem::Image(..)) => {}
+                    Some(GlyphItem::Color(color)) => assert!(!color.layers.is_empty()),
+                    lowered => panic!(
+                        "{workspace}/{name}.typ: color glyph {id:?} of {} is lowered into {lowered:?}",
+                        font.info().family
+                    ),
+                }
+            }
+        }
+
+        assert!(color_glyphs > 0, "no color glyph is found in the corpus");
+    }
+
+    /// Checks that the SVG backend and the vector artifact, which is drawn by
+    /// the canvas backend, render the glyphs of the emoji corpus in the same
+    /// kinds.
+    #[test]
+    fn test_color_glyph_backends() {
+        use std::collections::BTreeMap;
+
+        use typst_ts_core::vector::flat_ir::{FlatGlyphItem, ModuleMetadata};
+        use typst_ts_svg_exporter::{lower_module, render_svg};
+
+        let corpora = [("text", "emoji_00"), ("text", "color-emoji_00")];
+        for (workspace, name) in corpora {
+            let (_, doc) = compile_corpus(workspace, &format!("{name}.typ"));
+
+            let module = lower_module(&doc).unwrap();
+            let mut artifact_kinds = BTreeMap::new();
+            for metadata in &module.metadata {
+                let ModuleMetadata::Glyph(glyphs) = metadata else {
+                    continue;
+                };
+                for (_, glyph) in &glyphs.items {
+                    let kind = match glyph {
+                        FlatGlyphItem::Image(..) => "image_glyph",
+                        FlatGlyphItem::Outline(..) => "outline_glyph",
+                        FlatGlyphItem::Color(..) => "color_glyph",
+                        FlatGlyphItem::None => continue,
+                    };
+                    *artifact_kinds.entry(kind).or_insert(0) += 1;
+                }
+            }
+
+            let svg = render_svg(&doc);
+            let svg_kinds = ["image_glyph", "outline_glyph", "color_glyph"]
+                .into_iter()
+                .map(|kind| (kind, svg.matches(&format!(r#"class="{kind}""#)).count()))
+                .filter(|(_, count)| *count > 0)
+                .collect::<BTreeMap<_, _>>();
+
+            assert!(artifact_kinds.contains_key("image_glyph"), "{name}.typ");
+            assert_eq!(artifact_kinds, svg_kinds, "{workspace}/{name}.typ");
+        }
+    }
+
+    /// Checks that a glyph painted only by a paint graph of `COLR` version 1,
+    /// which is not supported, falls back to its outline.
+    #[test]
+    fn test_colr_v1_glyph_fallback() {
+        use typst::font::Font;
+        use typst_ts_core::{
+            font::GlyphProvider,
+            vector::{
+                ir::{FontInstance, GlyphItem},
+                GlyphLowerBuilder,
+            },
+            Bytes,
+        };
+
+        /// Add the tables to a single font in sfnt format.
+        fn with_tables(data: &[u8], extra: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+            let u16_at = |at: usize| u16::from_be_bytes([data[at], data[at + 1]]) as usize;
+            let u32_at = |at: usize| {
+                u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]) as usize
+            };
+
+            let mut tables = (0..u16_at(4))
+                .map(|i| {
+                    let record = 12 + i * 16;
+                    let tag = [0, 1, 2, 3].map(|j| data[record + j]);
+                    let (offset, len) = (u32_at(record + 8), u32_at(record + 12));
+                    (tag, data[offset..offset + len].to_vec())
+                })
+                .collect::<Vec<_>>();
+            tables.extend(extra);
+            tables.sort_by_key(|(tag, _)| *tag);
+
+            let mut font = data[..4].to_vec();
+            font.extend((tables.len() as u16).to_be_bytes());
+            font.extend([0; 6]);
+            let mut body = vec![];
+            for (tag, table) in &tables {
+                let offset = 12 + tables.len() * 16 + body.len();
+                font.extend(tag);
+                font.extend(0u32.to_be_bytes());
+                font.extend((offset as u32).to_be_bytes());
+                font.extend((table.len() as u32).to_be_bytes());
+                body.extend(table);
+                body.resize((body.len() + 3) & !3, 0);
+            }
+            font.extend(body);
+            font
+        }
+
+        let (_, doc) = compile_corpus("text", "emoji_00.typ");
+        let font = text_items(&doc)
+            .into_iter()
+            .map(|text| text.font.clone())
+            .find(|font| font.index() == 0 && !font.data().starts_with(b"ttcf"))
+            .unwrap();
+        let id = font.ttf().glyph_index('A').unwrap();
+
+        // a version 1 table, whose base glyph list paints the glyph in the
+        // first color of the palette.
+        let mut colr = vec![0, 1];
+        colr.extend([0; 12]);
+        colr.extend(34u32.to_be_bytes());
+        colr.extend([0; 16]);
+        colr.extend(1u32.to_be_bytes());
+        colr.extend(id.0.to_be_bytes());
+        colr.extend(10u32.to_be_bytes());
+        colr.extend([2, 0, 0, 0x40, 0]);
+        let mut cpal = vec![0, 0, 0, 1, 0, 1, 0, 1];
+        cpal.extend(14u32.to_be_bytes());
+        cpal.extend([0, 0, 0, 0, 0xff, 0xff]);
+
+        let data = with_tables(font.data(), vec![(*b"COLR", colr), (*b"CPAL", cpal)]);
+        let font = Font::new(Bytes::from(data), 0).unwrap();
+
+        let glyph_provider = GlyphProvider::default();
+        let glyph_lower_builder = GlyphLowerBuilder::new(&glyph_provider);
+        let instance = FontInstance::default_of(&font);
+        let glyph = GlyphItem::Raw(font, id, instance);
+        match glyph_lower_builder.lower_glyph(&glyph) {
+            Some(GlyphItem::Outline(outline)) => assert!(!outline.d.is_empty()),
+            lowered => panic!("COLR version 1 glyph is lowered into {lowered:?}"),
+        }
+    }
+
+    /// Checks that the element layer records the regions of labelled
+    /// elements, and is carried to the client by the incremental server.
+    #[test]
+    fn test_element_layer() {
+        use typst_ts_core::vector::{
+            element::ElementLayerBuilder,
+            flat_ir::ModuleMetadata,
+            incr::{IncrDocClient, IncrDocServer},
+            stream::BytesModuleStream,
+        };
+
+        let (_, doc) = compile_corpus("meta", "element-layer_00.typ");
+
+        let elements = ElementLayerBuilder::from_document(&doc).finalize();
+        let page_width = doc.pages[0].width().to_pt() as f32;
+        for (label, kind) in [
+            ("sec-intro", "heading"),
+            ("fig-rect", "figure"),
+            ("eq-sum", "equation"),
+        ] {
+            let elem = elements
+                .iter()
+                .find(|e| e.label.as_deref() == Some(label))
+                .unwrap_or_else(|| panic!("element <{label}> is not found in {elements:?}"));
+            assert_eq!(elem.kind.as_ref(), kind);
+            assert_eq!(elem.page, 0);
+            assert!(elem.size.x.0 > 0. && elem.size.y.0 > 0.);
+            assert!(elem.pos.x.0 >= 0. && elem.pos.x.0 + elem.size.x.0 <= page_width);
+        }
+
+        // the rotated equation is taller than it is wide
+        let eq = elements
+            .iter()
+            .find(|e| e.label.as_deref() == Some("eq-sum"))
+            .unwrap();
+        assert!(eq.size.y.0 > eq.size.x.0);
+
+        let mut server = IncrDocServer::default();
+        server.set_should_attach_elements(true);
+        let delta = server.pack_delta(doc.clone());
+        let delta = delta.strip_prefix(b"diff-v1,").unwrap();
+
+        let mut client = IncrDocClient::default();
+        client.merge_delta(
+            BytesModuleStream::from_slice(delta)
+                .unwrap()
+                .checkout_owned(),
+        );
+        assert_eq!(client.kern().elements(), elements.as_slice());
+        assert_eq!(client.kern().elements_by_label("fig-rect").count(), 1);
+
+        // an unchanged element layer is not sent again
+        let delta = server.pack_delta(doc);
+        let delta = delta.strip_prefix(b"diff-v1,").unwrap();
+        let delta = BytesModuleStream::from_slice(delta)
+            .unwrap()
+            .checkout_owned();
+        assert!(!delta
+            .metadata
+            .iter()
+            .any(|m| matches!(m, ModuleMetadata::Element(..))));
+        client.merge_delta(delta);
+        assert_eq!(client.kern().elements(), elements.as_slice());
+    }
+
+    /// Checks the introspection data exported by `--format meta-json`.
+    #[test]
+    fn test_document_introspection() {
+        use typst_ts_core::DocumentIntrospection;
+
+        let (_, doc) = compile_corpus("meta", "introspection_00.typ");
+
+        let meta = DocumentIntrospection::new(&doc);
+        assert_eq!(meta.title.as_deref(), Some("Report"));
+        assert_eq!(meta.author, ["Alice", "Bob"]);
+
+        assert_eq!(meta.pages.len(), 2);
+        assert_eq!(meta.pages[0].width, 200.);
+        assert_eq!(meta.pages[1].label.as_deref(), Some("ii"));
+
+        let headings: Vec<_> = meta
+            .headings
+            .iter()
+            .map(|h| (h.level, h.body.as_str(), h.location.page))
+            .collect();
+        assert_eq!(
+            headings,
+            [
+                (1, "Introduction", 1),
+                (2, "Motivation", 1),
+                (1, "Conclusion", 2)
+            ]
+        );
+        assert_eq!(meta.headings[0].numbering.as_deref(), Some("1."));
+        assert_eq!(meta.headings[0].label.as_deref(), Some("sec-intro"));
+
+        assert_eq!(meta.figures.len(), 1);
+        assert_eq!(meta.figures[0].caption.as_deref(), Some("A rectangle."));
+        assert_eq!(meta.figures[0].label.as_deref(), Some("fig-rect"));
+
+        let labels: Vec<_> = meta
+            .labels
+            .iter()
+            .map(|l| (l.label.as_str(), l.kind.as_str()))
+            .collect();
+        assert_eq!(labels, [("sec-intro", "heading"), ("fig-rect", "figure")]);
+
+        // the schema is stable
+        let json = serde_json::to_value(&meta).unwrap();
+        assert_eq!(json["headings"][1]["location"]["page"], 1);
+        assert_eq!(json["figures"][0]["kind"], "image");
+    }
+
+    /// Checks that only the items in the window are rendered for a long
+    /// single page.
+    #[test]
+    fn test_render_long_page_in_window() {
+        use typst_ts_core::vector::ir::{Point, Rect, Scalar};
+        use typst_ts_svg_exporter::{DefaultExportFeature, SvgExporter};
+
+        type Exporter = SvgExporter<DefaultExportFeature>;
+
+        let (_, doc) = compile_corpus("layout", "poster_00.typ");
+        assert_eq!(doc.pages.len(), 1);
+
+        let mut svg_doc = Exporter::svg_doc(&doc);
+        let full = Exporter::render_flat_svg(&svg_doc.module, &svg_doc.pages);
+
+        let page_height = svg_doc.pages[0].size.y.0;
+        let window = Rect {
+            lo: Point::new(Scalar(0.), Scalar(page_height / 2.)),
+            hi: Point::new(Scalar(200.), Scalar(page_height / 2. + 100.)),
+        };
+        let windowed =
+            Exporter::render_flat_svg_in_window(&mut svg_doc.module, &svg_doc.pages, window);
+
+        // the layout of document is kept
+        let header = |svg: &str| svg[..svg.find('>').unwrap()].to_owned();
+        assert_eq!(header(&full), header(&windowed));
+
+        // about 4 of 200 rows are in the window
+        let count_rects = |svg: &str| svg.matches("typst-shape").count();
+        assert!(count_rects(&full) >= 200);
+        assert!(
+            (1..=10).contains(&count_rects(&windowed)),
+            "{} shapes are rendered in window",
+            count_rects(&windowed)
+        );
+    }
+
+    #[test]
+    fn test_glyph_pack_only_fonts() {
+        use typst_ts_core::font::FontGlyphPack;
+
+        fn texts(doc: &Document) -> Vec<(String, String, Vec<u16>)> {
+            let texts = text_items(doc).into_iter().map(|text| {
+                let glyphs = text.glyphs.iter().map(|g| g.id).collect();
+                (
+                    text.font.info().family.clone(),
+                    text.text.to_string(),
+                    glyphs,
+                )
+            });
+            texts.collect()
+        }
+
+        let (_, doc) = compile_corpus("skyzh-cv", "main.typ");
+        let packs = FontGlyphPack::from_document(&doc);
+        assert!(!packs.is_empty());
+
+        let pack_dir = typst_ts_test_common::artifact_dir().join("integrations/glyph-pack");
+        std::fs::create_dir_all(&pack_dir).unwrap();
+        let pack_path = pack_dir.join("skyzh-cv.glyph-pack.json");
+        std::fs::write(&pack_path, serde_json::to_vec(&packs).unwrap()).unwrap();
+
+        let (_, packed_doc) = compile_corpus_with(
+            "skyzh-cv",
+            "main.typ",
+            CompileOpts {
+                no_vanilla_fonts: true,
+                glyph_pack_paths: vec![pack_path],
+                ..CompileOpts::default()
+            },
+        );
+        assert_eq!(texts(&doc), texts(&packed_doc));
+    }
+
+    /// Checks that the families requested by imported sources which render
+    /// no text themselves are reported.
+    #[test]
+    fn test_font_report_imported_families() {
+        use typst_ts_core::font::FontReport;
+
+        let (world, doc) = compile_corpus("font-report", "main.typ");
+        let report = FontReport::new(&world, &doc);
+        assert_eq!(
+            report.unresolved_families,
+            vec!["another missing family", "no such family"]
+        );
+    }
+
+    /// Compiles a document with imports entirely in memory, and recompiles it
+    /// after updating an imported file.
+    #[test]
+    fn test_memory_world_compile() {
+        use std::path::Path;
+
+        use typst_ts_compiler::{
+            font::system::SystemFontSearcher,
+            service::{CompileDriverImpl, Compiler},
+            vfs::memory::MemoryAccessModel,
+            TypstMemoryWorld,
+        };
+        use typst_ts_core::Bytes;
+
+        let model = MemoryAccessModel::new();
+        model.insert_file(
+            Path::new("/root/main.typ"),
+            Bytes::from_static(b"#import \"lib/greet.typ\": greet\n#greet(\"memory\")"),
+        );
+        model.insert_file(
+            Path::new("/root/lib/greet.typ"),
+            Bytes::from_static(b"#let greet(name) = [Hello, #name!]"),
+        );
+
+        let mut searcher = SystemFontSearcher::new();
+        searcher.search_vanilla();
+        let world = TypstMemoryWorld::new("/root".into(), model.clone(), searcher.into());
+        let mut driver = CompileDriverImpl::new(world).with_entry_file("/root/main.typ".into());
+
+        let mut compile = || {
+            let doc = driver.compile().unwrap();
+            let texts = text_items(&doc).into_iter().map(|t| t.text.as_str());
+            texts.collect::<String>()
+        };
+
+        let text = compile();
+        assert!(text.contains("Hello") && text.contains("memory"), "{text}");
+
+        model.insert_file(
+            Path::new("/root/lib/greet.typ"),
+            Bytes::from_static(b"#let greet(name) = [Bye, #name!]"),
+        );
+        let text = compile();
+        assert!(text.contains("Bye") && !text.contains("Hello"), "{text}");
+    }
+
     #[tokio::test]
     async fn test_wasm_renderer_functionality() -> anyhow::Result<()> {
         tokio::spawn(run_http(RunHttpArgs {
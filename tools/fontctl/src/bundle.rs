@@ -0,0 +1,214 @@
+//! Build a font bundle from a manifest, with a ready-made font profile.
+
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use cmd_lib::run_cmd;
+use serde::Deserialize;
+use sha2::Digest;
+use typst::font::FontInfo;
+use typst_ts_core::{
+    build_info,
+    font::{get_font_coverage_hash, FontInfoItem, FontProfile, FontProfileItem},
+};
+
+/// A manifest describing the fonts in a bundle.
+///
+/// ```json
+/// {
+///   "fonts": [
+///     { "path": "fonts/Roboto-Regular.ttf", "sha256": "797e35f7..." },
+///     { "url": "http://127.0.0.1:8080/LinLibertine_R.ttf", "sha256": "06e2b67a..." }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct BundleManifest {
+    pub fonts: Vec<BundleEntry>,
+}
+
+/// A font file in the bundle, which is either a local file or an url.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BundleEntry {
+    /// The file name in the bundle. The default is the last component of
+    /// `path` or `url`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The path to a local file, relative to the manifest.
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// The url to download the file from, e.g. served by a local mirror.
+    #[serde(default)]
+    pub url: Option<String>,
+    /// The expected SHA-256 of the file in hex.
+    pub sha256: String,
+}
+
+impl BundleEntry {
+    fn name(&self) -> anyhow::Result<String> {
+        let name = match (&self.name, &self.path, &self.url) {
+            (Some(name), _, _) => Some(name.clone()),
+            (None, Some(path), _) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            (None, None, Some(url)) => url
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .map(|name| name.to_owned()),
+            (None, None, None) => None,
+        };
+
+        match name {
+            Some(name) if !name.contains(['/', '\\']) && name != ".." => Ok(name),
+            _ => anyhow::bail!("cannot decide the file name of entry {:?}", self),
+        }
+    }
+
+    /// Read the content of the entry, relative to the directory of manifest.
+    fn read(&self, manifest_dir: &Path) -> anyhow::Result<Vec<u8>> {
+        match (&self.path, &self.url) {
+            (Some(path), None) => Ok(std::fs::read(manifest_dir.join(path))?),
+            (None, Some(url)) => {
+                let dir = tempfile::tempdir()?;
+                let file = dir.path().join("font");
+                run_cmd! {
+                    curl -fsSL -o $file $url;
+                }?;
+                Ok(std::fs::read(file)?)
+            }
+            _ => anyhow::bail!("exactly one of `path` and `url` is required: {:?}", self),
+        }
+    }
+}
+
+/// Build a font bundle from a manifest.
+#[derive(Debug)]
+pub struct Bundler {
+    manifest: BundleManifest,
+    manifest_dir: PathBuf,
+    out_dir: PathBuf,
+    install_dir: Option<PathBuf>,
+}
+
+impl Bundler {
+    /// Create a bundler from the manifest file in format of json.
+    pub fn from_manifest(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let manifest = std::fs::read(path)?;
+        let manifest: BundleManifest = serde_json::from_slice(&manifest)
+            .map_err(|err| anyhow::anyhow!("invalid manifest {:?}: {}", path, err))?;
+        let manifest_dir = path.parent().unwrap_or(Path::new(".")).to_owned();
+
+        Ok(Self {
+            manifest,
+            manifest_dir,
+            out_dir: std::env::current_dir()?,
+            install_dir: None,
+        })
+    }
+
+    /// Change the output directory. The default is the current dir.
+    pub fn out_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.out_dir = path.as_ref().to_owned();
+        self
+    }
+
+    /// Change the directory where the bundle is installed, which decides the
+    /// paths recorded in the profile. The default is the output directory.
+    ///
+    /// The paths are matched against the canonical paths of the searched
+    /// fonts, so the directory should be canonical.
+    pub fn install_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.install_dir = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Execute bundling, and return the profile of the bundled fonts.
+    ///
+    /// A file is skipped if all of its fonts are already in the bundle.
+    pub fn exec(self) -> anyhow::Result<FontProfile> {
+        std::fs::create_dir_all(&self.out_dir)?;
+        let install_dir = match &self.install_dir {
+            Some(dir) => dir.clone(),
+            None => self.out_dir.canonicalize()?,
+        };
+
+        let mut profile = FontProfile {
+            version: "v1beta".to_owned(),
+            build_info: build_info::VERSION.to_string(),
+            items: vec![],
+        };
+        let mut names = HashSet::new();
+        let mut seen = HashSet::new();
+        for entry in &self.manifest.fonts {
+            let name = entry.name()?;
+            print!("add font: {}", name);
+
+            let content = entry.read(&self.manifest_dir)?;
+            let sha_hash = hex::encode(sha2::Sha256::digest(&content));
+            println!(" .. {}", sha_hash);
+            let expected = entry.sha256.trim_start_matches("sha256:");
+            if !expected.eq_ignore_ascii_case(&sha_hash) {
+                anyhow::bail!(
+                    "hash mismatch for {} (expected: {}, actual: {})",
+                    name,
+                    expected,
+                    sha_hash
+                );
+            }
+
+            let infos = FontInfo::iter(&content).collect::<Vec<_>>();
+            if infos.is_empty() {
+                anyhow::bail!("no font is found in {}", name);
+            }
+            if infos.iter().all(|info| seen.contains(info)) {
+                println!("skip duplicated font: {}", name);
+                continue;
+            }
+            if !names.insert(name.clone()) {
+                anyhow::bail!("duplicated file name in bundle: {}", name);
+            }
+
+            let to = self.out_dir.join(&name);
+            std::fs::write(&to, &content)?;
+
+            let mut item = FontProfileItem::new("path", format!("sha256:{}", sha_hash));
+            item.set_path(install_dir.join(&name).to_string_lossy().to_string());
+            if let Ok(mtime) = std::fs::metadata(&to).and_then(|m| m.modified()) {
+                item.set_mtime(mtime);
+            }
+            for (i, info) in infos.into_iter().enumerate() {
+                seen.insert(info.clone());
+
+                let coverage_hash = get_font_coverage_hash(&info.coverage);
+                let mut ff = FontInfoItem::new(info);
+                ff.set_coverage_hash(coverage_hash);
+                if i != 0 {
+                    ff.set_index(i as u32);
+                }
+                item.add_info(ff);
+            }
+            profile.items.push(item);
+        }
+
+        Ok(profile)
+    }
+}
+
+/// Write the profile in format of `json.gz`.
+pub fn write_profile(profile: &FontProfile, path: impl AsRef<Path>) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let file = std::fs::File::create(path)?;
+    let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    encoder.write_all(&serde_json::to_vec(profile)?)?;
+    encoder.finish()?;
+    Ok(())
+}
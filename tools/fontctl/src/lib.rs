@@ -1 +1,2 @@
+pub mod bundle;
 pub mod git_download;
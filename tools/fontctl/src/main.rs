@@ -1,4 +1,6 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
 
 #[derive(Debug, Parser)]
 #[clap(name = "typst-ts-fontctl", version = "0.1.6")]
@@ -6,6 +8,36 @@ pub struct Opts {
     /// Use font assets in branch of typst
     #[clap(long, default_value = "")]
     pub using_branch: String,
+
+    #[clap(subcommand)]
+    pub sub: Option<Subcommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Subcommands {
+    /// Build a font bundle from a manifest, with a font profile
+    Bundle(BundleArgs),
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct BundleArgs {
+    /// Path to the manifest in format of json
+    #[clap(long)]
+    pub manifest: PathBuf,
+
+    /// Output directory of the bundle
+    #[clap(long, short, default_value = "fonts")]
+    pub output: PathBuf,
+
+    /// Path to the font profile in format of `json.gz`.
+    /// The default is `font-profile.json.gz` in the output directory
+    #[clap(long)]
+    pub profile: Option<PathBuf>,
+
+    /// Directory where the bundle is installed, which decides the paths
+    /// recorded in the profile. The default is the output directory
+    #[clap(long)]
+    pub install_dir: Option<PathBuf>,
 }
 
 const FONT_LISTS: &[(&str, &str)] = &[
@@ -165,8 +197,32 @@ fn download_fonts(opts: Opts) {
     println!("done");
 }
 
+fn bundle_fonts(args: BundleArgs) {
+    let mut bundler = typst_ts_fontctl::bundle::Bundler::from_manifest(&args.manifest)
+        .unwrap()
+        .out_dir(&args.output);
+    if let Some(install_dir) = &args.install_dir {
+        bundler = bundler.install_dir(install_dir);
+    }
+    let profile = bundler.exec().unwrap();
+
+    let profile_path = args
+        .profile
+        .unwrap_or_else(|| args.output.join("font-profile.json.gz"));
+    typst_ts_fontctl::bundle::write_profile(&profile, &profile_path).unwrap();
+
+    println!(
+        "done: {} files, profile written to {}",
+        profile.items.len(),
+        profile_path.display()
+    );
+}
+
 fn main() {
     let opts = Opts::parse();
 
-    download_fonts(opts);
+    match opts.sub {
+        Some(Subcommands::Bundle(args)) => bundle_fonts(args),
+        None => download_fonts(opts),
+    }
 }
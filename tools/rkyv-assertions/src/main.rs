@@ -48,11 +48,17 @@ fn main() {
         ArchivedPathItem,
         ArchivedImageGlyphItem,
         ArchivedOutlineGlyphItem,
+        ArchivedColorGlyphLayer,
+        ArchivedColorGlyphItem,
         ArchivedTextShape,
         ArchivedPathStyle,
         ArchivedTransformItem,
         ArchivedGlyphPack,
+        ArchivedFontItem,
+        ArchivedFontInstance,
         ArchivedPage,
+        ArchivedPageLabel,
+        ArchivedElementItem,
         ArchivedLayoutRegion,
         ArchivedBuildInfo,
     );